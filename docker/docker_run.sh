@@ -1,12 +1,20 @@
 #!/bin/sh
 
-# replace the vraiables in config file
-sed -i "s|db_driver|${DB_DRIVER}|g" /home/config.ini
-sed -i "s|db_host|${DB_HOST}|g" /home/config.ini
-sed -i "s|db_port|${DB_PORT}|g" /home/config.ini
-sed -i "s|db_name|${DB_NAME}|g" /home/config.ini
-sed -i "s|db_user|${DB_USER}|g" /home/config.ini
-sed -i "s|db_password|${DB_PASSWORD}|g" /home/config.ini
+# map the DB_* variables onto the config overrides understood by the api
+export IPTMNET_DEFAULT_DRIVER="${DB_DRIVER}"
+if [ "${DB_DRIVER}" = "oracle" ]; then
+    export IPTMNET_ORACLE_HOST="${DB_HOST}"
+    export IPTMNET_ORACLE_PORT="${DB_PORT}"
+    export IPTMNET_ORACLE_SERVICE_NAME="${DB_NAME}"
+    export IPTMNET_ORACLE_USER="${DB_USER}"
+    export IPTMNET_ORACLE_PASSWORD="${DB_PASSWORD}"
+else
+    export IPTMNET_POSTGRES_HOST="${DB_HOST}"
+    export IPTMNET_POSTGRES_PORT="${DB_PORT}"
+    export IPTMNET_POSTGRES_DATABASE_NAME="${DB_NAME}"
+    export IPTMNET_POSTGRES_USER="${DB_USER}"
+    export IPTMNET_POSTGRES_PASSWORD="${DB_PASSWORD}"
+fi
 
 #start the iptmnet server
 ./iptmnet_api
@@ -0,0 +1,34 @@
+import os
+import sqlite3
+import sys
+
+"""
+Build the sqlite fixture database used when running the api with driver = sqlite.
+Usage: python build_fixture.py [output_path]
+"""
+
+base_dir = os.path.dirname(os.path.abspath(__file__))
+schema_path = os.path.join(base_dir, "..", "sql", "sqlite_schema.sql")
+data_path = os.path.join(base_dir, "fixture", "fixture_data.sql")
+
+
+def build(output_path):
+    if os.path.exists(output_path):
+        os.remove(output_path)
+
+    conn = sqlite3.connect(output_path)
+    for path in [schema_path, data_path]:
+        file = open(path)
+        conn.executescript(file.read())
+        file.close()
+    conn.commit()
+    conn.close()
+
+
+if __name__ == "__main__":
+    if len(sys.argv) > 1:
+        output = sys.argv[1]
+    else:
+        output = os.path.join(base_dir, "fixture", "iptmnet.sqlite")
+    build(output)
+    print("Fixture database written to {output}".format(output=output))
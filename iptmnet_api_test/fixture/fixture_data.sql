@@ -0,0 +1,21 @@
+-- Small self-contained data set for running the API with driver = sqlite.
+-- Loaded on top of sql/sqlite_schema.sql by build_fixture.py.
+
+INSERT INTO mv_entry VALUES (1,'Q15796','uniprot_ac','SMAD2_HUMAN','Mothers against decapentaplegic homolog 2;','SMAD2','MADH2|MADR2','9606','Homo sapiens','Human','T','F','T',0,0,1,3,1,'p,ac,ub',NULL,NULL,NULL);
+INSERT INTO mv_entry VALUES (2,'PR:Q15796','pro_id',NULL,'mothers against decapentaplegic homolog 2 (human)',NULL,NULL,'9606','Homo sapiens','Human','F','F','F',0,0,0,0,0,NULL,'organism-gene','A smad2 that is encoded in the genome of human.','hSMAD2');
+INSERT INTO mv_entry VALUES (3,'O70436','uniprot_ac','SMAD2_RAT','Mothers against decapentaplegic homolog 2;','Smad2','Madh2','10116','Rattus norvegicus','Rat','T','F','F',0,0,0,6,0,'p',NULL,NULL,NULL);
+INSERT INTO mv_entry VALUES (4,'P36507','uniprot_ac','MP2K2_HUMAN','Dual specificity mitogen-activated protein kinase kinase 2;','MAP2K2','MEK2|MKK2|PRKMK2','9606','Homo sapiens','Human','T','T','F',1,1,0,2,0,'p',NULL,NULL,NULL);
+
+INSERT INTO mv_event VALUES ('Q15796','Q15796','SMAD2','K',19,'Acetylation','ac',NULL,NULL,NULL,'psp','17074756','1');
+INSERT INTO mv_event VALUES ('Q15796','Q15796','SMAD2','K',19,'Acetylation','ac',NULL,NULL,NULL,'uniprot','17074756','1');
+INSERT INTO mv_event VALUES ('Q15796','Q15796','SMAD2','S',245,'Phosphorylation','p','P36507','uniprot','MAP2K2','rlimsp','15241418','3');
+INSERT INTO mv_event VALUES ('Q15796','Q15796','SMAD2','S',465,'Phosphorylation','p','P36507','uniprot','MAP2K2','psp','11027280','2');
+
+INSERT INTO mv_proteo VALUES ('PR:000025934','SMAD2/iso:1/Phos:1','Q15796','S465,S467','Phosphorylation','PR:000025934','TGFBR1','pro','11027280');
+INSERT INTO mv_proteo VALUES ('PR:000025934','SMAD2/iso:1/Phos:1','Q15796',NULL,'Interaction','PR:Q13485','SMAD4','pro','9311995');
+
+INSERT INTO mv_efip VALUES ('Q15796','SMAD2','Q13485','SMAD4','Q15796','S',465,'Phosphorylation','psp','efip','increases_interaction','9311995');
+
+INSERT INTO sequence VALUES ('Q15796','MSSILPFTPPVVKRLLGWKKSAGGSGGAGGGEQNGQEEKWCEKAVKSLVKKLKKTGRLDELEKAITTQNCNTKCVTIPSTCSEIWGLSTPNTIDQWDTTGLYSFSEQTRSLDGRLQVSHRKGLPHVIYCRLWRWPDLHSHHELKAIENCEYAFNLKKDEVCVNPYHYQRVETPVLPPVLVPRHTEILTELPPLDDYTHSIPENTNFPAGIEPQSNYIPETPPPGYISEDGETSDQQLNQSMDTGSPAELSPTTLSPVNHSLDLQPVTYSEPAFWCSIAYYELNQRVGETFHASQPSLTVDGFTDPSNSERFCLGLLSNVNRNATVEMTRRHIGRGVRLYYIGGEVFAECLSDSAIFVQSPNCNQRYGWHPATVCKIPPGCNLKIFNNQEFAALLAQSVNQGFEAVYQLTRMCTIRMSFVKGWGAEYRRQTVTSTPCWIELHLNGPLQWLDKVLTQMGSPSVRCSSMS');
+
+INSERT INTO biomuta VALUES ('Q15796',245,'S','R','colorectal cancer','tissue','21499247');
@@ -2,6 +2,8 @@ import os
 import json
 import csv
 import io
+import models
+import requests
 
 """
 Load the json from given file name and then deserialize it into a python dict
@@ -37,3 +39,27 @@ def sanitize_substrates(substrates):
         substrate["sources"] = set(substrate["sources"].split(","))
         substrate["pmids"] = set(substrate["pmids"].split(","))
         substrate["enzymes"] = set(substrate["enzymes"].split(","))
+
+"""
+The query substrates posted by the batch tests
+"""
+def batch_substrates():
+    return [models.QuerySubstrate("Q15796", "K", "19"),
+            models.QuerySubstrate("Q15796", "T", "8"),
+            models.QuerySubstrate("P04637", "K", "120"),
+            models.QuerySubstrate("P04637", "S", "149"),
+            models.QuerySubstrate("P04637", "S", "378"),
+            models.QuerySubstrate("P04637", "S", "392"),
+            models.QuerySubstrate("P42356", "S", "199")]
+
+"""
+Posts the body as json to the url with the given Accept header and query parameters
+"""
+def post_json(url, body, accept="application/json", params=None):
+    return requests.post(url=url, data=json.dumps(body), params=params, headers={"Accept": accept})
+
+"""
+Gets the url with the given Accept header and query parameters
+"""
+def get(url, accept="application/json", params=None):
+    return requests.get(url, params=params, headers={"Accept": accept})
@@ -0,0 +1,138 @@
+import requests
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+ksea_url = "{host}/v1/analysis/ksea".format(host=host)
+
+sites = [
+    {"substrate_ac": "Q15796", "site_residue": "T", "site_position": "8", "fold_change": 1.5},
+    {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "19", "fold_change": -0.5},
+    {"substrate_ac": "Q15796", "site_residue": "S", "site_position": "465", "fold_change": 2.0},
+    {"substrate_ac": "Q15796", "site_residue": "S", "site_position": "467", "fold_change": "1.8"},
+]
+
+
+# test that every enzyme gets its statistics and the sites it was scored with
+def test_ksea():
+    result = helper.post_json(ksea_url, sites, params={"ptm_type": "all"})
+    assert result.status_code == 200, result.text
+
+    activities = json.loads(result.text)["results"]
+    assert len(activities) != 0
+    for activity in activities:
+        assert activity["num_sites"] == len(activity["sites"])
+        assert 0 <= activity["p_value"] <= 1
+        assert activity["p_value"] <= activity["fdr"] <= 1
+        for site in activity["sites"]:
+            assert site["substrate"]["uniprot_id"] == "Q15796"
+
+    p_values = [activity["p_value"] for activity in activities]
+    assert p_values == sorted(p_values)
+
+
+# test that the z-score follows the fold changes of the sites of the enzyme
+def test_ksea_z_score():
+    activities = json.loads(helper.post_json(ksea_url, sites, params={"ptm_type": "all"}).text)["results"]
+    fold_changes = [float(site["fold_change"]) for site in sites]
+    mean = sum(fold_changes) / len(fold_changes)
+    sd = (sum([(value - mean) ** 2 for value in fold_changes]) / (len(fold_changes) - 1)) ** 0.5
+
+    for activity in activities:
+        expected = (activity["mean_fold_change"] - mean) * activity["num_sites"] ** 0.5 / sd
+        assert abs(activity["z_score"] - expected) < 1e-9
+
+
+# test that the filters leave out enzymes
+def test_ksea_filters():
+    activities = json.loads(helper.post_json(ksea_url, sites, params={"ptm_type": "all", "min_sites": 100}).text)["results"]
+    assert activities == []
+
+    activities = json.loads(helper.post_json(ksea_url, sites, params={"ptm_type": "all", "min_score": 100}).text)["results"]
+    assert activities == []
+
+
+# test that a site without a fold change is a rejected row
+def test_ksea_missing_fold_change():
+    body = sites + [{"substrate_ac": "Q15796", "site_residue": "S", "site_position": "2"}]
+    result = helper.post_json(ksea_url, body)
+
+    assert result.status_code == 200, result.text
+    assert [row["index"] for row in json.loads(result.text)["rejected_rows"]] == [4]
+
+
+# test that a single site can not be scored
+def test_ksea_too_few_sites():
+    result = helper.post_json(ksea_url, sites[:1])
+
+    assert result.status_code == 422, result.text
+    assert json.loads(result.text)["code"] == "unprocessable_entity"
+
+
+# test that the csv holds one row per enzyme
+def test_ksea_csv():
+    result = helper.post_json(ksea_url, sites, accept="text/csv", params={"ptm_type": "all"})
+
+    assert result.status_code == 200, result.text
+    header = result.text.splitlines()[0].split(",")
+    assert header == ["enz_name", "enz_id", "num_sites", "mean_fold_change", "z_score", "p_value", "fdr", "sites"]
+
+
+# test that the options are checked
+def test_ksea_invalid_options():
+    for params in [{"min_sites": "0"}, {"min_score": "high"}, {"tolerance": "11"}, {"ptm_type": "phosphorilation"}]:
+        result = helper.post_json(ksea_url, sites, params=params)
+
+        assert result.status_code == 400, result.text
+        assert json.loads(result.text)["code"] == "bad_request"
+
+
+enrichment_url = "{host}/v1/analysis/ptm-enrichment".format(host=host)
+
+
+# test that every known ptm type is counted among the proteins of the list
+def test_ptm_enrichment():
+    result = helper.post_json(enrichment_url, ["Q15796", "P36507"])
+    assert result.status_code == 200, result.text
+
+    enrichments = json.loads(result.text)["results"]
+    assert len(enrichments) == 12
+    for enrichment in enrichments:
+        assert enrichment["list_size"] == 2
+        assert enrichment["count"] == len(enrichment["proteins"])
+        assert enrichment["count"] <= enrichment["background_count"] <= enrichment["background_size"]
+        assert 0 <= enrichment["p_value"] <= enrichment["fdr"] <= 1
+
+    phosphorylation = [enrichment for enrichment in enrichments if enrichment["ptm_type"] == "phosphorylation"][0]
+    assert phosphorylation["ptm_label"] == "p"
+    assert sorted(phosphorylation["proteins"]) == ["P36507", "Q15796"]
+
+
+# test that the proteins can be sent as plain text and restricted to an organism
+def test_ptm_enrichment_organism():
+    result = requests.post(url=enrichment_url, data="Q15796\nO70436", params={"organism": "9606"}, headers={"Content-Type": "text/plain", "Accept": "application/json"})
+    assert result.status_code == 200, result.text
+
+    returned = json.loads(result.text)
+    assert [row["substrate_ac"] for row in returned["rejected_rows"]] == ["O70436"]
+    assert returned["results"][0]["list_size"] == 1
+
+
+# test that unknown and repeated ids are reported
+def test_ptm_enrichment_report():
+    result = helper.post_json(enrichment_url, ["Q15796", "NOTANENTRY", "Q15796"])
+    assert result.status_code == 200, result.text
+
+    returned = json.loads(result.text)
+    assert [row["index"] for row in returned["rejected_rows"]] == [1]
+    assert [warning["index"] for warning in returned["warnings"]] == [2]
+    assert result.headers["X-Rejected-Rows"] == "1"
+
+
+# test that a list without any known substrate can not be analysed
+def test_ptm_enrichment_unknown():
+    result = helper.post_json(enrichment_url, ["NOTANENTRY"])
+
+    assert result.status_code == 422, result.text
+    assert json.loads(result.text)["code"] == "unprocessable_entity"
@@ -342,7 +342,7 @@ def test_batch_ptm_enzymes_json():
     assert result.status_code == 200, result.text
 
     # parse the returned response
-    returned_enzymes = json.loads(result.text)
+    returned_enzymes = json.loads(result.text)["results"]
 
     # assert that returned response is not empty
     assert len(returned_enzymes) is not 0 
@@ -403,7 +403,7 @@ def test_batch_ptm_ppi_json():
     assert result.status_code == 200, result.text
 
     # parse the returned response
-    returned_ptmppi = json.loads(result.text)
+    returned_ptmppi = json.loads(result.text)["results"]
 
     # assert that returned response is not empty
     assert len(returned_ptmppi) is not 0 
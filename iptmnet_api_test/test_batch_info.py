@@ -0,0 +1,45 @@
+import requests
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/v1/batch-info".format(host=host)
+
+
+# test that every info is the one returned by the info endpoint, in the order of the ids
+def test_batch_info():
+    result = helper.post_json(url, ["Q15796", "P36507"])
+    assert result.status_code == 200, result.text
+
+    infos = json.loads(result.text)["results"]
+    assert [info["uniprot_ac"] for info in infos] == ["Q15796", "P36507"]
+
+    info = json.loads(requests.get("{host}/v1/Q15796/info".format(host=host)).text)
+    assert infos[0] == info
+    assert infos[0]["pro"]["id"] == "PR:Q15796"
+
+
+# test that the unresolved ids are listed separately
+def test_unresolved_ids():
+    result = helper.post_json(url, ["Q15796", "NOTANENTRY", "PR:Q15796", "Q15796"])
+    assert result.status_code == 200, result.text
+
+    returned = json.loads(result.text)
+    assert [info["uniprot_ac"] for info in returned["results"]] == ["Q15796"]
+    assert [row["substrate_ac"] for row in returned["rejected_rows"]] == ["NOTANENTRY", "PR:Q15796"]
+    assert [warning["index"] for warning in returned["warnings"]] == [3]
+
+    result = helper.post_json(url, ["Q15796", "NOTANENTRY"], params={"strict": "true"})
+    assert result.status_code == 422, result.text
+
+
+# test that the csv holds one row per entry with the pro columns
+def test_batch_info_csv():
+    result = helper.post_json(url, ["Q15796"], accept="text/csv")
+    assert result.status_code == 200, result.text
+
+    rows = helper.load_csv_from_string(result.text)
+    assert len(rows) == 1
+    assert rows[0]["uniprot_ac"] == "Q15796"
+    assert rows[0]["pro_id"] == "PR:Q15796"
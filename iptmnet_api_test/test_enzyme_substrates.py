@@ -0,0 +1,56 @@
+import requests
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/v1/batch-enzyme-substrates".format(host=host)
+
+
+# test that every substrate site of the enzymes is returned
+def test_enzyme_substrates():
+    result = helper.post_json(url, ["P36507"])
+    assert result.status_code == 200, result.text
+
+    ptm_enzymes = json.loads(result.text)["results"]
+    assert len(ptm_enzymes) != 0
+    for ptm_enzyme in ptm_enzymes:
+        assert ptm_enzyme["enzyme"]["uniprot_id"] == "P36507"
+        assert ptm_enzyme["site"] == "{residue}{position}".format(residue=ptm_enzyme["site"][0], position=ptm_enzyme["site_position"])
+        assert "query_site" not in ptm_enzyme
+        assert "score" in ptm_enzyme
+        assert "pmids" in ptm_enzyme
+
+
+# test that the sites can be limited to a ptm type
+def test_ptm_type_filter():
+    result = helper.post_json(url, ["P36507"], params={"ptm_type": "Phosphorylation"})
+    assert result.status_code == 200, result.text
+    for ptm_enzyme in json.loads(result.text)["results"]:
+        assert ptm_enzyme["ptm_type"].lower() == "phosphorylation"
+
+    result = helper.post_json(url, ["P36507"], params={"ptm_type": "glycation"})
+    assert result.status_code == 400, result.text
+    assert json.loads(result.text)["code"] == "bad_request"
+
+
+# test that unknown and repeated enzymes are reported
+def test_report():
+    result = helper.post_json(url, ["P36507", "NOTANENTRY", "P36507"])
+    assert result.status_code == 200, result.text
+
+    returned = json.loads(result.text)
+    assert [row["index"] for row in returned["rejected_rows"]] == [1]
+    assert [warning["index"] for warning in returned["warnings"]] == [2]
+
+    result = helper.post_json(url, ["P36507", "NOTANENTRY"], params={"strict": "true"})
+    assert result.status_code == 422, result.text
+
+
+# test that the enzymes can be sent as plain text and the sites written as csv
+def test_plain_text_csv():
+    result = requests.post(url=url, data="P36507\n", headers={"Content-Type": "text/plain", "Accept": "text/csv"})
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+    assert result.text.splitlines()[0].startswith("enz_name,enz_id,sub_name,sub_id")
@@ -0,0 +1,125 @@
+import requests
+import json
+
+host = "http://localhost:8088"
+
+problem_fields = ["type", "title", "status", "code", "detail", "instance", "request_id"]
+
+
+"""
+Asserts that the response is an application/problem+json body with the given status and code
+and returns the deserialized problem
+"""
+def assert_problem(result, status, code):
+    assert result.status_code == status, result.text
+    assert result.headers["Content-Type"] == "application/problem+json", result.headers["Content-Type"]
+
+    problem = json.loads(result.text)
+    for field in problem_fields:
+        assert field in problem, field
+
+    assert problem["status"] == status
+    assert problem["code"] == code
+    assert problem["request_id"] == result.headers["X-Request-Id"]
+    return problem
+
+
+# test that a missing parameter is a bad request
+def test_missing_parameter():
+    params = {
+        "term_type": "All",
+        "role": "Enzyme or Substrate"
+    }
+    result = requests.get("{host}/search".format(host=host), params=params)
+
+    problem = assert_problem(result, 400, "bad_request")
+    assert problem["instance"] == "/search"
+    assert "search_term" in problem["detail"]
+
+
+# test that an invalid organism is a bad request
+def test_invalid_organism():
+    params = {
+        "search_term": "smad2",
+        "term_type": "All",
+        "role": "Enzyme or Substrate",
+        "organism": ["human"]
+    }
+    result = requests.get("{host}/search".format(host=host), params=params)
+
+    assert_problem(result, 400, "bad_request")
+
+
+# test that an unknown route is not found
+def test_unknown_route():
+    result = requests.get("{host}/v1/no/such/route".format(host=host))
+
+    problem = assert_problem(result, 404, "not_found")
+    assert problem["instance"] == "/v1/no/such/route"
+
+
+# test that a malformed batch body is a bad request
+def test_malformed_batch_body():
+    result = requests.post(url="{host}/batch_ptm_enzymes".format(host=host), data="[{", headers={"Accept": "application/json"})
+
+    assert_problem(result, 400, "bad_request")
+
+
+# test that a well formed batch body with the wrong shape is unprocessable
+def test_unprocessable_batch_body():
+    body_str = json.dumps({"substrate_ac": "Q15796"})
+    result = requests.post(url="{host}/batch_ptm_enzymes".format(host=host), data=body_str, headers={"Accept": "application/json"})
+
+    assert_problem(result, 422, "unprocessable_entity")
+
+
+# test that the request id sent by the client is echoed back
+def test_request_id_echoed():
+    headers = {
+        "X-Request-Id": "test-request-id"
+    }
+    result = requests.get("{host}/v1/no/such/route".format(host=host), headers=headers)
+
+    problem = assert_problem(result, 404, "not_found")
+    assert problem["request_id"] == "test-request-id"
+
+    # successful responses carry the request id too
+    result = requests.get("{host}/Q15796/info".format(host=host), headers=headers)
+    assert result.status_code == 200, result.text
+    assert result.headers["X-Request-Id"] == "test-request-id"
+
+
+# test that an unknown accession is not found and comes with close accessions
+def test_unknown_id_suggestions():
+    for endpoint in ["info", "substrate", "as-enzyme", "proteoforms", "proteoformsppi", "ptmppi", "variants"]:
+        result = requests.get("{host}/Q15797/{endpoint}".format(host=host, endpoint=endpoint))
+
+        problem = assert_problem(result, 404, "not_found")
+        suggested_acs = [suggestion["uniprot_ac"] for suggestion in problem["suggestions"]]
+        assert "Q15796" in suggested_acs, endpoint
+
+
+# test that a gene name used in place of an accession suggests the matching accessions
+def test_gene_name_suggestions():
+    result = requests.get("{host}/smad2/info".format(host=host))
+
+    problem = assert_problem(result, 404, "not_found")
+    suggested_acs = [suggestion["uniprot_ac"] for suggestion in problem["suggestions"]]
+    assert "Q15796" in suggested_acs
+    assert "O70436" in suggested_acs
+
+
+# test that an isoform of a known accession is not reported as unknown
+def test_isoform_is_known():
+    result = requests.get("{host}/Q15796-2/proteoforms".format(host=host))
+
+    assert result.status_code == 200, result.text
+
+
+# test that an isoform without an entry of its own has no info, and suggests its accession
+def test_isoform_info_not_found():
+    result = requests.get("{host}/Q15796-2/info".format(host=host))
+
+    problem = assert_problem(result, 404, "not_found")
+    suggested_acs = [suggestion["uniprot_ac"] for suggestion in problem["suggestions"]]
+    assert "Q15796" in suggested_acs
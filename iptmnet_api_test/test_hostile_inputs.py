@@ -0,0 +1,158 @@
+import requests
+import helper as helper
+import models
+import json
+from urllib.parse import quote
+
+host = "http://localhost:8088"
+
+# inputs that would break out of, or comment out the rest of, a string interpolated query
+hostile_inputs = [
+    "Q15796'",
+    "Q15796' OR '1'='1",
+    "Q15796' OR 1=1 --",
+    "Q15796'; DROP TABLE MV_ENTRY; --",
+    "Q15796'/* comment */--",
+    "Q15796\" OR \"\"=\"",
+    "%' OR '%'='",
+    "'); DELETE FROM MV_EVENT; --",
+    "Q15796';",
+    "Q15796(",
+    "[",
+]
+
+id_endpoints = [
+    "info",
+    "substrate",
+    "as-enzyme",
+    "proteoforms",
+    "proteoformsppi",
+    "ptmppi",
+    "variants",
+    "msa",
+]
+
+"""
+Returns true if the deserialized response holds no data
+"""
+def is_empty(value):
+    return value is None or len(value) == 0
+
+
+# test that hostile ids are treated as plain, unknown values by every id endpoint
+def test_hostile_ids():
+    for endpoint in id_endpoints:
+        for hostile_input in hostile_inputs:
+            url = "{host}/{id}/{endpoint}".format(host=host, id=quote(hostile_input, safe=""), endpoint=endpoint)
+
+            result = requests.get(url)
+
+            # assert that the hostile id was looked up as a plain value and not found
+            assert result.status_code == 404, "{url} - {content}".format(url=url, content=result.text)
+            assert json.loads(result.text)["code"] == "not_found", url
+
+
+# test that hostile search terms match nothing
+def test_hostile_search_terms():
+    for hostile_input in hostile_inputs:
+        params = {
+            "search_term": hostile_input,
+            "term_type": "All",
+            "role": "Enzyme or Substrate",
+            "organism": [9606]
+        }
+
+        result = requests.get('{host}/search'.format(host=host), params=params)
+
+        # assert that the request did not fail with a database error
+        assert result.status_code == 200, result.text
+
+        # assert that nothing matched the hostile search term
+        assert is_empty(json.loads(result.text)), hostile_input
+
+
+# test that hostile ptm types and organisms are rejected
+def test_hostile_search_filters():
+    for hostile_input in hostile_inputs:
+        ptm_params = {
+            "search_term": "smad2",
+            "term_type": "All",
+            "role": "Enzyme or Substrate",
+            "ptm_type": [hostile_input]
+        }
+        result = requests.get('{host}/search'.format(host=host), params=ptm_params)
+        assert result.status_code == 400, result.text
+
+        organism_params = {
+            "search_term": "smad2",
+            "term_type": "All",
+            "role": "Enzyme or Substrate",
+            "organism": [hostile_input]
+        }
+        result = requests.get('{host}/search'.format(host=host), params=organism_params)
+        assert result.status_code == 400, result.text
+
+
+# test that hostile batch substrates match nothing
+def test_hostile_batch_substrates():
+    substrates = []
+    for hostile_input in hostile_inputs:
+        substrates.append(models.QuerySubstrate(hostile_input, "K", "19"))
+        substrates.append(models.QuerySubstrate("Q15796", hostile_input, "19"))
+        substrates.append(models.QuerySubstrate("Q15796", "K", hostile_input))
+
+    body_str = json.dumps(substrates, default=lambda o: o.__dict__, indent=4, sort_keys=True)
+
+    headers = {
+        "Accept": "application/json"
+    }
+
+    for endpoint in ["batch_ptm_enzymes", "batch_ptm_ppi"]:
+        result = requests.post(url="{host}/{endpoint}".format(host=host, endpoint=endpoint), data=body_str, headers=headers)
+
+        # assert that the request did not fail with a database error
+        assert result.status_code == 200, result.text
+
+        # assert that nothing matched the hostile substrates
+        assert is_empty(json.loads(result.text)["results"]), endpoint
+
+
+# test that the data is untouched after all the hostile requests
+def test_data_intact_after_hostile_inputs():
+    test_hostile_ids()
+    test_hostile_search_terms()
+    test_hostile_batch_substrates()
+
+    result = requests.get("{host}/Q15796/info".format(host=host))
+    assert result.status_code == 200, result.content
+
+    expected_info = helper.load_json("info.json")
+    assert expected_info == json.loads(result.text)
+
+
+# test that search terms holding like wildcards are matched literally
+def test_wildcard_search_terms():
+    for search_term in ["%", "_", "SMAD_", "\\"]:
+        params = {
+            "search_term": search_term,
+            "term_type": "All",
+            "role": "Enzyme or Substrate",
+            "organism": [9606]
+        }
+
+        result = requests.get('{host}/search'.format(host=host), params=params)
+        assert result.status_code == 200, result.text
+        assert is_empty(json.loads(result.text)), search_term
+
+
+# test that ids holding like wildcards are matched literally by the endpoints looking them up with a pattern
+def test_wildcard_ids():
+    for endpoint in ["variants", "msa"]:
+        for id in ["%", "_", "Q15796%", "Q1579_", "%Q15796", "\\"]:
+            url = "{host}/{id}/{endpoint}".format(host=host, id=quote(id, safe=""), endpoint=endpoint)
+
+            result = requests.get(url)
+
+            # assert that the id was looked up as a plain value and not found
+            assert result.status_code == 404, "{url} - {content}".format(url=url, content=result.text)
+            assert json.loads(result.text)["code"] == "not_found", url
@@ -0,0 +1,78 @@
+import requests
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/v1/id-mapping".format(host=host)
+
+
+"""
+Maps a single token and returns its mapping
+"""
+def map_token(token, params=None):
+    result = helper.post_json(url, [token], params=params)
+    assert result.status_code == 200, result.text
+    return json.loads(result.text)[0]
+
+
+# test that accessions and pro ids are matched exactly, case insensitively
+def test_accession_and_pro():
+    mapping = map_token("q15796")
+    assert mapping["status"] == "mapped"
+    assert mapping["match_type"] == "accession"
+    assert [entry["iptm_entry_code"] for entry in mapping["entries"]] == ["Q15796"]
+
+    mapping = map_token("PR:Q15796")
+    assert mapping["status"] == "mapped"
+    assert mapping["match_type"] == "pro"
+
+
+# test that a gene name shared by several organisms is reported as ambiguous
+def test_ambiguous_gene_name():
+    mapping = map_token("SMAD2")
+    assert mapping["status"] == "ambiguous"
+    assert mapping["match_type"] == "gene_name"
+
+    entry_codes = [entry["iptm_entry_code"] for entry in mapping["entries"]]
+    assert "Q15796" in entry_codes
+    assert "O70436" in entry_codes
+    assert entry_codes == sorted(entry_codes)
+
+
+# test that the organism resolves an ambiguous gene name
+def test_organism():
+    mapping = map_token("SMAD2", params={"organism": "9606"})
+    assert mapping["status"] == "mapped"
+    assert mapping["entries"][0]["iptm_entry_code"] == "Q15796"
+    assert mapping["entries"][0]["organism"]["taxon_code"] == "9606"
+
+
+# test that a synonym is matched only when nothing else matches
+def test_synonym():
+    mapping = map_token("MADR2")
+    assert mapping["status"] == "mapped"
+    assert mapping["match_type"] == "synonym"
+    assert mapping["entries"][0]["iptm_entry_code"] == "Q15796"
+
+
+# test that every token gets a mapping in the order of the list
+def test_order_and_unmapped():
+    result = helper.post_json(url, ["NOTAGENE", "Q15796", "Q15796"])
+    assert result.status_code == 200, result.text
+
+    mappings = json.loads(result.text)
+    assert [mapping["input"] for mapping in mappings] == ["NOTAGENE", "Q15796", "Q15796"]
+    assert mappings[0]["status"] == "unmapped"
+    assert mappings[0]["match_type"] is None
+    assert mappings[0]["entries"] == []
+
+
+# test that the csv holds one row per token
+def test_id_mapping_csv():
+    result = requests.post(url=url, data="SMAD2 MADR2", headers={"Content-Type": "text/plain", "Accept": "text/csv"})
+    assert result.status_code == 200, result.text
+
+    rows = helper.load_csv_from_string(result.text)
+    assert [row["input"] for row in rows] == ["SMAD2", "MADR2"]
+    assert rows[1]["iptm_entry_codes"] == "Q15796"
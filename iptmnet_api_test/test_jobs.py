@@ -0,0 +1,108 @@
+import requests
+import helper as helper
+import json
+import csv
+import io
+import time
+
+host = "http://localhost:8088"
+
+substrates = helper.batch_substrates()
+
+body_str = json.dumps(substrates, default=lambda o: o.__dict__, indent=4, sort_keys=True)
+
+
+"""
+Submits a job of the given kind and waits until it is finished, returning the finished job
+"""
+def run_job(kind, body=body_str, params=None):
+    result = requests.post(url="{host}/v1/jobs/{kind}".format(host=host, kind=kind), data=body, params=params)
+    assert result.status_code == 202, result.text
+
+    job = json.loads(result.text)
+    assert job["kind"] == kind
+    assert job["progress"]["total"] == len(json.loads(body))
+    assert result.headers["Location"] == "/v1/jobs/{id}".format(id=job["id"])
+
+    for _ in range(100):
+        result = requests.get("{host}/v1/jobs/{id}".format(host=host, id=job["id"]))
+        assert result.status_code == 200, result.text
+        job = json.loads(result.text)
+        if job["status"] not in ["queued", "running"]:
+            return job
+        time.sleep(0.1)
+
+    assert False, "job {id} did not finish".format(id=job["id"])
+
+
+# test that a ptm enzymes job gives the same result as the synchronous endpoint
+def test_ptm_enzymes_job():
+    job = run_job("batch-ptm-enzymes")
+    assert job["status"] == "completed", job
+    assert job["progress"]["processed"] == len(substrates)
+    assert job["expires"] is not None
+
+    result = requests.get("{host}/v1/jobs/{id}/result".format(host=host, id=job["id"]))
+    assert result.status_code == 200, result.text
+
+    expected = requests.post(url="{host}/batch_ptm_enzymes".format(host=host), data=body_str, headers={"Accept": "application/json"})
+    assert json.loads(result.text) == json.loads(expected.text)
+
+
+# test that a ptm ppi job result can be fetched as csv
+def test_ptm_ppi_job_csv():
+    job = run_job("batch-ptm-ppi")
+    assert job["status"] == "completed", job
+
+    result = requests.get("{host}/v1/jobs/{id}/result".format(host=host, id=job["id"]), headers={"Accept": "text/csv"})
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+
+    expected = requests.post(url="{host}/batch_ptm_ppi".format(host=host), data=body_str, headers={"Accept": "application/json"})
+    returned_rows = list(csv.DictReader(io.StringIO(result.text)))
+    assert len(returned_rows) == len(json.loads(expected.text)["results"])
+
+
+# test that deleting a finished job removes it along with its result
+def test_delete_finished_job():
+    job = run_job("batch-ptm-enzymes")
+
+    result = requests.delete("{host}/v1/jobs/{id}".format(host=host, id=job["id"]))
+    assert result.status_code == 200, result.text
+
+    result = requests.get("{host}/v1/jobs/{id}".format(host=host, id=job["id"]))
+    assert result.status_code == 404, result.text
+    result = requests.get("{host}/v1/jobs/{id}/result".format(host=host, id=job["id"]))
+    assert result.status_code == 404, result.text
+
+
+# test that an unknown job is not found
+def test_unknown_job():
+    result = requests.get("{host}/v1/jobs/0123456789abcdef".format(host=host))
+
+    assert result.status_code == 404, result.text
+    assert json.loads(result.text)["code"] == "not_found"
+
+
+# test that a malformed submission is rejected before it is queued
+def test_malformed_submission():
+    result = requests.post(url="{host}/v1/jobs/batch-ptm-enzymes".format(host=host), data="[{")
+
+    assert result.status_code == 400, result.text
+    assert json.loads(result.text)["code"] == "bad_request"
+
+
+# test that a job keeps the validation report of its rows, and fails on rejected rows in strict mode
+def test_job_report():
+    body = json.dumps([
+        {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "19"},
+        {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "nineteen"},
+    ])
+
+    job = run_job("batch-ptm-enzymes", body=body)
+    assert job["status"] == "completed", job
+    assert [row["index"] for row in job["report"]["rejected_rows"]] == [1]
+
+    job = run_job("batch-ptm-enzymes", body=body, params={"strict": "true"})
+    assert job["status"] == "failed", job
+    assert [row["index"] for row in job["report"]["rejected_rows"]] == [1]
@@ -0,0 +1,147 @@
+import requests
+import helper as helper
+import json
+import csv
+import io
+
+host = "http://localhost:8088"
+
+url = "{host}/Q15796/proteoforms".format(host=host)
+
+
+"""
+Requests the proteoforms with the given Accept header and format parameter
+"""
+def get_proteoforms(accept=None, format=None):
+    headers = {}
+    if accept is not None:
+        headers["Accept"] = accept
+
+    params = {}
+    if format is not None:
+        params["format"] = format
+
+    return requests.get(url, headers=headers, params=params)
+
+
+# test that a request without an Accept header gets json
+def test_default_is_json():
+    result = get_proteoforms()
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "application/json"
+    assert helper.load_json("proteoforms.json") == json.loads(result.text)
+
+
+# test that a browser style Accept header gets json
+def test_wildcard_is_json():
+    result = get_proteoforms(accept="text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "application/json"
+
+
+# test that text/csv is a first class type
+def test_csv():
+    result = get_proteoforms(accept="text/csv")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+
+    returned_proteoforms = helper.load_csv_from_string(result.text)
+    expected_proteoforms = helper.load_csv_from_file("proteoforms.csv")
+    for index, proteoform in enumerate(expected_proteoforms):
+        assert proteoform in returned_proteoforms, "Item at index: {index} not found".format(index=index)
+
+
+# test that text/plain is still answered with csv
+def test_text_plain_is_csv():
+    result = get_proteoforms(accept="text/plain")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+
+
+# test that tab separated values use tabs as delimiter
+def test_tsv():
+    result = get_proteoforms(accept="text/tab-separated-values")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/tab-separated-values"
+
+    returned_proteoforms = list(csv.DictReader(io.StringIO(result.text), delimiter="\t"))
+    expected_proteoforms = helper.load_csv_from_file("proteoforms.csv")
+    assert len(returned_proteoforms) == len(expected_proteoforms)
+
+
+# test that the type with the highest quality wins
+def test_quality_values():
+    result = get_proteoforms(accept="application/json;q=0.5, text/csv;q=0.9, text/tab-separated-values;q=0.1")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+
+
+# test that a type with quality 0 is never picked
+def test_quality_zero_excludes():
+    result = get_proteoforms(accept="application/json;q=0, */*;q=0.5")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+
+
+# test that the format parameter overrides the Accept header
+def test_format_override():
+    result = get_proteoforms(accept="application/json", format="tsv")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/tab-separated-values"
+
+
+# test that an unsupported Accept header is not acceptable
+def test_not_acceptable():
+    result = get_proteoforms(accept="application/xml")
+
+    assert result.status_code == 406, result.text
+    assert result.headers["Content-Type"] == "application/problem+json"
+    assert json.loads(result.text)["code"] == "not_acceptable"
+
+
+# test that an unknown format is a bad request
+def test_unknown_format():
+    result = get_proteoforms(format="xml")
+
+    assert result.status_code == 400, result.text
+    assert json.loads(result.text)["code"] == "bad_request"
+
+
+# test that ndjson holds one item of the json response per line
+def test_ndjson():
+    result = get_proteoforms(accept="application/x-ndjson")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "application/x-ndjson"
+
+    returned_proteoforms = [json.loads(line) for line in result.text.splitlines()]
+    assert helper.load_json("proteoforms.json") == returned_proteoforms
+
+
+# test that the info of an entry is negotiated like the lists
+def test_info_formats():
+    result = requests.get("{host}/Q15796/info".format(host=host), params={"format": "csv"})
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "text/csv"
+    rows = helper.load_csv_from_string(result.text)
+    assert [row["uniprot_ac"] for row in rows] == ["Q15796"]
+
+    result = requests.get("{host}/Q15796/info".format(host=host), headers={"Accept": "image/png"})
+    assert result.status_code == 406, result.text
+
+
+# test that the alignment, which is json only, refuses the other formats
+def test_msa_is_json_only():
+    result = requests.get("{host}/Q15796/msa".format(host=host), params={"format": "csv"})
+    assert result.status_code == 406, result.text
+
+    result = requests.get("{host}/Q15796/msa".format(host=host), headers={"Accept": "text/csv"})
+    assert result.status_code == 406, result.text
@@ -0,0 +1,125 @@
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/v1/network".format(host=host)
+
+
+def edges_of(network, edge_type):
+    return [edge["data"] for edge in network["elements"]["edges"] if edge["data"]["edge_type"] == edge_type]
+
+
+# test that the enzymes and ppi interactants of the seed are reached at depth 1
+def test_cytoscape_network():
+    result = helper.get(url, params={"seed": "Q15796"})
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"].startswith("application/json")
+
+    network = json.loads(result.text)
+    nodes = {node["data"]["id"]: node["data"] for node in network["elements"]["nodes"]}
+    assert sorted(nodes.keys()) == ["P36507", "Q13485", "Q15796"]
+    assert nodes["Q15796"]["seed"] is True
+    assert nodes["Q15796"]["depth"] == 0
+    assert nodes["P36507"]["seed"] is False
+    assert nodes["P36507"]["depth"] == 1
+    assert nodes["P36507"]["name"] == "MAP2K2"
+
+    enzyme_edges = edges_of(network, "enzyme_substrate")
+    assert sorted(edge["site"] for edge in enzyme_edges) == ["S245", "S465"]
+    for edge in enzyme_edges:
+        assert edge["source"] == "P36507"
+        assert edge["target"] == "Q15796"
+        assert edge["interaction"] == "phosphorylation"
+        assert edge["score"] is not None
+
+    ppi_edges = edges_of(network, "ptm_ppi")
+    assert len(ppi_edges) == 1
+    assert ppi_edges[0]["source"] == "Q15796"
+    assert ppi_edges[0]["target"] == "Q13485"
+    assert ppi_edges[0]["site"] == "S465"
+    assert ppi_edges[0]["interaction"] == "increases_interaction"
+    assert ppi_edges[0]["pmids"] == ["9311995"]
+
+
+# test that the ptm_type filter applies to both kinds of edges
+def test_ptm_type_filter():
+    network = json.loads(helper.get(url, params={"seed": "Q15796", "ptm_type": "acetylation"}).text)
+
+    assert network["elements"]["edges"] == []
+    assert [node["data"]["id"] for node in network["elements"]["nodes"]] == ["Q15796"]
+
+
+# test that min_score leaves out the enzyme-substrate edges only
+def test_min_score():
+    network = json.loads(helper.get(url, params={"seed": "Q15796", "min_score": "1000"}).text)
+
+    assert edges_of(network, "enzyme_substrate") == []
+    assert len(edges_of(network, "ptm_ppi")) == 1
+
+
+# test that the same network is exported as sif lines
+def test_sif():
+    result = helper.get(url, params={"seed": "Q15796", "format": "sif"})
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"].startswith("text/plain")
+
+    lines = sorted(result.text.strip().split("\n"))
+    assert lines == ["P36507\tphosphorylation\tQ15796",
+                     "P36507\tphosphorylation\tQ15796",
+                     "Q15796\tincreases_interaction\tQ13485"]
+
+
+# test that a seed without edges is written alone in sif
+def test_sif_isolated_seed():
+    result = helper.get(url, params={"seed": "Q15796", "ptm_type": "acetylation"}, accept="text/x-sif")
+    assert result.status_code == 200, result.text
+    assert result.text.strip() == "Q15796"
+
+
+# test that the network is exported as graphml with its edge attributes
+def test_graphml():
+    result = helper.get(url, params={"seed": "Q15796"}, accept="application/graphml+xml")
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"].startswith("application/graphml+xml")
+
+    assert "<graphml" in result.text
+    assert '<node id="P36507">' in result.text
+    assert '<data key="pmids">9311995</data>' in result.text
+    assert 'directed="false"' in result.text
+
+
+# test that a comma separated list of seeds is read like repeated seeds
+def test_seed_list():
+    result = helper.get(url, params={"seed": "Q15796,P36507"})
+    assert result.status_code == 200, result.text
+
+    nodes = json.loads(result.text)["elements"]["nodes"]
+    assert sorted(node["data"]["id"] for node in nodes if node["data"]["seed"]) == ["P36507", "Q15796"]
+
+
+# test that a seed is read case insensitively and joins the node of its accession
+def test_lower_case_seed():
+    result = helper.get(url, params={"seed": "q15796,Q15796"})
+    assert result.status_code == 200, result.text
+
+    nodes = json.loads(result.text)["elements"]["nodes"]
+    assert sorted(node["data"]["id"] for node in nodes) == ["P36507", "Q13485", "Q15796"]
+
+
+# test that a missing seed and an out of range depth are bad requests
+def test_bad_requests():
+    assert helper.get(url, params={}).status_code == 400
+    assert helper.get(url, params={"seed": "Q15796", "depth": "0"}).status_code == 400
+    assert helper.get(url, params={"seed": "Q15796", "depth": "4"}).status_code == 400
+    assert helper.get(url, params={"seed": "Q15796", "ptm_type": "unknown"}).status_code == 400
+
+
+# test that an unknown seed is not found
+def test_unknown_seed():
+    assert helper.get(url, params={"seed": "Q99999"}).status_code == 404
+
+
+# test that an unsupported export format is not acceptable
+def test_not_acceptable():
+    assert helper.get(url, params={"seed": "Q15796"}, accept="text/csv").status_code == 406
@@ -0,0 +1,98 @@
+import requests
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/v1/pmid/{pmid}"
+
+batch_url = "{host}/v1/batch-pmid".format(host=host)
+
+
+# test that the events and enzyme-substrate relations citing a pmid are grouped apart
+def test_events_and_enzyme_substrates():
+    result = helper.get(url.format(host=host, pmid="11027280"))
+    assert result.status_code == 200, result.text
+
+    publication = json.loads(result.text)
+    assert publication["pmid"] == "11027280"
+    assert [event["site"] for event in publication["ptm_events"]] == ["S465"]
+    assert publication["ptm_events"][0]["substrate"]["uniprot_id"] == "Q15796"
+
+    enzyme_substrates = publication["enzyme_substrates"]
+    assert [relation["enzyme"]["uniprot_id"] for relation in enzyme_substrates] == ["P36507"]
+    assert enzyme_substrates[0]["site"] == "S465"
+
+    assert [proteoform["pro_id"] for proteoform in publication["proteoforms"]] == ["PR:000025934"]
+    assert publication["ptm_ppis"] == []
+
+
+# test that an event without enzyme is an event only, with the sources of all its rows
+def test_event_without_enzyme():
+    publication = json.loads(helper.get(url.format(host=host, pmid="17074756")).text)
+
+    assert [event["site"] for event in publication["ptm_events"]] == ["K19"]
+    assert len(publication["ptm_events"][0]["sources"]) == 2
+    assert publication["enzyme_substrates"] == []
+
+
+# test that the ppis citing a pmid are found
+def test_ppis():
+    publication = json.loads(helper.get(url.format(host=host, pmid="9311995")).text)
+
+    assert [ptm_ppi["interactant"]["uniprot_id"] for ptm_ppi in publication["ptm_ppis"]] == ["Q13485"]
+    assert [ppi["protein_2"]["pro_id"] for ppi in publication["proteoform_ppis"]] == ["PR:Q13485"]
+    assert publication["ptm_events"] == []
+
+
+# test that the csv holds one row for every record with its type
+def test_pmid_csv():
+    result = helper.get(url.format(host=host, pmid="11027280"), accept="text/csv")
+    assert result.status_code == 200, result.text
+
+    rows = helper.load_csv_from_string(result.text)
+    assert sorted(row["record_type"] for row in rows) == ["enzyme_substrate", "proteoform", "ptm_event"]
+    assert all(row["pmid"] == "11027280" for row in rows)
+
+
+# test that malformed and uncited pmids are told apart
+def test_unknown_pmids():
+    result = helper.get(url.format(host=host, pmid="abc"))
+    assert result.status_code == 400, result.text
+    assert json.loads(result.text)["code"] == "bad_request"
+
+    result = helper.get(url.format(host=host, pmid="1"))
+    assert result.status_code == 404, result.text
+    assert json.loads(result.text)["code"] == "not_found"
+
+
+# test that the batch form keeps the order of the pmids and reports the bad ones
+def test_batch():
+    body = ["9311995", "11027280", "abc", "9311995", "1"]
+    result = helper.post_json(batch_url, body)
+    assert result.status_code == 200, result.text
+
+    returned = json.loads(result.text)
+    assert [publication["pmid"] for publication in returned["results"]] == ["9311995", "11027280", "1"]
+    assert [row["index"] for row in returned["rejected_rows"]] == [2]
+    assert [warning["index"] for warning in returned["warnings"]] == [3]
+    assert returned["results"][2]["ptm_events"] == []
+
+
+# test that the batch form reads plain text and writes the rows of every publication
+def test_batch_csv():
+    result = requests.post(url=batch_url, data="11027280\n9311995", headers={"Content-Type": "text/plain", "Accept": "text/csv"})
+    assert result.status_code == 200, result.text
+    assert result.headers["X-Rejected-Rows"] == "0"
+
+    rows = helper.load_csv_from_string(result.text)
+    assert [row["pmid"] for row in rows if row["record_type"] == "ptm_ppi"] == ["9311995"]
+
+
+# test that strict mode rejects a batch with a malformed pmid
+def test_batch_strict():
+    body = ["11027280", "PMID:9311995"]
+    result = helper.post_json(batch_url, body, params={"strict": "true"})
+
+    assert result.status_code == 422, result.text
+    assert [row["index"] for row in json.loads(result.text)["rejected_rows"]] == [1]
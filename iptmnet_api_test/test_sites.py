@@ -0,0 +1,94 @@
+import helper as helper
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/v1/{id}/sites/{site}"
+
+
+# test that the site record gathers the events, ptm ppis and proteoforms of the site
+def test_site_record():
+    result = helper.get(url.format(host=host, id="Q15796", site="S465"))
+    assert result.status_code == 200, result.text
+
+    site_record = json.loads(result.text)
+    assert site_record["uniprot_ac"] == "Q15796"
+    assert site_record["site"] == "S465"
+    assert site_record["position"] == 465
+    assert site_record["site_check"]["status"] == "match"
+    assert site_record["ptm_types"] == ["Phosphorylation"]
+    assert [enzyme["id"] for enzyme in site_record["enzymes"]] == ["P36507"]
+
+    assert len(site_record["events"]) > 0
+    assert all(event["site"] == "S465" for event in site_record["events"])
+
+    assert [ptm_ppi["interactant"]["uniprot_id"] for ptm_ppi in site_record["ptm_ppis"]] == ["Q13485"]
+    assert [proteoform["pro_id"] for proteoform in site_record["proteoforms"]] == ["PR:000025934"]
+    assert site_record["variants"] == []
+
+
+# test that the variants are looked for around the site
+def test_variant_window():
+    site_record = json.loads(helper.get(url.format(host=host, id="Q15796", site="S245")).text)
+    assert [variant["position"] for variant in site_record["variants"]] == [245]
+
+    site_record = json.loads(helper.get(url.format(host=host, id="Q15796", site="S242")).text)
+    assert [variant["position"] for variant in site_record["variants"]] == [245]
+
+    site_record = json.loads(helper.get(url.format(host=host, id="Q15796", site="S242"), params={"variant_window": "0"}).text)
+    assert site_record["variants"] == []
+
+
+# test that the residue is read case insensitively
+def test_lower_case_residue():
+    result = helper.get(url.format(host=host, id="Q15796", site="k19"))
+    assert result.status_code == 200, result.text
+
+    site_record = json.loads(result.text)
+    assert site_record["site"] == "K19"
+    assert site_record["ptm_types"] == ["Acetylation"]
+    assert site_record["enzymes"] == []
+
+
+# test that a site nothing is reported at gives an empty record with its site check
+def test_empty_site():
+    result = helper.get(url.format(host=host, id="Q15796", site="S19"))
+    assert result.status_code == 200, result.text
+
+    site_record = json.loads(result.text)
+    assert site_record["events"] == []
+    assert site_record["score"] is None
+    assert site_record["site_check"]["status"] == "mismatch"
+    assert site_record["site_check"]["sequence_residue"] == "K"
+
+
+# test that the csv holds the record as a single row
+def test_site_csv():
+    result = helper.get(url.format(host=host, id="Q15796", site="S465"), accept="text/csv")
+    assert result.status_code == 200, result.text
+
+    rows = helper.load_csv_from_string(result.text)
+    assert len(rows) == 1
+    assert rows[0]["site"] == "S465"
+    assert rows[0]["site_status"] == "match"
+    assert rows[0]["enzymes"] == "MAP2K2"
+    assert rows[0]["proteoforms"] == "PR:000025934"
+    assert "9311995" in rows[0]["pmids"].split(",")
+
+
+# test that malformed sites and windows are bad requests
+def test_bad_requests():
+    for site in ["465", "S", "SS465", "S0", "S-1"]:
+        result = helper.get(url.format(host=host, id="Q15796", site=site))
+        assert result.status_code == 400, site
+        assert json.loads(result.text)["code"] == "bad_request", site
+
+    result = helper.get(url.format(host=host, id="Q15796", site="S465"), params={"variant_window": "51"})
+    assert result.status_code == 400, result.text
+
+
+# test that an unknown entry is not found
+def test_unknown_entry():
+    result = helper.get(url.format(host=host, id="NOTANENTRY", site="S465"))
+    assert result.status_code == 404, result.text
+    assert json.loads(result.text)["code"] == "not_found"
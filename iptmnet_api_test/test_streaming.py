@@ -0,0 +1,90 @@
+import requests
+import helper as helper
+import json
+import csv
+import io
+
+host = "http://localhost:8088"
+
+substrates = helper.batch_substrates()
+
+browse_params = {
+    "term_type": "All",
+    "role": "Enzyme or Substrate",
+    "start_index": "0",
+    "end_index": "120",
+}
+
+
+"""
+Posts the substrates to the batch ptm enzymes endpoint with the given Accept header
+"""
+def post_batch_ptm_enzymes(accept):
+    body_str = json.dumps(substrates, default=lambda o: o.__dict__, indent=4, sort_keys=True)
+    url = "{host}/batch_ptm_enzymes".format(host=host)
+    return requests.post(url=url, data=body_str, headers={"Accept": accept}, stream=True)
+
+
+# test that the batch ptm enzymes are sent as a chunked response
+def test_batch_ptm_enzymes_chunked():
+    result = post_batch_ptm_enzymes("application/json")
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Transfer-Encoding"] == "chunked"
+    assert len(json.loads(result.text)["results"]) != 0
+
+
+# test that the streamed ndjson holds the items of the streamed json
+def test_batch_ptm_enzymes_ndjson():
+    expected_enzymes = json.loads(post_batch_ptm_enzymes("application/json").text)["results"]
+
+    result = post_batch_ptm_enzymes("application/x-ndjson")
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "application/x-ndjson"
+
+    returned_enzymes = [json.loads(line) for line in result.iter_lines() if line]
+    assert returned_enzymes == expected_enzymes
+
+
+# test that the streamed csv has one row per item of the streamed json
+def test_batch_ptm_enzymes_csv_rows():
+    expected_enzymes = json.loads(post_batch_ptm_enzymes("application/json").text)["results"]
+
+    result = post_batch_ptm_enzymes("text/csv")
+    assert result.status_code == 200, result.text
+
+    returned_rows = list(csv.DictReader(io.StringIO(result.text)))
+    assert len(returned_rows) == len(expected_enzymes)
+
+
+# test that a streamed browse still sends the total count
+def test_browse_ndjson():
+    params = dict(browse_params, format="ndjson")
+    result = requests.get("{host}/browse".format(host=host), params=params, stream=True)
+
+    assert result.status_code == 200, result.text
+    assert result.headers["Content-Type"] == "application/x-ndjson"
+    assert int(result.headers["count"]) >= 0
+
+    returned_results = [json.loads(line) for line in result.iter_lines() if line]
+    assert len(returned_results) <= 120
+
+
+# test that an empty streamed json response is still an array
+def test_browse_empty_json():
+    params = dict(browse_params, organism=["1"])
+    result = requests.get("{host}/browse".format(host=host), params=params)
+
+    assert result.status_code == 200, result.text
+    assert json.loads(result.text) == []
+
+
+# test that an empty streamed csv response still has its header row
+def test_browse_empty_csv():
+    params = dict(browse_params, organism=["1"], format="csv")
+    result = requests.get("{host}/browse".format(host=host), params=params)
+
+    assert result.status_code == 200, result.text
+    lines = result.text.splitlines()
+    assert len(lines) == 1
+    assert lines[0].startswith("iptm_id,protein_name,gene_name")
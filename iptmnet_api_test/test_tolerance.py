@@ -0,0 +1,62 @@
+import requests
+import json
+
+host = "http://localhost:8088"
+
+# Q15796 is acetylated at K19, two residues past the query site
+body = json.dumps([{"substrate_ac": "Q15796", "site_residue": "K", "site_position": "17"}])
+
+
+"""
+Posts the body to the given endpoint with the given tolerance and returns the response
+"""
+def post_with_tolerance(endpoint, tolerance=None):
+    params = {}
+    if tolerance is not None:
+        params["tolerance"] = tolerance
+    url = "{host}/{endpoint}".format(host=host, endpoint=endpoint)
+    return requests.post(url=url, data=body, params=params, headers={"Accept": "application/json"})
+
+
+# test that sites are matched exactly without a tolerance
+def test_exact_by_default():
+    result = post_with_tolerance("batch_ptm_enzymes")
+
+    assert result.status_code == 200, result.text
+    for enzyme in json.loads(result.text)["results"]:
+        assert enzyme["offset"] == 0
+        assert enzyme["site"] == enzyme["query_site"]
+
+
+# test that the sites within the tolerance are matched and report their offset
+def test_tolerance_offsets():
+    result = post_with_tolerance("batch_ptm_enzymes", tolerance=2)
+    assert result.status_code == 200, result.text
+
+    enzymes = json.loads(result.text)["results"]
+    assert len(enzymes) != 0
+    for enzyme in enzymes:
+        assert enzyme["query_site"] == "K17"
+        assert enzyme["site_position"] - 17 == enzyme["offset"]
+        assert abs(enzyme["offset"]) <= 2
+    assert 2 in [enzyme["offset"] for enzyme in enzymes]
+
+
+# test that the offset is a column of the csv
+def test_tolerance_csv():
+    result = requests.post(url="{host}/batch_ptm_enzymes".format(host=host), data=body, params={"tolerance": 2}, headers={"Accept": "text/csv"})
+
+    assert result.status_code == 200, result.text
+    header = result.text.splitlines()[0].split(",")
+    assert "query_site" in header
+    assert "offset" in header
+
+
+# test that the tolerance must be a small positive integer
+def test_invalid_tolerance():
+    for tolerance in ["-1", "11", "two"]:
+        for endpoint in ["batch_ptm_enzymes", "batch_ptm_ppi"]:
+            result = post_with_tolerance(endpoint, tolerance=tolerance)
+
+            assert result.status_code == 400, result.text
+            assert json.loads(result.text)["code"] == "bad_request"
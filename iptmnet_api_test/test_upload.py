@@ -0,0 +1,98 @@
+import requests
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/batch_ptm_enzymes".format(host=host)
+
+json_body = json.dumps([
+    {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "19"},
+    {"substrate_ac": "Q15796", "site_residue": "T", "site_position": "8"},
+    {"substrate_ac": "P04637", "site_residue": "S", "site_position": "392"},
+])
+
+
+"""
+Posts the body with the given content type and returns the returned enzymes
+"""
+def post_enzymes(body, content_type=None, params=None, files=None, data=None):
+    headers = {"Accept": "application/json"}
+    if content_type is not None:
+        headers["Content-Type"] = content_type
+
+    if files is not None:
+        result = requests.post(url=url, files=files, data=data, params=params, headers=headers)
+    else:
+        result = requests.post(url=url, data=body, params=params, headers=headers)
+
+    assert result.status_code == 200, result.text
+    return json.loads(result.text)["results"]
+
+
+def expected_enzymes():
+    return post_enzymes(json_body, content_type="application/json")
+
+
+# test that csv with the field names as headers gives the same enzymes as json
+def test_csv():
+    body = "substrate_ac,site_residue,site_position\nQ15796,K,19\nQ15796,T,8\nP04637,S,392\n"
+    assert post_enzymes(body, content_type="text/csv") == expected_enzymes()
+
+
+# test that tsv with other known headers and extra columns is read
+def test_tsv_known_headers():
+    body = "Accession\tResidue\tPosition\tIntensity\nQ15796\tK\t19\t1.0\nQ15796\tT\t8\t2.0\nP04637\tS\t392\t3.0\n"
+    assert post_enzymes(body, content_type="text/tab-separated-values") == expected_enzymes()
+
+
+# test that the columns can be named with query parameters, with the site in a single column
+def test_csv_column_mapping():
+    body = "Protein ID,Phosphosite\nQ15796,K19\nQ15796,T8\nP04637,S392\n"
+    params = {"substrate_ac": "Protein ID", "site": "Phosphosite"}
+    assert post_enzymes(body, content_type="text/csv", params=params) == expected_enzymes()
+
+
+# test the compact line format
+def test_lines():
+    body = "# comment\nQ15796 K19\nQ15796\tT 8\n\nP04637,S392\n"
+    assert post_enzymes(body, content_type="text/plain") == expected_enzymes()
+
+
+# test a multipart file upload with the columns named in form fields
+def test_multipart():
+    body = "Protein,Phosphosite\nQ15796,K19\nQ15796,T8\nP04637,S392\n"
+    files = {"file": ("sites.csv", body)}
+    data = {"site": "Phosphosite"}
+    assert post_enzymes(None, files=files, data=data) == expected_enzymes()
+
+
+# test that an unreadable line is rejected while the other lines are looked up
+def test_bad_line():
+    result = requests.post(url=url, data="Q15796 K19\nQ15796\n", headers={"Content-Type": "text/plain"})
+
+    assert result.status_code == 200, result.text
+    rejected_rows = json.loads(result.text)["rejected_rows"]
+    assert len(rejected_rows) == 1
+    assert rejected_rows[0]["index"] == 1
+    assert rejected_rows[0]["substrate_ac"] == "Q15796"
+
+
+# test that a csv without the needed columns is a bad request
+def test_missing_columns():
+    result = requests.post(url=url, data="a,b\n1,2\n", headers={"Content-Type": "text/csv"})
+
+    assert result.status_code == 400, result.text
+
+
+# test that json sent with another content type, like curl's default, or as text is read as json
+def test_json_with_other_content_types():
+    for content_type in ["application/x-www-form-urlencoded", "text/plain", "text/plain; charset=utf-8"]:
+        assert post_enzymes(json_body, content_type=content_type) == expected_enzymes(), content_type
+
+
+# test that a body of an unknown content type that is not json is a bad request
+def test_unknown_content_type():
+    result = requests.post(url=url, data="<sites/>", headers={"Content-Type": "application/xml"})
+
+    assert result.status_code == 400, result.text
+    assert json.loads(result.text)["code"] == "bad_request"
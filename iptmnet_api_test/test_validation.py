@@ -0,0 +1,102 @@
+import requests
+import json
+
+host = "http://localhost:8088"
+
+url = "{host}/batch_ptm_enzymes".format(host=host)
+
+# one valid row followed by one row for every reason a row is rejected or warned about
+rows = [
+    {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "19"},
+    {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "nineteen"},
+    {"substrate_ac": "Q15796", "site_residue": "KK", "site_position": "19"},
+    {"substrate_ac": "NOTANENTRY", "site_residue": "S", "site_position": "15"},
+    {"substrate_ac": "Q15796", "site_residue": "K"},
+    {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "19"},
+    {"substrate_ac": "Q15796", "site_residue": "N", "site_position": "20"},
+]
+
+
+"""
+Posts the rows to the given endpoint with the given Accept header and query parameters
+"""
+def post_rows(endpoint="batch_ptm_enzymes", accept="application/json", params=None):
+    url = "{host}/{endpoint}".format(host=host, endpoint=endpoint)
+    return requests.post(url=url, data=json.dumps(rows), params=params, headers={"Accept": accept})
+
+
+# test that the bad rows are reported with their index while the valid rows are looked up
+def test_report():
+    for endpoint in ["batch_ptm_enzymes", "batch_ptm_ppi"]:
+        result = post_rows(endpoint)
+        assert result.status_code == 200, result.text
+
+        returned = json.loads(result.text)
+        assert [row["index"] for row in returned["rejected_rows"]] == [1, 2, 3, 4], endpoint
+        assert [warning["index"] for warning in returned["warnings"]] == [5, 6], endpoint
+        assert "results" in returned, endpoint
+
+
+# test that the reasons name the problem of every rejected row
+def test_reasons():
+    returned = json.loads(post_rows().text)
+    reasons = [row["reason"] for row in returned["rejected_rows"]]
+
+    assert "site_position" in reasons[0]
+    assert "site_residue" in reasons[1]
+    assert "NOTANENTRY" in reasons[2]
+    assert "site_position" in reasons[3]
+    assert returned["rejected_rows"][2]["substrate_ac"] == "NOTANENTRY"
+
+
+# test that the other formats hold the results only and carry the counts in headers
+def test_report_headers():
+    result = post_rows(accept="text/csv")
+    assert result.status_code == 200, result.text
+    assert result.headers["X-Rejected-Rows"] == "4"
+    assert result.headers["X-Warnings"] == "2"
+
+
+# test that strict mode rejects the whole request and lists the rejected rows
+def test_strict():
+    result = post_rows(params={"strict": "true"})
+
+    assert result.status_code == 422, result.text
+    problem = json.loads(result.text)
+    assert problem["code"] == "unprocessable_entity"
+    assert [row["index"] for row in problem["rejected_rows"]] == [1, 2, 3, 4]
+
+
+# test that strict mode accepts a batch without rejected rows
+def test_strict_valid():
+    body = json.dumps([rows[0]])
+    result = requests.post(url=url, data=body, params={"strict": "true"}, headers={"Accept": "application/json"})
+
+    assert result.status_code == 200, result.text
+    assert json.loads(result.text)["rejected_rows"] == []
+
+
+# test that an invalid strict flag is a bad request
+def test_invalid_strict_flag():
+    result = post_rows(params={"strict": "maybe"})
+
+    assert result.status_code == 400, result.text
+    assert json.loads(result.text)["code"] == "bad_request"
+
+
+# test that every site looked up is checked against the stored sequence
+def test_site_checks():
+    body = json.dumps([
+        {"substrate_ac": "Q15796", "site_residue": "K", "site_position": "19"},
+        {"substrate_ac": "Q15796", "site_residue": "S", "site_position": "19"},
+        {"substrate_ac": "Q15796", "site_residue": "S", "site_position": "5000"},
+    ])
+    result = requests.post(url=url, data=body, headers={"Accept": "application/json"})
+    assert result.status_code == 200, result.text
+    assert result.headers["X-Unmatched-Sites"] == "2"
+
+    site_checks = json.loads(result.text)["site_checks"]
+    assert [site_check["index"] for site_check in site_checks] == [0, 1, 2]
+    assert [site_check["status"] for site_check in site_checks] == ["match", "mismatch", "out_of_range"]
+    assert site_checks[1]["sequence_residue"] == "K"
+    assert site_checks[0]["suggestions"] == []
@@ -0,0 +1,89 @@
+-- Schema for the embedded sqlite backend (driver = sqlite).
+-- Mirrors the materialized views the postgres and oracle engines query, with lowercase column names.
+
+CREATE TABLE IF NOT EXISTS mv_entry (
+    iptm_entry_id       INTEGER PRIMARY KEY,
+    iptm_entry_code     TEXT NOT NULL,
+    iptm_entry_type     TEXT,
+    uniprot_id          TEXT,
+    protein_name        TEXT,
+    gene_name           TEXT,
+    gene_syn            TEXT,
+    taxon_code          TEXT,
+    taxon_species       TEXT,
+    taxon_common        TEXT,
+    role_as_substrate   TEXT,
+    role_as_enzyme      TEXT,
+    role_as_ppi         TEXT,
+    num_substrate       INTEGER,
+    num_enzyme          INTEGER,
+    num_ppi             INTEGER,
+    num_site            INTEGER,
+    num_form            INTEGER,
+    list_as_substrate   TEXT,
+    category            TEXT,
+    definition          TEXT,
+    protein_synonyms    TEXT
+);
+CREATE INDEX IF NOT EXISTS mv_entry_code_idx ON mv_entry (iptm_entry_code);
+
+CREATE TABLE IF NOT EXISTS mv_event (
+    sub_code            TEXT,
+    sub_form_code       TEXT,
+    sub_symbol          TEXT,
+    residue             TEXT,
+    position            INTEGER,
+    event_name          TEXT,
+    event_label         TEXT,
+    enz_code            TEXT,
+    enz_type            TEXT,
+    enz_symbol          TEXT,
+    source_label        TEXT,
+    pmids               TEXT,
+    num_substrates      TEXT
+);
+CREATE INDEX IF NOT EXISTS mv_event_sub_code_idx ON mv_event (sub_code);
+CREATE INDEX IF NOT EXISTS mv_event_sub_form_code_idx ON mv_event (sub_form_code);
+CREATE INDEX IF NOT EXISTS mv_event_enz_code_idx ON mv_event (enz_code);
+
+CREATE TABLE IF NOT EXISTS mv_proteo (
+    sub_code            TEXT,
+    sub_symbol          TEXT,
+    sub_xref            TEXT,
+    sites               TEXT,
+    event_name          TEXT,
+    enz_code            TEXT,
+    enz_symbol          TEXT,
+    source_label        TEXT,
+    pmids               TEXT
+);
+
+CREATE TABLE IF NOT EXISTS mv_efip (
+    ppi_sub_code        TEXT,
+    ppi_sub_symbol      TEXT,
+    ppi_pr_code         TEXT,
+    ppi_pr_symbol       TEXT,
+    ptm_sub_code        TEXT,
+    ptm_residue         TEXT,
+    ptm_position        INTEGER,
+    ptm_event_name      TEXT,
+    ptm_source_label    TEXT,
+    ppi_source_label    TEXT,
+    impact              TEXT,
+    ppi_pmids           TEXT
+);
+
+CREATE TABLE IF NOT EXISTS sequence (
+    id                  TEXT PRIMARY KEY,
+    seq                 TEXT
+);
+
+CREATE TABLE IF NOT EXISTS biomuta (
+    ac                  TEXT,
+    position            INTEGER,
+    residue_sequence    TEXT,
+    residue_mutated     TEXT,
+    disease             TEXT,
+    sample_source       TEXT,
+    pmid                TEXT
+);
@@ -0,0 +1,367 @@
+use errors::*;
+use misc;
+use models::{ActivitySite, BatchPTMEnzyme, Entity, EnzymeActivity, PtmEnrichment, QuerySubstrate, RowWarning, ValidationReport};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use validation;
+
+/// Options of a kinase-substrate enrichment analysis.
+#[derive(Debug, Clone)]
+pub struct KseaOptions {
+    /// PTM type of the enzyme-substrate relations used, compared case insensitively. None uses every type.
+    pub ptm_type: Option<String>,
+    /// Relations with a lower evidence score are not used.
+    pub min_score: Option<i64>,
+    /// Enzymes with fewer sites among the query sites are not reported.
+    pub min_sites: usize,
+}
+
+//a query site as (substrate accession, site), the site written like the query_site of the lookups, e.g. K19
+pub type QueryKey = (String,String);
+
+//the key of a query substrate, normalized like validation::validate does
+pub fn query_key(query_substrate: &QuerySubstrate) -> QueryKey {
+    let site_position = query_substrate.site_position.trim();
+    let position;
+    match site_position.parse::<u32>() {
+        Ok(value) => {
+            position = format!("{}",value);
+        },
+        Err(_) => {
+            position = String::from(site_position);
+        }
+    }
+    let site = format!("{residue}{position}",residue=query_substrate.site_residue.trim().to_uppercase(),position=position);
+    return (String::from(query_substrate.substrate_ac.trim()),site);
+}
+
+// Kinase-substrate enrichment analysis (KSEA, Casado et al. 2013). The fold changes are the log
+// fold changes of the query sites, and ptm_enzymes the enzymes found for the query sites that were
+// looked up.
+//
+// Every enzyme gets the mean fold change of its sites among the query sites, each site counted once
+// even when several relations or nearby sites lead to it. Its z-score compares that mean with the
+// mean of all the query sites looked up, z = (mean_enzyme - mean_all) * sqrt(sites) / sd_all, and
+// the two sided p-value is taken from the normal distribution. The FDR is computed over the
+// reported enzymes with the Benjamini-Hochberg procedure. Enzymes are sorted by p-value.
+pub fn ksea(query_substrates: &Vec<QuerySubstrate>, fold_changes: &HashMap<QueryKey,f64>, ptm_enzymes: Vec<BatchPTMEnzyme>, options: &KseaOptions) -> Result<Vec<EnzymeActivity>> {
+    //the fold changes of the query sites looked up
+    let mut values: Vec<f64> = Vec::new();
+    for query_substrate in query_substrates {
+        match fold_changes.get(&query_key(query_substrate)) {
+            Some(fold_change) => {
+                values.push(*fold_change);
+            },
+            None => {}
+        }
+    }
+
+    if values.len() < 2 {
+        return Err(ErrorKind::Unprocessable(format!("At least 2 valid sites with a fold change are needed, got {count}",count=values.len())).into());
+    }
+    let mean_all = mean(&values);
+    let sd_all = standard_deviation(&values,mean_all);
+    if sd_all == 0.0 {
+        return Err(ErrorKind::Unprocessable(String::from("The fold changes of the sites are all the same")).into());
+    }
+
+    //the sites of every enzyme, by query site
+    let ptm_type = options.ptm_type.as_ref().map(|value| value.to_lowercase());
+    let mut enzyme_sites: HashMap<String,(Entity,HashMap<QueryKey,ActivitySite>)> = HashMap::new();
+    for ptm_enzyme in ptm_enzymes {
+        if !misc::filter_empty_enzymes(&ptm_enzyme) {
+            continue;
+        }
+
+        if ptm_type.is_some() && ptm_enzyme.ptm_type.as_ref().map(|value| value.to_lowercase()) != ptm_type {
+            continue;
+        }
+
+        match options.min_score {
+            Some(min_score) if ptm_enzyme.score < min_score => {
+                continue;
+            },
+            _ => {}
+        }
+
+        let enzyme = ptm_enzyme.enzyme.clone().unwrap();
+        let enzyme_id;
+        match enzyme.uniprot_id {
+            Some(ref value) => {
+                enzyme_id = value.clone();
+            },
+            None => {
+                continue;
+            }
+        }
+
+        let key;
+        match (ptm_enzyme.substrate.as_ref().and_then(|substrate| substrate.uniprot_id.clone()),ptm_enzyme.query_site.clone()) {
+            (Some(substrate_ac),Some(query_site)) => {
+                key = (substrate_ac,query_site);
+            },
+            _ => {
+                continue;
+            }
+        }
+
+        let fold_change;
+        match fold_changes.get(&key) {
+            Some(value) => {
+                fold_change = *value;
+            },
+            None => {
+                continue;
+            }
+        }
+
+        //keep the best scored relation of every query site
+        let sites = &mut enzyme_sites.entry(enzyme_id).or_insert((enzyme,HashMap::new())).1;
+        let is_better = match sites.get(&key) {
+            Some(site) => ptm_enzyme.score > site.score,
+            None => true
+        };
+        if is_better {
+            sites.insert(key,ActivitySite {
+                substrate: ptm_enzyme.substrate,
+                site: ptm_enzyme.site,
+                query_site: ptm_enzyme.query_site,
+                fold_change: fold_change,
+                score: ptm_enzyme.score
+            });
+        }
+    }
+
+    let mut activities: Vec<EnzymeActivity> = Vec::new();
+    for (_,(enzyme,sites)) in enzyme_sites {
+        if sites.len() < options.min_sites {
+            continue;
+        }
+
+        let mut sites: Vec<(QueryKey,ActivitySite)> = sites.into_iter().collect();
+        sites.sort_by(|a,b| a.0.cmp(&b.0));
+        let sites: Vec<ActivitySite> = sites.into_iter().map(|(_,site)| site).collect();
+
+        let site_values: Vec<f64> = sites.iter().map(|site| site.fold_change).collect();
+        let mean_fold_change = mean(&site_values);
+        let z_score = (mean_fold_change - mean_all) * (sites.len() as f64).sqrt() / sd_all;
+
+        activities.push(EnzymeActivity {
+            enzyme: Some(enzyme),
+            num_sites: sites.len(),
+            mean_fold_change: mean_fold_change,
+            z_score: z_score,
+            p_value: normal_p_value(z_score),
+            fdr: 1.0,
+            sites: sites
+        });
+    }
+
+    let p_values: Vec<f64> = activities.iter().map(|activity| activity.p_value).collect();
+    for (activity,fdr) in activities.iter_mut().zip(benjamini_hochberg(&p_values)) {
+        activity.fdr = fdr;
+    }
+
+    activities.sort_by(|a,b| {
+        return compare(a.p_value,b.p_value)
+                .then_with(|| a.enzyme.as_ref().map(|enzyme| enzyme.uniprot_id.clone()).cmp(&b.enzyme.as_ref().map(|enzyme| enzyme.uniprot_id.clone())));
+    });
+
+    return Ok(activities);
+}
+
+// PTM type enrichment of a protein list. The entry labels are the ptm labels (list_as_substrate)
+// of the ids that are substrates of the background, and the label counts the number of background
+// substrates for every list of labels.
+//
+// For every PTM type known to misc::get_ptm_event_label, the proteins of the list carrying it are
+// counted, and the hypergeometric p-value is the probability of drawing at least that many of them
+// when drawing as many proteins as the list holds from the background. The FDR is computed over the
+// types with the Benjamini-Hochberg procedure, and the types are sorted by p-value.
+//
+// An id that is not a substrate of the background is a rejected row and a repeated id is counted
+// once, with a warning.
+pub fn ptm_enrichment(ids: &Vec<String>, entry_labels: Vec<(String,Option<String>)>, label_counts: Vec<(Option<String>,i64)>) -> Result<(Vec<PtmEnrichment>,ValidationReport)> {
+    let mut protein_labels: HashMap<String,Vec<String>> = HashMap::new();
+    for (entry_code,labels) in entry_labels {
+        protein_labels.insert(entry_code,split_labels(&labels));
+    }
+
+    //the proteins of the list that are in the background
+    let mut report = ValidationReport::default();
+    let mut proteins: Vec<String> = Vec::new();
+    let mut first_rows: HashMap<String,usize> = HashMap::new();
+    for (index,id) in ids.iter().enumerate() {
+        if id.is_empty() {
+            report.rejected_rows.push(validation::rejected_id(index,id,String::from("the id is empty")));
+            continue;
+        }
+
+        match first_rows.get(id) {
+            Some(first_index) => {
+                report.warnings.push(RowWarning {
+                    index: index,
+                    message: format!("Duplicate of row {first_index}, counted once",first_index=first_index)
+                });
+                continue;
+            },
+            None => {}
+        }
+        first_rows.insert(id.clone(),index);
+
+        if protein_labels.contains_key(id) {
+            proteins.push(id.clone());
+        }else{
+            report.rejected_rows.push(validation::rejected_id(index,id,format!("{id} is not a substrate of the background",id=id)));
+        }
+    }
+
+    if proteins.is_empty() {
+        return Err(ErrorKind::Unprocessable(format!("None of the {count} ids is a substrate of the background",count=ids.len())).into());
+    }
+
+    //the background substrates carrying every label
+    let mut background_size: i64 = 0;
+    let mut background_counts: HashMap<String,i64> = HashMap::new();
+    for (labels,count) in label_counts {
+        background_size = background_size + count;
+        for label in split_labels(&labels) {
+            *background_counts.entry(label).or_insert(0) += count;
+        }
+    }
+
+    let ln_factorials = ln_factorials(background_size as usize);
+    let list_size = proteins.len();
+
+    let mut enrichments: Vec<PtmEnrichment> = Vec::new();
+    for ptm_type in misc::ptm_event_names() {
+        let ptm_label = misc::get_ptm_event_label(&ptm_type).unwrap_or(String::new());
+
+        let type_proteins: Vec<String> = proteins.iter()
+                .filter(|protein| protein_labels[*protein].contains(&ptm_label))
+                .cloned()
+                .collect();
+        let background_count = *background_counts.get(&ptm_label).unwrap_or(&0);
+
+        let mut fold_enrichment = 0.0;
+        if background_count > 0 {
+            fold_enrichment = (type_proteins.len() as f64 / list_size as f64) / (background_count as f64 / background_size as f64);
+        }
+
+        let p_value = hypergeometric_p_value(type_proteins.len(),list_size,background_count as usize,background_size as usize,&ln_factorials);
+
+        enrichments.push(PtmEnrichment {
+            ptm_type: ptm_type,
+            ptm_label: ptm_label,
+            count: type_proteins.len(),
+            list_size: list_size,
+            background_count: background_count,
+            background_size: background_size,
+            fold_enrichment: fold_enrichment,
+            p_value: p_value,
+            fdr: 1.0,
+            proteins: type_proteins
+        });
+    }
+
+    let p_values: Vec<f64> = enrichments.iter().map(|enrichment| enrichment.p_value).collect();
+    for (enrichment,fdr) in enrichments.iter_mut().zip(benjamini_hochberg(&p_values)) {
+        enrichment.fdr = fdr;
+    }
+
+    //the sort is stable, so types with the same p-value keep the order of misc::ptm_event_names
+    enrichments.sort_by(|a,b| compare(a.p_value,b.p_value));
+
+    return Ok((enrichments,report));
+}
+
+//the labels of a comma separated list like list_as_substrate
+fn split_labels(labels: &Option<String>) -> Vec<String> {
+    return misc::to_vec_string(labels,",").iter().map(|label| String::from(label.trim())).collect();
+}
+
+//ln(i!) for i from 0 to count
+fn ln_factorials(count: usize) -> Vec<f64> {
+    let mut values: Vec<f64> = Vec::with_capacity(count + 1);
+    values.push(0.0);
+    for i in 1..(count + 1) {
+        let previous = values[i - 1];
+        values.push(previous + (i as f64).ln());
+    }
+    return values;
+}
+
+fn ln_choose(n: usize, k: usize, ln_factorials: &Vec<f64>) -> f64 {
+    return ln_factorials[n] - ln_factorials[k] - ln_factorials[n - k];
+}
+
+// Probability of drawing at least `drawn_hits` hits when drawing `drawn` of `total` items, `hits`
+// of which are hits, without replacement.
+fn hypergeometric_p_value(drawn_hits: usize, drawn: usize, hits: usize, total: usize, ln_factorials: &Vec<f64>) -> f64 {
+    if drawn_hits == 0 {
+        return 1.0;
+    }
+    if drawn > total || hits > total {
+        return 1.0;
+    }
+
+    let ln_all_draws = ln_choose(total,drawn,ln_factorials);
+    let mut p_value = 0.0;
+    for i in drawn_hits..(drawn.min(hits) + 1) {
+        if drawn - i > total - hits {
+            continue;
+        }
+        p_value = p_value + (ln_choose(hits,i,ln_factorials) + ln_choose(total - hits,drawn - i,ln_factorials) - ln_all_draws).exp();
+    }
+    return p_value.min(1.0);
+}
+
+fn mean(values: &Vec<f64>) -> f64 {
+    return values.iter().sum::<f64>() / values.len() as f64;
+}
+
+//sample standard deviation
+fn standard_deviation(values: &Vec<f64>, mean: f64) -> f64 {
+    let sum_squares: f64 = values.iter().map(|value| (value - mean) * (value - mean)).sum();
+    return (sum_squares / (values.len() - 1) as f64).sqrt();
+}
+
+fn compare(a: f64, b: f64) -> Ordering {
+    return a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+}
+
+//two sided p-value of a z-score under the standard normal distribution
+pub fn normal_p_value(z_score: f64) -> f64 {
+    return erfc(z_score.abs() / 2.0_f64.sqrt()).min(1.0);
+}
+
+//complementary error function, with a fractional error below 1.2e-7 (Numerical Recipes, erfcc)
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let value = t * (-x * x - 1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418 + t * (-0.18628806
+                + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))))).exp();
+    if x >= 0.0 {
+        return value;
+    }else{
+        return 2.0 - value;
+    }
+}
+
+// Benjamini-Hochberg adjusted p-values, in the order of the p-values given. The adjusted value of
+// the p-value ranked i of n is the smallest p * n / rank over the ranks from i on, capped at 1.
+pub fn benjamini_hochberg(p_values: &Vec<f64>) -> Vec<f64> {
+    let count = p_values.len();
+    let mut order: Vec<usize> = (0..count).collect();
+    order.sort_by(|a,b| compare(p_values[*a],p_values[*b]));
+
+    let mut adjusted: Vec<f64> = vec![1.0; count];
+    let mut running_min: f64 = 1.0;
+    for (rank_index,index) in order.iter().enumerate().rev() {
+        let value = p_values[*index] * count as f64 / (rank_index + 1) as f64;
+        if value < running_min {
+            running_min = value;
+        }
+        adjusted[*index] = running_min;
+    }
+    return adjusted;
+}
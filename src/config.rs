@@ -0,0 +1,526 @@
+use database::DBParams;
+use database::PoolParams;
+use jobs::JobParams;
+use errors::*;
+use ini;
+use std;
+use std::fmt;
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.ini";
+
+const LOG_LEVELS: [&str; 6] = ["off","error","warn","info","debug","trace"];
+
+//every key that can be set in config.ini, grouped by section
+const CONFIG_KEYS: &[(&str,&str)] = &[
+    ("DEFAULT","driver"),
+    ("POSTGRES","host"),
+    ("POSTGRES","port"),
+    ("POSTGRES","user"),
+    ("POSTGRES","password"),
+    ("POSTGRES","database-name"),
+    ("ORACLE","host"),
+    ("ORACLE","port"),
+    ("ORACLE","user"),
+    ("ORACLE","password"),
+    ("ORACLE","service-name"),
+    ("SQLITE","path"),
+    ("POOL","min-idle"),
+    ("POOL","max-size"),
+    ("POOL","idle-timeout"),
+    ("POOL","connection-timeout"),
+    ("SERVER","host"),
+    ("SERVER","port"),
+    ("SERVER","workers"),
+    ("SERVER","keep-alive"),
+    ("SERVER","body-limit"),
+    ("SERVER","max-streams"),
+    ("SERVER","log-level"),
+    ("SERVER","log-format"),
+    ("JOBS","directory"),
+    ("JOBS","workers"),
+    ("JOBS","max-queued"),
+    ("JOBS","ttl"),
+];
+
+#[derive(Clone)]
+pub struct ServerParams {
+    pub host: String,
+    pub port: u16,
+    pub workers: Option<usize>,
+    pub keep_alive: Option<usize>,
+    pub body_limit: usize,
+    pub max_streams: usize,
+    pub log_level: String,
+    pub log_format: String
+}
+
+#[derive(Clone)]
+pub struct Config {
+    pub db: DBParams,
+    pub pool: PoolParams,
+    pub server: ServerParams,
+    pub jobs: JobParams
+}
+
+#[derive(Debug,Clone)]
+pub struct ConfigProblem {
+    pub section: String,
+    pub key: String,
+    pub message: String,
+    pub expected: String
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"[{section}] {key}: {message} (expected {expected})",section=self.section,key=self.key,message=self.message,expected=self.expected)
+    }
+}
+
+pub struct CliArgs {
+    pub config_path: String,
+    pub check_config: bool
+}
+
+pub fn usage() -> String {
+    let lines = vec![
+        "Usage: iptmnet_api [--config <path>] [--check-config]",
+        "",
+        "  --config <path>   read the configuration from <path> instead of config.ini",
+        "  --check-config    validate the configuration and database connectivity, then exit",
+    ];
+    return lines.join("\n");
+}
+
+//parses the command line arguments, without the program name
+pub fn parse_args(args: &Vec<String>) -> std::result::Result<CliArgs,String> {
+    let mut cli_args = CliArgs {
+        config_path: String::from(DEFAULT_CONFIG_PATH),
+        check_config: false
+    };
+
+    let mut index = 0;
+    while index < args.len() {
+        let arg = &args[index];
+        if arg == "--check-config" {
+            cli_args.check_config = true;
+        }else if arg == "--config" {
+            index = index + 1;
+            match args.get(index) {
+                Some(value) => {
+                    cli_args.config_path = value.clone();
+                },
+                None => {
+                    return Err(String::from("--config requires a path"));
+                }
+            }
+        }else if arg.starts_with("--config=") {
+            cli_args.config_path = String::from(&arg["--config=".len()..]);
+        }else{
+            return Err(format!("Unknown argument {arg}",arg=arg));
+        }
+        index = index + 1;
+    }
+
+    if cli_args.config_path.is_empty() {
+        return Err(String::from("--config requires a path"));
+    }
+
+    return Ok(cli_args);
+}
+
+//reads the config file and applies the environment overrides
+pub fn read(path: &str) -> Result<ini::Ini> {
+    match ini::Ini::load_from_file(path) {
+        Ok(mut conf) => {
+            apply_env_overrides(&mut conf);
+            return Ok(conf);
+        },
+        Err(error) => {
+            return Err(format!("Could not read config file {path} - {error}",path=path,error=error).into());
+        }
+    }
+}
+
+//name of the environment variable overriding a config key, e.g. IPTMNET_POSTGRES_DATABASE_NAME
+pub fn env_override_name(section: &str, key: &str) -> String {
+    return format!("IPTMNET_{section}_{key}",section=section,key=key.replace("-","_").to_uppercase());
+}
+
+//replaces the values in config.ini with the ones set in the environment. Empty variables are ignored.
+fn apply_env_overrides(conf: &mut ini::Ini) {
+    for &(section,key) in CONFIG_KEYS.iter() {
+        match std::env::var(env_override_name(section,key)) {
+            Ok(value) => {
+                if !value.is_empty() {
+                    conf.set_to(Some(section),String::from(key),value);
+                }
+            },
+            Err(_error) => {
+
+            }
+        }
+    }
+}
+
+//the log level is needed before the logger is set up, so an invalid value falls back to info here and is reported by parse
+pub fn log_level(conf: &ini::Ini) -> String {
+    match conf.get_from(Some("SERVER"),"log-level") {
+        Some(value) => {
+            let level = value.trim().to_lowercase();
+            if LOG_LEVELS.contains(&level.as_str()) {
+                return level;
+            }else{
+                return String::from("info");
+            }
+        },
+        None => {
+            return String::from("info");
+        }
+    }
+}
+
+//validates the whole configuration, collecting every problem instead of stopping at the first one
+pub fn parse(conf: &ini::Ini) -> std::result::Result<Config,Vec<ConfigProblem>> {
+    let mut reader = Reader {
+        conf: conf,
+        problems: Vec::new()
+    };
+
+    let db = parse_db(&mut reader);
+    let pool = parse_pool(&mut reader);
+    let server = parse_server(&mut reader,&pool);
+    let jobs = parse_jobs(&mut reader);
+
+    if reader.problems.is_empty() {
+        return Ok(Config {
+            db: db,
+            pool: pool,
+            server: server,
+            jobs: jobs
+        });
+    }else{
+        return Err(reader.problems);
+    }
+}
+
+fn parse_db(reader: &mut Reader) -> DBParams {
+    let mut db_params = DBParams {
+        engine: String::new(),
+        host: String::new(),
+        port: String::new(),
+        user: String::new(),
+        pass: String::new(),
+        db_name: String::new()
+    };
+
+    let expected_driver = "one of postgres, oracle or sqlite";
+    db_params.engine = reader.required("DEFAULT","driver",expected_driver);
+    if db_params.engine.is_empty() {
+        return db_params;
+    }
+
+    if db_params.engine == "postgres" || db_params.engine == "oracle" {
+        let section;
+        let name_key;
+        if db_params.engine == "postgres" {
+            section = "POSTGRES";
+            name_key = "database-name";
+        }else{
+            section = "ORACLE";
+            name_key = "service-name";
+        }
+
+        db_params.host = reader.required(section,"host","the host name or address of the database server");
+        db_params.port = reader.required(section,"port","the port of the database server");
+        if !db_params.port.is_empty() {
+            reader.port(section,"port",&db_params.port);
+        }
+        db_params.user = reader.required(section,"user","the user name used to connect");
+        //an empty password is allowed
+        match reader.optional(section,"password") {
+            Some(value) => {
+                db_params.pass = value;
+            },
+            None => {
+                reader.problem(section,"password","missing value","the password used to connect");
+            }
+        }
+        db_params.db_name = reader.required(section,name_key,"the name of the database to connect to");
+    }else if db_params.engine == "sqlite" {
+        db_params.db_name = reader.required("SQLITE","path","the path to the sqlite database file");
+        if !db_params.db_name.is_empty() && !std::path::Path::new(&db_params.db_name).is_file() {
+            let message = format!("file {path} does not exist",path=db_params.db_name);
+            reader.problem("SQLITE","path",&message,"the path to an existing sqlite database file");
+        }
+    }else{
+        let message = format!("unknown driver '{driver}'",driver=db_params.engine);
+        reader.problem("DEFAULT","driver",&message,expected_driver);
+    }
+
+    return db_params;
+}
+
+fn parse_pool(reader: &mut Reader) -> PoolParams {
+    let expected = "a non-negative integer";
+    let pool_params = PoolParams {
+        min_idle: reader.integer("POOL","min-idle",1,expected) as u32,
+        max_size: reader.integer("POOL","max-size",10,expected) as u32,
+        idle_timeout: reader.integer("POOL","idle-timeout",600,"a number of seconds"),
+        connection_timeout: reader.integer("POOL","connection-timeout",30,"a number of seconds")
+    };
+
+    if pool_params.max_size == 0 {
+        reader.problem("POOL","max-size","must be greater than 0","a positive integer");
+    }
+
+    if pool_params.min_idle > pool_params.max_size {
+        let message = format!("{min_idle} is greater than max-size {max_size}",min_idle=pool_params.min_idle,max_size=pool_params.max_size);
+        reader.problem("POOL","min-idle",&message,"a value not greater than max-size");
+    }
+
+    return pool_params;
+}
+
+fn parse_server(reader: &mut Reader, pool_params: &PoolParams) -> ServerParams {
+    let mut server_params = ServerParams {
+        host: String::from("0.0.0.0"),
+        port: 8088,
+        workers: None,
+        keep_alive: None,
+        body_limit: 16_777_216,
+        max_streams: 1,
+        log_level: String::from("info"),
+        log_format: String::from("STATUS : %s | %t | %D ms | PID: %P | ID: %{X-Request-Id}i | %r ")
+    };
+
+    //host
+    match reader.optional("SERVER","host") {
+        Some(value) => {
+            server_params.host = value;
+        },
+        None => {}
+    }
+
+    //port
+    match reader.optional("SERVER","port") {
+        Some(value) => {
+            server_params.port = reader.port("SERVER","port",&value);
+        },
+        None => {}
+    }
+
+    //workers
+    match reader.optional("SERVER","workers") {
+        Some(_) => {
+            let workers = reader.integer("SERVER","workers",0,"a positive number of threads") as usize;
+            if workers == 0 {
+                reader.problem("SERVER","workers","must be greater than 0","a positive number of threads");
+            }
+            server_params.workers = Some(workers);
+        },
+        None => {}
+    }
+
+    //keep alive, 0 disables it
+    let keep_alive = reader.integer("SERVER","keep-alive",0,"a number of seconds, 0 to disable") as usize;
+    if keep_alive != 0 {
+        server_params.keep_alive = Some(keep_alive);
+    }
+
+    //body limit
+    server_params.body_limit = reader.integer("SERVER","body-limit",server_params.body_limit as u64,"a positive number of bytes") as usize;
+    if server_params.body_limit == 0 {
+        reader.problem("SERVER","body-limit","must be greater than 0","a positive number of bytes");
+    }
+
+    //streamed responses, each one holds a pooled connection until its client has read it, so
+    //by default half of the pool is left to the other requests
+    let default_max_streams = ::std::cmp::max(1,pool_params.max_size / 2) as u64;
+    server_params.max_streams = reader.integer("SERVER","max-streams",default_max_streams,"a positive number of responses") as usize;
+    if server_params.max_streams == 0 {
+        reader.problem("SERVER","max-streams","must be greater than 0","a positive number of responses");
+    }else if pool_params.max_size > 0 && server_params.max_streams > pool_params.max_size as usize {
+        let message = format!("{max_streams} is greater than the pool max-size {max_size}",max_streams=server_params.max_streams,max_size=pool_params.max_size);
+        reader.problem("SERVER","max-streams",&message,"a value not greater than the pool max-size");
+    }
+
+    //log level
+    match reader.optional("SERVER","log-level") {
+        Some(value) => {
+            let level = value.to_lowercase();
+            if LOG_LEVELS.contains(&level.as_str()) {
+                server_params.log_level = level;
+            }else{
+                let message = format!("unknown log level '{level}'",level=value);
+                reader.problem("SERVER","log-level",&message,"one of off, error, warn, info, debug or trace");
+            }
+        },
+        None => {}
+    }
+
+    //log format
+    match reader.optional("SERVER","log-format") {
+        Some(value) => {
+            server_params.log_format = value;
+        },
+        None => {}
+    }
+
+    return server_params;
+}
+
+fn parse_jobs(reader: &mut Reader) -> JobParams {
+    let mut job_params = JobParams {
+        directory: String::from("jobs"),
+        workers: reader.integer("JOBS","workers",2,"a positive number of threads") as usize,
+        max_queued: reader.integer("JOBS","max-queued",100,"a positive number of jobs") as usize,
+        ttl: reader.integer("JOBS","ttl",86400,"a positive number of seconds")
+    };
+
+    //directory
+    match reader.optional("JOBS","directory") {
+        Some(value) => {
+            if value.is_empty() {
+                reader.problem("JOBS","directory","value is empty","the directory the job results are written to");
+            }
+            job_params.directory = value;
+        },
+        None => {}
+    }
+
+    if job_params.workers == 0 {
+        reader.problem("JOBS","workers","must be greater than 0","a positive number of threads");
+    }
+
+    if job_params.max_queued == 0 {
+        reader.problem("JOBS","max-queued","must be greater than 0","a positive number of jobs");
+    }
+
+    if job_params.ttl == 0 {
+        reader.problem("JOBS","ttl","must be greater than 0","a positive number of seconds");
+    }
+
+    return job_params;
+}
+
+struct Reader<'a> {
+    conf: &'a ini::Ini,
+    problems: Vec<ConfigProblem>
+}
+
+impl<'a> Reader<'a> {
+    fn problem(&mut self, section: &str, key: &str, message: &str, expected: &str) {
+        self.problems.push(ConfigProblem {
+            section: String::from(section),
+            key: String::from(key),
+            message: String::from(message),
+            expected: String::from(expected)
+        });
+    }
+
+    fn optional(&self, section: &str, key: &str) -> Option<String> {
+        match self.conf.get_from(Some(section),key) {
+            Some(value) => {
+                return Some(String::from(value.trim()));
+            },
+            None => {
+                return None;
+            }
+        }
+    }
+
+    fn required(&mut self, section: &str, key: &str, expected: &str) -> String {
+        match self.optional(section,key) {
+            Some(value) => {
+                if value.is_empty() {
+                    self.problem(section,key,"value is empty",expected);
+                }
+                return value;
+            },
+            None => {
+                self.problem(section,key,"missing value",expected);
+                return String::new();
+            }
+        }
+    }
+
+    fn integer(&mut self, section: &str, key: &str, default: u64, expected: &str) -> u64 {
+        match self.optional(section,key) {
+            Some(value) => {
+                match value.parse::<u64>() {
+                    Ok(parsed_value) => {
+                        return parsed_value;
+                    },
+                    Err(_error) => {
+                        let message = format!("'{value}' is not a non-negative integer",value=value);
+                        self.problem(section,key,&message,expected);
+                        return default;
+                    }
+                }
+            },
+            None => {
+                return default;
+            }
+        }
+    }
+
+    fn port(&mut self, section: &str, key: &str, value: &str) -> u16 {
+        match value.parse::<u16>() {
+            Ok(port) => {
+                if port != 0 {
+                    return port;
+                }
+            },
+            Err(_error) => {}
+        }
+
+        let message = format!("'{value}' is not a valid port",value=value);
+        self.problem(section,key,&message,"a port number between 1 and 65535");
+        return 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSTGRES_CONFIG: &str = "[DEFAULT]\n\
+                                   driver = postgres\n\
+                                   [POSTGRES]\n\
+                                   host = localhost\n\
+                                   port = 5432\n\
+                                   user = iptmnet\n\
+                                   password = secret\n\
+                                   database-name = iptmnet\n\
+                                   [POOL]\n\
+                                   max-size = 8\n";
+
+    fn parse_str(text: &str) -> std::result::Result<Config,Vec<ConfigProblem>> {
+        let conf = ini::Ini::load_from_str(text).unwrap();
+        return parse(&conf);
+    }
+
+    #[test]
+    fn every_config_key_is_overridable() {
+        assert!(CONFIG_KEYS.contains(&("SERVER","max-streams")));
+    }
+
+    #[test]
+    fn max_streams_defaults_to_half_the_pool() {
+        let config = parse_str(POSTGRES_CONFIG).unwrap();
+        assert_eq!(config.server.max_streams,4);
+    }
+
+    #[test]
+    fn max_streams_is_read() {
+        let config = parse_str(&format!("{config}[SERVER]\nmax-streams = 6\n",config=POSTGRES_CONFIG)).unwrap();
+        assert_eq!(config.server.max_streams,6);
+    }
+
+    #[test]
+    fn max_streams_above_the_pool_is_a_problem() {
+        let problems = parse_str(&format!("{config}[SERVER]\nmax-streams = 9\n",config=POSTGRES_CONFIG)).err().unwrap();
+        assert_eq!(problems.len(),1);
+        assert_eq!(problems[0].key,"max-streams");
+    }
+}
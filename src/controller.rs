@@ -4,15 +4,24 @@ use database;
 use serde_json;
 use misc;
 use msa;
-use std::str;
+use futures::future;
 use futures::future::Future;
-use models::QuerySubstrate;
-use flatten;
-use csv;
+use models::{BatchPTMEnzyme, Info, Job, JobKind, SearchResult};
+use render;
 use std::collections::HashMap;
-use futures::Stream;
 use std::fs::File;
 use std::io::prelude::*;
+use problem;
+use negotiation;
+use stream;
+use jobs;
+use upload;
+use validation;
+use analysis;
+use id_mapping;
+use site;
+use publication;
+use network;
 
 pub fn get_status_controller(_req: HttpRequest<super::State>) -> HttpResponse {
     let mut status : HashMap<&str,&str> = HashMap::new();
@@ -23,49 +32,47 @@ pub fn get_status_controller(_req: HttpRequest<super::State>) -> HttpResponse {
 }
 
 pub fn get_info_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError().force_close().body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id string
-    let info_result = database::get_info(&id,&conn);
+    let info_result = conn.get_info(&id);
 
     match info_result {
-        Ok(info) => {
-
-            let info_serialized_result = serde_json::to_string_pretty(&info);
-
-            match info_serialized_result {
-
-                Ok(info_serialized) => {
-                    HttpResponse::Ok()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "application/json")
-                    .body(info_serialized)
-                },
-
-                Err(error) => {
-                    HttpResponse::InternalServerError()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body(format!("{}",error))
-                }
-
-            }
+        Ok(Some(info)) => {
+            return render::response(&context,&info,format);
+        },
 
+        //the id passed check_entry through its base accession, but the isoform itself has no entry
+        Ok(None) => {
+            return problem::response(&context,&conn.unknown_entry(&id));
         },
 
         Err(error) => {
-            HttpResponse::InternalServerError()
-            .force_close()
-            .header(http::header::CONTENT_TYPE, "text/plain")
-            .body(format!("{}",error))
+            return problem::response(&context,&error);
         }
     }
 
@@ -73,19 +80,23 @@ pub fn get_info_controller(req: HttpRequest<super::State>) -> HttpResponse {
 
 
 pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
     
     //params
     let search_term;
@@ -102,36 +113,24 @@ pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
             if !val.is_empty(){
                 search_term = val
             }else{
-                return HttpResponse::BadRequest()
-                .force_close()
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body("search_term cannot be blank");
+                return problem::bad_request(&context,"search_term cannot be blank");
             }
         },
-        None => {return HttpResponse::BadRequest()
-        .force_close()
-        .header(http::header::CONTENT_TYPE, "text/plain")
-        .body("search_term cannot be empty");}
+        None => {return problem::bad_request(&context,"search_term cannot be empty");}
     }
 
     // term type
     let term_type_option = req.query().get("term_type");
     match term_type_option {
         Some(val) => {term_type=val},
-        None => {return HttpResponse::BadRequest()
-        .force_close()
-        .header(http::header::CONTENT_TYPE, "text/plain")
-        .body("term_type cannot be empty");}
+        None => {return problem::bad_request(&context,"term_type cannot be empty");}
     }
 
     // role
     let role_option = req.query().get("role");
     match role_option {
         Some(val) => {role=val},
-        None => {return HttpResponse::BadRequest()
-                .force_close()
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body("role cannot be empty");}
+        None => {return problem::bad_request(&context,"role cannot be empty");}
     }
 
     // ptm type
@@ -150,10 +149,7 @@ pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
                 None => {
                     let error_msg = format!("invalid PTM type {}",ptm_type);
                     error!("{}",error_msg);
-                    return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body(error_msg);
+                    return problem::bad_request(&context,error_msg);
                 }
             }
         };       
@@ -169,10 +165,7 @@ pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
         },
         Err(error) => {
             error!("{}",error);
-            return  HttpResponse::InternalServerError()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body(format!("{}",error))
+            return problem::response(&context,&error)
         }
     }
     
@@ -205,18 +198,12 @@ pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
                     },
                     Err(error) => {
                         error!("{}",error);
-                        return  HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error))
+                        return problem::bad_request(&context,format!("start_index must be an integer - {}",error))
                     },
                 }
             },
             None => {
-                return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body("start_index cannot be empty");
+                return problem::bad_request(&context,"start_index cannot be empty");
             }
         }
 
@@ -231,28 +218,19 @@ pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
                     },
                     Err(error) => {
                         error!("{}",error);
-                        return  HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error))
+                        return problem::bad_request(&context,format!("end_index must be an integer - {}",error))
                     },
                 }
             },
             None => {
-                return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body("end_index cannot be empty");
+                return problem::bad_request(&context,"end_index cannot be empty");
             }
         }
 
         //calculate the limit and offset
         limit = end_index - start_index;
         if limit <= 0 {
-            {return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body("end_index cannot be smaller than or equal to start index");}
+            {return problem::bad_request(&context,"end_index cannot be smaller than or equal to start index");}
         }
         offset = start_index;
     }else{
@@ -261,117 +239,48 @@ pub fn search_controller(req: HttpRequest<super::State>) -> HttpResponse {
     }
 
     // perform the search
-    let search_values_result = database::search(search_term,term_type,role,&ptm_labels_to_filter,&organism_taxon_codes,paginate,offset,limit,&conn);
+    let search_values_result = conn.search(search_term,term_type,role,&ptm_labels_to_filter,&organism_taxon_codes,paginate,offset,limit);
 
     match search_values_result {
         Ok(values) => {
             let (count, search_values) = values; 
-            if content_header == "application/json" || content_header.is_empty() {
-                let search_values_serialized_result = serde_json::to_string_pretty(&(*search_values));
-
-                match search_values_serialized_result {
-
-                    Ok(search_values_serialized) => {
-                        HttpResponse::Ok()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "application/json")
-                        .header("count", count.to_string())
-                        .body(search_values_serialized)
-                    },
-
-                    Err(error) => {
-                        error!("{}",error);
-                        HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error))
-                    }
-
-                }
-            } else if content_header == "text/plain" {
-                    //convert the values to flat structure
-                    let search_results_flat = flatten::search_results(&(*search_values.borrow()));
-
-                    let mut wtr = csv::Writer::from_writer(vec![]);
-                    
-                    for search_result_flat in search_results_flat {
-                        let result = wtr.serialize(&search_result_flat);
-                        match result {
-                            Ok(_) => {},
-                            Err(error) => {
-                                error!("{}",error);
-                                return HttpResponse::InternalServerError()
-                                .force_close()
-                                .header(http::header::CONTENT_TYPE, "text/plain")
-                                .body(format!("{}",error));
-                            }
-                        }
-                    }
-
-                    let inner;
-                    let inner_result = wtr.into_inner();
-                    match inner_result {
-                        Ok(value) => {inner=value;},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError()
+            match render::render(&search_values,format) {
+                Ok(data) => {
+                    return HttpResponse::Ok()
                             .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/plain")
-                            .body(format!("{}",error));
-                        }
-                    }
-
-                    let data_result = String::from_utf8(inner);
-                    match data_result {
-                        Ok(data) => {
-                            return HttpResponse::Ok()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/csv")
+                            .header(http::header::CONTENT_TYPE, format.content_type())
                             .header("count", count.to_string())
                             .body(data);
-                        },
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/plain")
-                            .body(format!("{}",error));
-                        }
-                    }
-            }else {
-                return HttpResponse::BadRequest()
-                .force_close()
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body(format!("Invalid ACCEPT header - {}",content_header));
+                },
+                Err(error) => {
+                    return problem::response(&context,&error);
+                }
             }
-
         },
-
         Err(error) => {
-            error!("{}",error);
-            HttpResponse::InternalServerError()
-            .force_close()
-            .header(http::header::CONTENT_TYPE, "text/plain")
-            .body(format!("{}",error))
+            return problem::response(&context,&error);
         }
-
     }
 }
 
 pub fn browse_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
     
     //params
     let search_term = "";
@@ -385,20 +294,14 @@ pub fn browse_controller(req: HttpRequest<super::State>) -> HttpResponse {
     let term_type_option = req.query().get("term_type");
     match term_type_option {
         Some(val) => {term_type=val},
-        None => {return HttpResponse::BadRequest()
-        .force_close()
-        .header(http::header::CONTENT_TYPE, "text/plain")
-        .body("term_type cannot be empty");}
+        None => {return problem::bad_request(&context,"term_type cannot be empty");}
     }
 
     // role
     let role_option = req.query().get("role");
     match role_option {
         Some(val) => {role=val},
-        None => {return HttpResponse::BadRequest()
-                .force_close()
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body("role cannot be empty");}
+        None => {return problem::bad_request(&context,"role cannot be empty");}
     }
 
     // ptm type
@@ -417,10 +320,7 @@ pub fn browse_controller(req: HttpRequest<super::State>) -> HttpResponse {
                 None => {
                     let error_msg = format!("invalid PTM type {}",ptm_type);
                     error!("{}",error_msg);
-                    return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body(error_msg);
+                    return problem::bad_request(&context,error_msg);
                 }
             }
         };       
@@ -436,10 +336,7 @@ pub fn browse_controller(req: HttpRequest<super::State>) -> HttpResponse {
         },
         Err(error) => {
             error!("{}",error);
-            return  HttpResponse::InternalServerError()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body(format!("{}",error))
+            return problem::response(&context,&error)
         }
     }
     
@@ -453,18 +350,12 @@ pub fn browse_controller(req: HttpRequest<super::State>) -> HttpResponse {
                     },
                     Err(error) => {
                         error!("{}",error);
-                        return  HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error))
+                        return problem::bad_request(&context,format!("start_index must be an integer - {}",error))
                     },
                 }
             },
             None => {
-                return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body("start_index cannot be empty");
+                return problem::bad_request(&context,"start_index cannot be empty");
             }
     }
 
@@ -479,816 +370,931 @@ pub fn browse_controller(req: HttpRequest<super::State>) -> HttpResponse {
                     },
                     Err(error) => {
                         error!("{}",error);
-                        return  HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error))
+                        return problem::bad_request(&context,format!("end_index must be an integer - {}",error))
                     },
                 }
             },
             None => {
-                return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body("end_index cannot be empty");
+                return problem::bad_request(&context,"end_index cannot be empty");
         }
     }
 
     //calculate the limit and offset
     limit = end_index - start_index;
     if limit <= 0 {
-        {return HttpResponse::BadRequest()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "text/plain")
-                    .body("end_index cannot be smaller than or equal to start index");}
+        {return problem::bad_request(&context,"end_index cannot be smaller than or equal to start index");}
     }
     offset = start_index;
 
-    // perform the search
-    let search_values_result = database::search(search_term,term_type,role,&ptm_labels_to_filter,&organism_taxon_codes,paginate,offset,limit,&conn);
-
-    match search_values_result {
-        Ok(values) => {
-            let (count, search_values) = values; 
-            if content_header == "application/json" || content_header.is_empty() {
-                let search_values_serialized_result = serde_json::to_string_pretty(&(*search_values));
-
-                match search_values_serialized_result {
-
-                    Ok(search_values_serialized) => {
-                        HttpResponse::Ok()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "application/json")
-                        .header("count", count.to_string())
-                        .body(search_values_serialized)
-                    },
-
-                    Err(error) => {
-                        error!("{}",error);
-                        HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error))
-                    }
-
-                }
-            } else if content_header == "text/plain" {
-                    //convert the values to flat structure
-                    let search_results_flat = flatten::search_results(&(*search_values.borrow()));
-
-                    let mut wtr = csv::Writer::from_writer(vec![]);
-                    
-                    for search_result_flat in search_results_flat {
-                        let result = wtr.serialize(&search_result_flat);
-                        match result {
-                            Ok(_) => {},
-                            Err(error) => {
-                                error!("{}",error);
-                                return HttpResponse::InternalServerError()
-                                .force_close()
-                                .header(http::header::CONTENT_TYPE, "text/plain")
-                                .body(format!("{}",error));
-                            }
-                        }
-                    }
-
-                    let inner;
-                    let inner_result = wtr.into_inner();
-                    match inner_result {
-                        Ok(value) => {inner=value;},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/plain")
-                            .body(format!("{}",error));
-                        }
-                    }
-
-                    let data_result = String::from_utf8(inner);
-                    match data_result {
-                        Ok(data) => {
-                            return HttpResponse::Ok()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/csv")
-                            .header("count", count.to_string())
-                            .body(data);
-                        },
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/plain")
-                            .body(format!("{}",error));
-                        }
-                    }
-            }else {
-                return HttpResponse::BadRequest()
-                .force_close()
-                .header(http::header::CONTENT_TYPE, "text/plain")
-                .body(format!("Invalid ACCEPT header - {}",content_header));
-            }
-
-        },
-
-        Err(error) => {
-            HttpResponse::InternalServerError()
-            .force_close()
-            .header(http::header::CONTENT_TYPE, "text/plain")
-            .body(format!("{}",error))
-        }
-
+    //count the results, which is sent before the results are streamed
+    let count;
+    match conn.search_count(search_term,term_type,role,&ptm_labels_to_filter,&organism_taxon_codes) {
+        Ok(value) => {count = value},
+        Err(error) => {return problem::response(&context,&error);},
     }
-}
 
+    //stream the results as they are read
+    let term_type = term_type.to_string();
+    let role = role.to_string();
+    return stream::response(&context,&req.state().streams,HttpResponse::Ok().header("count", count.to_string()),format,move |row_writer| {
+        row_writer.columns::<SearchResult>()?;
+        return conn.search_each(search_term,&term_type,&role,&ptm_labels_to_filter,&organism_taxon_codes,paginate,offset,limit,&mut |search_result| {
+            return row_writer.write(&search_result);
+        });
+    });
+}
 
 pub fn substrate_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id strings
-    let substrate_events_results = database::get_substrate_events(&id,&conn);
+    let substrate_events_results = conn.get_substrate_events(&id);
 
     //check if operation was successful
     match substrate_events_results {
         Ok(substrate_events) => {
-
-            if content_header == "application/json" || content_header.is_empty() {
-                //try deserializing    
-                let substrate_events_serialized_result = serde_json::to_string_pretty(&substrate_events);
-
-                //check if operation was successful
-                match substrate_events_serialized_result {
-
-                    Ok(substrate_events_serialized) => {
-                        HttpResponse::Ok()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "application/json")
-                        .body(substrate_events_serialized)
-                    },
-
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));
-                    }
-
-                }
-            }else if content_header == "text/plain"{
-                //convert the values to flat structure
-                let substrate_events_flat = flatten::substrate_events(&substrate_events);
-
-                let mut wtr = csv::Writer::from_writer(vec![]);
-                
-                for substrate_event_flat in substrate_events_flat {
-                    let result = wtr.serialize(&substrate_event_flat);
-                    match result {
-                        Ok(_) => {},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/plain")
-                            .body(format!("{}",error));
-                        }
-                    }
-                }
-
-                let inner;
-                let inner_result = wtr.into_inner();
-                match inner_result {
-                    Ok(value) => {inner=value;},
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));
-                    }
-                }
-
-                let data_result = String::from_utf8(inner);
-                match data_result {
-                    Ok(data) => {
-                        return HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/csv").body(data);
-                    },
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));
-                    }
-                }
-            }else {
-                return HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header));
-            }          
-
+            return render::response(&context,&substrate_events,format);
         },
-
         Err(error) => {
-            HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
+            return problem::response(&context,&error);
         }
     }
-
 }
 
 
 pub fn as_enzyme_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id strings
-    let enzyme_events_results = database::get_enzyme_events(&id,&conn);
+    let enzyme_events_results = conn.get_enzyme_events(&id);
 
     //check if operation was successful
     match enzyme_events_results {
         Ok(enzyme_events) => {
-
-            if content_header == "application/json" || content_header.is_empty() {
-                //try deserializing    
-                let events_serialized_result = serde_json::to_string_pretty(&enzyme_events);
-
-                //check if operation was successful
-                match events_serialized_result {
-
-                    Ok(events_serialized) => {
-                        HttpResponse::Ok()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "application/json")
-                        .body(events_serialized)
-                    },
-
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));
-                    }
-
-                }
-            }else if content_header == "text/plain"{
-                //convert the values to flat structure
-                let events_flat = flatten::enzyme_events(&enzyme_events);
-
-                let mut wtr = csv::Writer::from_writer(vec![]);
-                
-                for event in events_flat {
-                    let result = wtr.serialize(&event);
-                    match result {
-                        Ok(_) => {},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError()
-                            .force_close()
-                            .header(http::header::CONTENT_TYPE, "text/plain")
-                            .body(format!("{}",error));
-                        }
-                    }
-                }
-
-                let inner;
-                let inner_result = wtr.into_inner();
-                match inner_result {
-                    Ok(value) => {inner=value;},
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));
-                    }
-                }
-
-                let data_result = String::from_utf8(inner);
-                match data_result {
-                    Ok(data) => {
-                        return HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/csv").body(data);
-                    },
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError()
-                        .force_close()
-                        .header(http::header::CONTENT_TYPE, "text/plain")
-                        .body(format!("{}",error));
-                    }
-                }
-            }else {
-                return HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header));
-            }          
-
+            return render::response(&context,&enzyme_events,format);
         },
-
         Err(error) => {
-            HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
+            return problem::response(&context,&error);
         }
     }
-
 }
 
 
 pub fn proteoforms_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id strings
-    let proteoforms_result = database::get_proteoforms(&id,&conn);
+    let proteoforms_result = conn.get_proteoforms(&id);
 
     //check if operation was successful
     match proteoforms_result {
         Ok(proteoforms) => {
-
-            if content_header == "application/json" || content_header.is_empty() {
-                //try deserializing    
-                let proteoforms_serialized_result = serde_json::to_string_pretty(&proteoforms);
-
-                //check if operation was successful
-                match proteoforms_serialized_result {
-
-                    Ok(proteoforms_serialized) => {
-                        HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "application/json").body(proteoforms_serialized)
-                    },
-
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                    }
-
-                }
-            }else if content_header == "text/plain"{
-                //convert the values to flat structure
-                let protoroforms_flat = flatten::proteoform(&proteoforms);
-
-                let mut wtr = csv::Writer::from_writer(vec![]);
-                
-                for proteoform_flat in protoroforms_flat {
-                    let result = wtr.serialize(&proteoform_flat);
-                    match result {
-                        Ok(_) => {},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                        }
-                    }
-                }
-
-                let inner;
-                let inner_result = wtr.into_inner();
-                match inner_result {
-                    Ok(value) => {inner=value;},
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                    }
-                }
-
-                let data_result = String::from_utf8(inner);
-                match data_result {
-                    Ok(data) => {
-                        return HttpResponse::Ok().force_close().body(data);
-                    },
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                    }
-                }
-            }else {
-                return HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header));
-            }          
-
+            return render::response(&context,&proteoforms,format);
         },
-
         Err(error) => {
-            HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
+            return problem::response(&context,&error);
         }
     }
-
 }
 
 
 pub fn proteoformsppi_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id strings
-    let proteoforms_ppi_result = database::get_proteoformppis(&id,&conn);
+    let proteoforms_ppi_result = conn.get_proteoformppis(&id);
 
     //check if the operation was successful
     match proteoforms_ppi_result {
         Ok(proteoforms_ppi) => {
-            
-            if content_header == "application/json" || content_header.is_empty() {
-                //try deserializing    
-                let proteoforms_serialized_result = serde_json::to_string_pretty(&proteoforms_ppi);
-
-                //check if operation was successful
-                match proteoforms_serialized_result {
-
-                    Ok(proteoforms_serialized) => {
-                        HttpResponse::Ok().body(proteoforms_serialized)
-                    },
-
-                    Err(error) => {
-                        HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "application/json").body(format!("{}",error))
-                    }
-
-                }
-            }else if content_header == "text/plain" {
-                //convert the values to flat structure
-                let protoroforms_ppi_flat = flatten::proteoform_ppis(&proteoforms_ppi);
-
-                let mut wtr = csv::Writer::from_writer(vec![]);
-                
-                for proteoform_ppi_flat in protoroforms_ppi_flat {
-                    let result = wtr.serialize(&proteoform_ppi_flat);
-                    match result {
-                        Ok(_) => {},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                        }
-                    }
-                }
-
-                let inner;
-                let inner_result = wtr.into_inner();
-                match inner_result {
-                    Ok(value) => {inner=value;},
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                    }
-                }
-
-                let data_result = String::from_utf8(inner);
-                match data_result {
-                    Ok(data) => {
-                        return HttpResponse::Ok().force_close().body(data);
-                    },
-                    Err(error) => {
-                        error!("{}",error);
-                        return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                    }
-                }                
-            }else {
-                return HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header));
-            }           
+            return render::response(&context,&proteoforms_ppi,format);
         },
-
         Err(error) => {
-            HttpResponse::InternalServerError().header(http::header::CONTENT_TYPE, "text/plain").force_close().body(format!("{}",error))
+            return problem::response(&context,&error);
         }
     }
 }
 
 
 pub fn ptmppi_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError().header(http::header::CONTENT_TYPE, "text/plain").force_close().body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id strings
-    let ptmppi_result = database::get_ptmppis(&id,&conn);
+    let ptmppi_result = conn.get_ptmppis(&id);
 
     //check if the operation was successful
     match ptmppi_result {
         Ok(ptmppis) => {
-            
-            if content_header == "application/json"{
-                //try deserializing    
-                let ptmppis_serialized_result = serde_json::to_string_pretty(&ptmppis);
+            return render::response(&context,&ptmppis,format);
+        },
+        Err(error) => {
+            return problem::response(&context,&error);
+        }
+    }
+}
 
-                //check if operation was successful
-                match ptmppis_serialized_result {
 
-                    Ok(proteoforms_serialized) => {
-                        HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(proteoforms_serialized)
-                    },
+pub fn batch_ptm_enzymes_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {  
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
 
-                    Err(error) => {
-                        HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
-                    }
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let streams = req.state().streams.clone();
 
-                }
-            } else if content_header =="text/plain" {
-                    //convert the values to flat structure
-                    let ptm_ppi_flat = flatten::ptm_ppi(&ptmppis);
-
-                    let mut wtr = csv::Writer::from_writer(vec![]);
-                    
-                    for ptm_ppi_flat in ptm_ppi_flat {
-                        let result = wtr.serialize(&ptm_ppi_flat);
-                        match result {
-                            Ok(_) => {},
-                            Err(error) => {
-                                error!("{}",error);
-                                return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                            }
-                        }
-                    }
+    //the body is read according to its content type, with the columns named in the query
+    let content_type = request_content_type(&req);
+    let column_mapping = upload::column_mapping(req.query());
 
-                    let inner;
-                    let inner_result = wtr.into_inner();
-                    match inner_result {
-                        Ok(value) => {inner=value;},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                        }
-                    }
+    //in strict mode a single rejected row fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
 
-                    let data_result = String::from_utf8(inner);
-                    match data_result {
-                        Ok(data) => {
-                            return HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(data);
-                        },
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                        }
-                    }                
-            }else {
-                return HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header));
-            }                 
+    //sites within the tolerance of a query site are matched too
+    let tolerance;
+    match tolerance_param(&req) {
+        Ok(value) => {tolerance = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
 
 
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
 
-        },
+            
+            let conn;
+            match database::get_connection(&db_pool) {
+                Ok(val) => {conn = val},
+                Err(error) => {return Ok(problem::response(&context,&error));},
+            }
+            info!("Got database connection");
 
-        Err(error) => {
-            HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
-        }
-    }
+            //parse the body according to its content type
+            let rows;
+            match upload::parse_query_substrates(&content_type,&column_mapping,&body_bytes) {
+                Ok(val) => {rows = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            info!("parsed query substrates");
+
+            //check the rows, keeping the valid ones
+            let query_substrates;
+            let report;
+            match validation::validate(&**conn,rows,strict) {
+                Ok((valid_substrates,validation_report)) => {
+                    query_substrates = valid_substrates;
+                    report = validation_report;
+                },
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            //stream the ptm enzymes as they are read
+            return Ok(stream::batch_response(&context,&streams,&mut HttpResponse::Ok(),format,report,move |row_writer| {
+                row_writer.columns::<BatchPTMEnzyme>()?;
+                return conn.ptm_enzymes_each(&query_substrates,tolerance,&mut |ptm_enzyme| {
+                    return row_writer.write(&ptm_enzyme);
+                });
+            }));
+        })
+    .responder()
 }
 
+pub fn batch_ptm_ppi_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
 
-pub fn batch_ptm_enzymes_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {  
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
 
-    //get the connection
-    let conn_result = database::connect(&req.state().db_params);
+    //the body is read according to its content type, with the columns named in the query
+    let content_type = request_content_type(&req);
+    let column_mapping = upload::column_mapping(req.query());
+
+    //in strict mode a single rejected row fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //sites within the tolerance of a query site are matched too
+    let tolerance;
+    match tolerance_param(&req) {
+        Ok(value) => {tolerance = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the body according to its content type
+            let rows;
+            match upload::parse_query_substrates(&content_type,&column_mapping,&body_bytes) {
+                Ok(val) => {rows = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
 
 
-    req.concat2()
-        .from_err()
-        .and_then(move |body_bytes| {
-            
             let conn;
-            match conn_result  {
+            match database::get_connection(&db_pool) {
                 Ok(val) => {conn = val},
-                Err(error) => {return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));},
+                Err(error) => {return Ok(problem::response(&context,&error));},
             }
-            info!("Got database connection");
 
-            //read the bytes into str
-            let body_str;
-            let body_read_result = str::from_utf8(&body_bytes);
-            match body_read_result {
-                Ok(val) => {body_str = val},
-                Err(error) => {return Ok(HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));}
+            //check the rows, keeping the valid ones
+            let query_substrates;
+            let report;
+            match validation::validate(&**conn,rows,strict) {
+                Ok((valid_substrates,validation_report)) => {
+                    query_substrates = valid_substrates;
+                    report = validation_report;
+                },
+                Err(error) => {return Ok(problem::response(&context,&error));}
             }
 
-            info!("Got raw srtring");
+            //get the ptm enzymes
+            let ptm_ppis_result = conn.get_ptm_ppi(&query_substrates,tolerance);
 
-            //parse the string
-            let query_substrates: Vec<QuerySubstrate>;
-            match serde_json::from_str(body_str) {
-                Ok(val) => {query_substrates = val},
-                Err(error) => {return Ok(HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));}
+            match ptm_ppis_result {
+                Ok(ptm_ppis) => {
+                    return Ok(render::batch_response(&context,&ptm_ppis,&report,format));
+                },
+                Err(error) => {
+                    return Ok(problem::response(&context,&error));
+                }
             }
+        }).responder()
+}
 
-            info!("parsed srtring");
+pub fn batch_info_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
 
-            //get the ptm enzymes
-            let ptm_enzymes_result = database::get_ptm_enzymes(&query_substrates,&conn);
-
-            info!("Got enzymes");
-
-            match ptm_enzymes_result {
-                Ok(ptm_enzymes) => {
-
-                    if content_header == "application/json"{
-                        info!("Serializing");
-                        let ptm_enzymes_serialized_result = serde_json::to_string_pretty(&ptm_enzymes);
-                        match ptm_enzymes_serialized_result {
-                            Ok(ptm_enzymes_serialized) => {
-                                info!("returned data");
-                                return Ok(HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "application/json").body(ptm_enzymes_serialized));    
-                            },
-                            Err(error) => {
-                                error!("{}",error);
-                                return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                            }
-                        }
-                    }else if content_header == "text/plain" {
-                            //convert the values to flat structure
-                            let batch_ptm_enzymes_flat = flatten::batch_ptm_enzymes(&ptm_enzymes);
-
-                            let mut wtr = csv::Writer::from_writer(vec![]);
-                            
-                            for batch_ptm_enzyme_flat in batch_ptm_enzymes_flat {
-                                let result = wtr.serialize(&batch_ptm_enzyme_flat);
-                                match result {
-                                    Ok(_) => {},
-                                    Err(error) => {
-                                        error!("{}",error);
-                                        return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                                    }
-                                }
-                            }
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
 
-                            let inner;
-                            let inner_result = wtr.into_inner();
-                            match inner_result {
-                                Ok(value) => {inner=value;},
-                                Err(error) => {
-                                    error!("{}",error);
-                                    return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                                }
-                            }
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let content_type = request_content_type(&req);
 
-                            let data_result = String::from_utf8(inner);
-                            match data_result {
-                                Ok(data) => {
-                                    return Ok(HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(data));
-                                },
-                                Err(error) => {
-                                    error!("{}",error);
-                                    return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                                }
-                            }                 
-                    }else {
-                        return Ok(HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header)));
+    //in strict mode a single unresolved id fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the list of ids
+            let ids;
+            match upload::parse_id_list(&content_type,&body_bytes) {
+                Ok(val) => {ids = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            let conn;
+            match database::get_connection(&db_pool) {
+                Ok(val) => {conn = val},
+                Err(error) => {return Ok(problem::response(&context,&error));},
+            }
+
+            //get the info of every id at once
+            let mut unique_ids: Vec<String> = ids.iter().filter(|id| !id.is_empty()).cloned().collect();
+            unique_ids.sort();
+            unique_ids.dedup();
+            let mut infos_by_id: HashMap<String,Info> = HashMap::new();
+            match conn.get_infos(&unique_ids) {
+                Ok(infos) => {
+                    for info in infos {
+                        infos_by_id.insert(info.uniprot_ac.clone(),info);
                     }
                 },
-                Err(error) => {
-                    error!("{}",error);
-                    return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            //the ids without an entry are reported as rejected rows
+            let resolved_ids;
+            let report;
+            match validation::check_ids(ids,strict,&|id| infos_by_id.contains_key(id)) {
+                Ok((checked_ids,validation_report)) => {
+                    resolved_ids = checked_ids;
+                    report = validation_report;
+                },
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            //the infos in the order of the ids
+            let mut infos: Vec<Info> = Vec::new();
+            for id in resolved_ids {
+                match infos_by_id.remove(&id) {
+                    Some(info) => {
+                        infos.push(info);
+                    },
+                    None => {}
                 }
-            }            
+            }
+
+            return Ok(render::batch_response(&context,&infos,&report,format));
+        }).responder()
+}
 
+pub fn id_mapping_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
 
-    })
-    .responder()
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let content_type = request_content_type(&req);
+
+    //only the entries of these organisms are mapped to, if any
+    let organism_taxon_codes;
+    match misc::get_vec_i32_from_param(req.query(),"organism") {
+        Ok(value) => {organism_taxon_codes = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the list of tokens
+            let tokens;
+            match upload::parse_id_list(&content_type,&body_bytes) {
+                Ok(val) => {tokens = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            let conn;
+            match database::get_connection(&db_pool) {
+                Ok(val) => {conn = val},
+                Err(error) => {return Ok(problem::response(&context,&error));},
+            }
+
+            //the entries any of the tokens could name, each token looked up once
+            let mut unique_tokens: Vec<String> = tokens.iter().filter(|token| !token.is_empty()).map(|token| token.to_uppercase()).collect();
+            unique_tokens.sort();
+            unique_tokens.dedup();
+            let candidates;
+            match conn.get_mapping_candidates(&unique_tokens) {
+                Ok(value) => {candidates = value},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            let id_mappings = id_mapping::map_ids(&tokens,candidates,&organism_taxon_codes);
+            return Ok(render::response(&context,&id_mappings,format));
+        }).responder()
 }
 
-pub fn batch_ptm_ppi_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
-
-    //get the connection
-    let conn_result = database::connect(&req.state().db_params);
-
-    req.concat2()
-        .from_err()
-        .and_then(move |body_bytes| {
-            //read the bytes into str
-            let body_str;
-            let body_read_result = str::from_utf8(&body_bytes);
-            match body_read_result {
-                Ok(val) => {body_str = val},
-                Err(error) => {return Ok(HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));}
+pub fn pmid_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //get the value of PMID
+    let pmid: String = req.match_info().query("pmid").unwrap();
+    if !validation::is_pmid(&pmid) {
+        return problem::bad_request(&context,format!("{pmid} is not a PMID",pmid=pmid));
+    }
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //get the connection from pool
+    let conn;
+    match database::get_connection(&req.state().db_pool) {
+        Ok(val) => {conn = val},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //get the records citing the pmid
+    let pmids = vec![pmid.clone()];
+    let cited_records;
+    match conn.get_cited_records(&pmids) {
+        Ok(value) => {cited_records = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    match publication::publications(&pmids,cited_records).pop() {
+        Some(ref cited_publication) if !publication::is_empty(cited_publication) => {
+            return render::response(&context,cited_publication,format);
+        },
+        _ => {
+            return problem::not_found(&context,format!("No record cites PMID {pmid}",pmid=pmid));
+        }
+    }
+}
+
+pub fn batch_pmid_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let content_type = request_content_type(&req);
+
+    //in strict mode a single malformed pmid fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
             }
 
-            //parse the string
-            let query_substrates: Vec<QuerySubstrate>;
-            match serde_json::from_str(body_str) {
-                Ok(val) => {query_substrates = val},
-                Err(error) => {return Ok(HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));}
+            //parse the list of pmids
+            let pmids;
+            match upload::parse_id_list(&content_type,&body_bytes) {
+                Ok(val) => {pmids = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
             }
 
+            //the malformed pmids are reported as rejected rows
+            let checked_pmids;
+            let report;
+            match validation::check_pmids(pmids,strict) {
+                Ok((value,validation_report)) => {
+                    checked_pmids = value;
+                    report = validation_report;
+                },
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
 
             let conn;
-            match conn_result  {
+            match database::get_connection(&db_pool) {
                 Ok(val) => {conn = val},
-                Err(error) => {return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));},
+                Err(error) => {return Ok(problem::response(&context,&error));},
             }
 
-            //get the ptm enzymes
-            let ptm_ppis_result = database::get_ptm_ppi(&query_substrates,&conn);
+            //get the records citing any of the pmids at once
+            let cited_records;
+            match conn.get_cited_records(&checked_pmids) {
+                Ok(value) => {cited_records = value},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
 
-            match ptm_ppis_result {
-                Ok(ptm_ppis) => {
+            let publications = publication::publications(&checked_pmids,cited_records);
+            return Ok(render::batch_response(&context,&publications,&report,format));
+        }).responder()
+}
 
-                    if content_header == "application/json" {
-                        let ptm_ppis_serialized_result = serde_json::to_string_pretty(&ptm_ppis);
-                        match ptm_ppis_serialized_result {
-                            Ok(ptm_ppis_serialized) => {
-                                return Ok(HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "application/json").body(ptm_ppis_serialized));    
-                            },
-                            Err(error) => {
-                                return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                            }
-                        }
-                    }else if content_header == "text/plain"{
-                            //convert the values to flat structure
-                            let batch_ptm_ppis_flat = flatten::batch_ptm_ppi(&ptm_ppis);
-
-                            let mut wtr = csv::Writer::from_writer(vec![]);
-                            
-                            for batch_ptm_ppis_flat in batch_ptm_ppis_flat {
-                                let result = wtr.serialize(&batch_ptm_ppis_flat);
-                                match result {
-                                    Ok(_) => {},
-                                    Err(error) => {
-                                        error!("{}",error);
-                                        return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                                    }
-                                }
-                            }
+pub fn batch_enzyme_substrates_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
 
-                            let inner;
-                            let inner_result = wtr.into_inner();
-                            match inner_result {
-                                Ok(value) => {inner=value;},
-                                Err(error) => {
-                                    error!("{}",error);
-                                    return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                                }
-                            }
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
 
-                            let data_result = String::from_utf8(inner);
-                            match data_result {
-                                Ok(data) => {
-                                    return Ok(HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(data));
-                                },
-                                Err(error) => {
-                                    error!("{}",error);
-                                    return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
-                                }
-                            }  
-                    }else {
-                        return Ok(HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header)));
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let streams = req.state().streams.clone();
+    let content_type = request_content_type(&req);
+
+    //in strict mode a single rejected id fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //the substrate sites are limited to these ptm types, if any
+    let ptm_types;
+    match ptm_types_param(&req) {
+        Ok(value) => {ptm_types = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the list of enzymes
+            let ids;
+            match upload::parse_id_list(&content_type,&body_bytes) {
+                Ok(val) => {ids = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            let conn;
+            match database::get_connection(&db_pool) {
+                Ok(val) => {conn = val},
+                Err(error) => {return Ok(problem::response(&context,&error));},
+            }
+
+            //check the ids, keeping the known ones
+            let enzyme_ids;
+            let report;
+            match validation::validate_ids(&**conn,ids,strict) {
+                Ok((valid_ids,validation_report)) => {
+                    enzyme_ids = valid_ids;
+                    report = validation_report;
+                },
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            //stream the substrate sites as they are read
+            return Ok(stream::batch_response(&context,&streams,&mut HttpResponse::Ok(),format,report,move |row_writer| {
+                row_writer.columns::<BatchPTMEnzyme>()?;
+                return conn.enzyme_substrates_each(&enzyme_ids,&ptm_types,&mut |ptm_enzyme| {
+                    return row_writer.write(&ptm_enzyme);
+                });
+            }));
+        })
+    .responder()
+}
+
+pub fn ksea_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let content_type = request_content_type(&req);
+
+    //in strict mode a single rejected row fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //sites within the tolerance of a query site are matched too
+    let tolerance;
+    match tolerance_param(&req) {
+        Ok(value) => {tolerance = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    let options;
+    match ksea_options(&req) {
+        Ok(value) => {options = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the sites and their fold changes
+            let quantified_rows;
+            match upload::parse_quantified_sites(&content_type,&body_bytes) {
+                Ok(val) => {quantified_rows = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            //a repeated site keeps the fold change of its first row, like it is looked up once
+            let mut fold_changes: HashMap<analysis::QueryKey,f64> = HashMap::new();
+            let mut rows: Vec<upload::InputRow> = Vec::new();
+            for quantified_row in quantified_rows {
+                match quantified_row {
+                    Ok((query_substrate,fold_change)) => {
+                        fold_changes.entry(analysis::query_key(&query_substrate)).or_insert(fold_change);
+                        rows.push(Ok(query_substrate));
+                    },
+                    Err(rejected_row) => {
+                        rows.push(Err(rejected_row));
                     }
+                }
+            }
+
+            let conn;
+            match database::get_connection(&db_pool) {
+                Ok(val) => {conn = val},
+                Err(error) => {return Ok(problem::response(&context,&error));},
+            }
+
+            //check the rows, keeping the valid ones
+            let query_substrates;
+            let report;
+            match validation::validate(&**conn,rows,strict) {
+                Ok((valid_substrates,validation_report)) => {
+                    query_substrates = valid_substrates;
+                    report = validation_report;
+                },
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            //get the enzymes of the sites
+            let ptm_enzymes;
+            match conn.get_ptm_enzymes(&query_substrates,tolerance) {
+                Ok(value) => {ptm_enzymes = value},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            match analysis::ksea(&query_substrates,&fold_changes,ptm_enzymes,&options) {
+                Ok(activities) => {
+                    return Ok(render::batch_response(&context,&activities,&report,format));
                 },
                 Err(error) => {
-                    return Ok(HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error)));
+                    return Ok(problem::response(&context,&error));
                 }
             }
+        }).responder()
+}
+
+pub fn ptm_enrichment_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //get the connection pool and the body size limit
+    let db_pool = req.state().db_pool.clone();
+    let body_limit = req.state().body_limit;
+    let content_type = request_content_type(&req);
+
+    //the background is restricted to the substrates of these organisms, if any
+    let organism_taxon_codes;
+    match misc::get_vec_i32_from_param(req.query(),"organism") {
+        Ok(value) => {organism_taxon_codes = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the list of proteins
+            let ids;
+            match upload::parse_id_list(&content_type,&body_bytes) {
+                Ok(val) => {ids = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            let conn;
+            match database::get_connection(&db_pool) {
+                Ok(val) => {conn = val},
+                Err(error) => {return Ok(problem::response(&context,&error));},
+            }
+
+            //the ptm labels of the proteins of the list, each looked up once
+            let mut unique_ids: Vec<String> = ids.iter().filter(|id| !id.is_empty()).cloned().collect();
+            unique_ids.sort();
+            unique_ids.dedup();
+            let entry_labels;
+            match conn.get_substrate_labels(&unique_ids,&organism_taxon_codes) {
+                Ok(value) => {entry_labels = value},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
 
+            //the ptm labels of the background
+            let label_counts;
+            match conn.get_substrate_label_counts(&organism_taxon_codes) {
+                Ok(value) => {label_counts = value},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            match analysis::ptm_enrichment(&ids,entry_labels,label_counts) {
+                Ok((enrichments,report)) => {
+                    return Ok(render::batch_response(&context,&enrichments,&report,format));
+                },
+                Err(error) => {
+                    return Ok(problem::response(&context,&error));
+                }
+            }
         }).responder()
 }
 
-pub fn get_statistics_controller(_req: HttpRequest<super::State>) -> HttpResponse {
+pub fn get_statistics_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //Open the statistics file
     let mut statistics_file;
     match File::open("static/statistics.json") {
@@ -1297,7 +1303,7 @@ pub fn get_statistics_controller(_req: HttpRequest<super::State>) -> HttpRespons
         },
         Err(error) => {
             error!("{}",error);
-            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
+            return problem::internal(&context,error);
         }
     }
 
@@ -1308,7 +1314,7 @@ pub fn get_statistics_controller(_req: HttpRequest<super::State>) -> HttpRespons
         },
         Err(error) => {
             error!("{}",error);
-            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
+            return problem::internal(&context,error);
         }
     }
 
@@ -1316,25 +1322,44 @@ pub fn get_statistics_controller(_req: HttpRequest<super::State>) -> HttpRespons
 }
 
 pub fn get_msa_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
+    //the alignment is only available as json
+    let format;
+    match negotiation::negotiate_json(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError().force_close().body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id string
-    let sequences_result = database::get_sequences(&id,&conn);
+    let sequences_result = conn.get_sequences(&id);
+
+    //the connection goes back to the pool while muscle aligns, decorate checks out its own
+    drop(conn);
 
     match sequences_result {
         Ok(sequences) => {
             let alignment_result = msa::align(&sequences);
             match alignment_result {
                 Ok(alignment) => {
-                    let decorate_result = msa::decorate(&id,&alignment,(&req.state().db_params).clone());
+                    let decorate_result = msa::decorate(&id,&alignment,&req.state().db_pool);
                     match decorate_result {
                         Ok(alignment_decorated) => {
                             let alignment_serialized_result = serde_json::to_string(&alignment_decorated);
@@ -1342,131 +1367,461 @@ pub fn get_msa_controller(req: HttpRequest<super::State>) -> HttpResponse {
                                 Ok(alignment_serialized) => {
                                     return HttpResponse::Ok()
                                     .force_close()
-                                    .header(http::header::CONTENT_TYPE, "application/json")
+                                    .header(http::header::CONTENT_TYPE, format.content_type())
                                     .body(alignment_serialized);
                                 },
                                 Err(error) => {
-                                    let error_msg = format!("{}",error);
-                                    return HttpResponse::Ok()
-                                    .force_close()
-                                    .header(http::header::CONTENT_TYPE, "application/json")
-                                    .body(error_msg);        
+                                    return problem::internal(&context,error);
                                 }
                             }
                         },
                         Err(error) => {
-                            let error_msg = format!("{}",error);
-                            return HttpResponse::Ok()
-                                    .force_close()
-                                    .header(http::header::CONTENT_TYPE, "application/json")
-                                    .body(error_msg);
+                            return problem::response(&context,&error);
                         }
                     }
                 },
                 Err(error) => {
-                    let error_msg = format!("{}",error);
-                    return HttpResponse::InternalServerError()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "application/json")
-                    .body(error_msg);
+                    return problem::response(&context,&error);
                 }
             }
             
         },
         Err(error) => {
-            let error_msg = format!("{}",error);
-            return HttpResponse::InternalServerError()
-                    .force_close()
-                    .header(http::header::CONTENT_TYPE, "application/json")
-                    .body(error_msg);       
+            return problem::response(&context,&error);
         },
     }
 
 }
 
 pub fn get_variants(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
     //get the value of ID
     let id: String  = req.match_info().query("id").unwrap();
 
-    //get content header
-    let content_header = misc::get_accept_header_value(&req);
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
 
     //get the connection from pool
     let conn;
-    match database::connect(&req.state().db_params) {
+    match database::get_connection(&req.state().db_pool) {
         Ok(val) => {conn = val},
-        Err(error) => {return HttpResponse::InternalServerError().header(http::header::CONTENT_TYPE, "text/plain").force_close().body(format!("{}",error));},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
     }
 
     //get the id strings
-    let variant_result = database::get_variants(&id,&conn);
+    let variant_result = conn.get_variants(&id);
 
     //check if the operation was successful
     match variant_result {
         Ok(variants) => {
-            
-            if content_header == "application/json"{
-                //try deserializing    
-                let variants_serialized_result = serde_json::to_string_pretty(&variants);
+            return render::response(&context,&variants,format);
+        },
+        Err(error) => {
+            return problem::response(&context,&error);
+        }
+    }
+}
 
-                //check if operation was successful
-                match variants_serialized_result {
+pub fn site_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
 
-                    Ok(proteoforms_serialized) => {
-                        HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(proteoforms_serialized)
-                    },
+    //get the value of ID and of the site
+    let id: String  = req.match_info().query("id").unwrap();
+    let site_str: String = req.match_info().query("site").unwrap();
 
-                    Err(error) => {
-                        HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
-                    }
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //read the residue and position of the site
+    let residue;
+    let position;
+    match validation::parse_site(&site_str) {
+        Ok((residue_value,position_value)) => {
+            residue = residue_value;
+            position = position_value;
+        },
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //get the residues taken on each side of the site when looking for variants
+    let variant_window;
+    match variant_window_param(&req) {
+        Ok(value) => {variant_window = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //get the connection from pool
+    let conn;
+    match database::get_connection(&req.state().db_pool) {
+        Ok(val) => {conn = val},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that the id is known
+    match conn.check_entry(&id) {
+        Ok(_) => {},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //gather the site record
+    match site::site_record(&**conn,&id,&residue,position,variant_window) {
+        Ok(site_record) => {
+            return render::response(&context,&site_record,format);
+        },
+        Err(error) => {
+            return problem::response(&context,&error);
+        }
+    }
+}
+
+pub fn network_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //negotiate the export format
+    let format;
+    match negotiation::negotiate_network(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //get the seeds, each seed parameter may hold a comma separated list
+    let mut seeds: Vec<String> = Vec::new();
+    for seed_param in misc::get_vec_str_from_param(req.query(),"seed") {
+        for seed in seed_param.split(",") {
+            let seed = String::from(seed.trim());
+            if !seed.is_empty() && !seeds.contains(&seed) {
+                seeds.push(seed);
+            }
+        }
+    }
+    if seeds.is_empty() {
+        return problem::bad_request(&context,String::from("At least one seed is required"));
+    }
+
+    //get the depth, ptm types and score of the network
+    let options;
+    match network_options(&req) {
+        Ok(value) => {options = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //get the connection from pool
+    let conn;
+    match database::get_connection(&req.state().db_pool) {
+        Ok(val) => {conn = val},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //check that every seed is known, then write it as the accessions of the nodes are written
+    let mut seed_ids: Vec<String> = Vec::new();
+    for seed in &seeds {
+        match conn.check_entry(seed) {
+            Ok(_) => {},
+            Err(error) => {return problem::response(&context,&error);},
+        }
+        let seed_id = seed.trim().to_uppercase();
+        if !seed_ids.contains(&seed_id) {
+            seed_ids.push(seed_id);
+        }
+    }
+
+    //build the network
+    let network_value;
+    match network::build_network(&**conn,&seed_ids,&options) {
+        Ok(value) => {network_value = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //export it in the negotiated format
+    let body;
+    match format {
+        negotiation::NetworkFormat::Cytoscape => {
+            match network::to_cytoscape(&network_value) {
+                Ok(value) => {body = value},
+                Err(error) => {return problem::response(&context,&error);},
+            }
+        },
+        negotiation::NetworkFormat::Graphml => {
+            body = network::to_graphml(&network_value);
+        },
+        negotiation::NetworkFormat::Sif => {
+            body = network::to_sif(&network_value);
+        }
+    }
+
+    return HttpResponse::Ok()
+            .force_close()
+            .header(http::header::CONTENT_TYPE, format.content_type())
+            .body(body);
+}
+
+pub fn submit_ptm_enzymes_job_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    return submit_job(req,JobKind::PtmEnzymes);
+}
+
+pub fn submit_ptm_ppi_job_controller(req: HttpRequest<super::State>) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    return submit_job(req,JobKind::PtmPpi);
+}
 
+//queues a job for the query substrates in the body, answering 202 with the job and its location
+fn submit_job(req: HttpRequest<super::State>, kind: JobKind) -> Box<Future<Item=HttpResponse, Error=Error>> {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //get the job queue and the body size limit
+    let job_queue = req.state().jobs.clone();
+    let body_limit = req.state().body_limit;
+
+    //the body is read according to its content type, with the columns named in the query
+    let content_type = request_content_type(&req);
+    let column_mapping = upload::column_mapping(req.query());
+
+    //in strict mode a single rejected row fails the request
+    let strict;
+    match misc::get_bool_from_param(req.query(),"strict") {
+        Ok(value) => {strict = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    //sites within the tolerance of a query site are matched too
+    let tolerance;
+    match tolerance_param(&req) {
+        Ok(value) => {tolerance = value},
+        Err(error) => {return Box::new(future::ok(problem::response(&context,&error)));},
+    }
+
+    req.body()
+        .limit(body_limit)
+        .then(move |body_result| -> Result<HttpResponse,Error> {
+            //payload errors, like a body over the size limit, are rendered as problems too
+            let body_bytes;
+            match body_result {
+                Ok(value) => {body_bytes = value},
+                Err(error) => {return Ok(problem::payload_error(&context,&error));}
+            }
+
+            //parse the body according to its content type, the rows are checked when the job starts
+            let rows;
+            match upload::parse_query_substrates(&content_type,&column_mapping,&body_bytes) {
+                Ok(val) => {rows = val},
+                Err(error) => {return Ok(problem::response(&context,&error));}
+            }
+
+            match job_queue.submit(kind,rows,strict,tolerance) {
+                Ok(job) => {
+                    return Ok(job_response(&context,HttpResponse::Accepted().header(http::header::LOCATION, format!("/v1/jobs/{id}",id=job.id)),&job));
+                },
+                Err(error) => {
+                    return Ok(problem::response(&context,&error));
                 }
-            } else if content_header =="text/plain" {
-                    //convert the values to flat structure
-
-                    let mut wtr = csv::Writer::from_writer(vec![]);
-                    
-                    for variant_flat in variants {
-                        let result = wtr.serialize(&variant_flat);
-                        match result {
-                            Ok(_) => {},
-                            Err(error) => {
-                                error!("{}",error);
-                                return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                            }
-                        }
-                    }
+            }
+        }).responder()
+}
 
-                    let inner;
-                    let inner_result = wtr.into_inner();
-                    match inner_result {
-                        Ok(value) => {inner=value;},
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                        }
-                    }
+pub fn get_job_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
 
-                    let data_result = String::from_utf8(inner);
-                    match data_result {
-                        Ok(data) => {
-                            return HttpResponse::Ok().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(data);
-                        },
-                        Err(error) => {
-                            error!("{}",error);
-                            return HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error));
-                        }
-                    }                
-            }else {
-                return HttpResponse::BadRequest().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("Invalid ACCEPT header - {}",content_header));
-            }                 
+    //get the value of ID
+    let id: String  = req.match_info().query("id").unwrap();
+
+    match req.state().jobs.get(&id) {
+        Ok(job) => {
+            return job_response(&context,&mut HttpResponse::Ok(),&job);
+        },
+        Err(error) => {
+            return problem::response(&context,&error);
+        }
+    }
+}
+
+pub fn cancel_job_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //get the value of ID
+    let id: String  = req.match_info().query("id").unwrap();
+
+    match req.state().jobs.cancel(&id) {
+        Ok(job) => {
+            return job_response(&context,&mut HttpResponse::Ok(),&job);
+        },
+        Err(error) => {
+            return problem::response(&context,&error);
+        }
+    }
+}
+
+pub fn get_job_result_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    //get the value of ID
+    let id: String  = req.match_info().query("id").unwrap();
+
+    //negotiate the response format
+    let format;
+    match negotiation::negotiate(&req) {
+        Ok(value) => {format = value},
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //only completed jobs have a result
+    let job;
+    let result_path;
+    match req.state().jobs.result(&id) {
+        Ok((value,path)) => {
+            job = value;
+            result_path = path;
+        },
+        Err(error) => {return problem::response(&context,&error);},
+    }
+
+    //stream the saved result, along with the validation report of the rows
+    let kind = job.kind;
+    let report = job.report.unwrap_or_default();
+    return stream::batch_response(&context,&req.state().streams,&mut HttpResponse::Ok(),format,report,move |row_writer| {
+        return jobs::write_result(kind,&result_path,row_writer);
+    });
+}
+
+//the tolerance of a batch lookup, from 0, the default, to MAX_TOLERANCE
+fn tolerance_param(req: &HttpRequest<super::State>) -> ::errors::Result<i64> {
+    let tolerance = misc::get_i64_from_param(req.query(),"tolerance",0)?;
+    if tolerance < 0 || tolerance > database::MAX_TOLERANCE {
+        return Err(::errors::ErrorKind::BadRequest(format!("tolerance must be between 0 and {max_tolerance}, got {tolerance}",max_tolerance=database::MAX_TOLERANCE,tolerance=tolerance)).into());
+    }
+    return Ok(tolerance);
+}
+
+//the variant_window of a site record, from 0 to site::MAX_VARIANT_WINDOW
+fn variant_window_param(req: &HttpRequest<super::State>) -> ::errors::Result<i64> {
+    let variant_window = misc::get_i64_from_param(req.query(),"variant_window",site::DEFAULT_VARIANT_WINDOW)?;
+    if variant_window < 0 || variant_window > site::MAX_VARIANT_WINDOW {
+        return Err(::errors::ErrorKind::BadRequest(format!("variant_window must be between 0 and {max_window}, got {variant_window}",max_window=site::MAX_VARIANT_WINDOW,variant_window=variant_window)).into());
+    }
+    return Ok(variant_window);
+}
+
+//the ptm_type parameters as lower case event names, each one must be known to misc::get_ptm_event_label
+fn ptm_types_param(req: &HttpRequest<super::State>) -> ::errors::Result<Vec<String>> {
+    let mut ptm_types: Vec<String> = Vec::new();
+    for ptm_type in misc::get_vec_str_from_param(req.query(),"ptm_type") {
+        let ptm_type = ptm_type.trim().to_lowercase();
+        if misc::get_ptm_event_label(&ptm_type).is_none() {
+            return Err(::errors::ErrorKind::BadRequest(format!("Unknown ptm_type {ptm_type}, expected one of {ptm_types}",ptm_type=ptm_type,ptm_types=misc::ptm_event_names().join(", "))).into());
+        }
+        if !ptm_types.contains(&ptm_type) {
+            ptm_types.push(ptm_type);
+        }
+    }
+    return Ok(ptm_types);
+}
+
+// The options of a kinase-substrate enrichment analysis. Phosphorylation is used unless another
+// ptm_type, or all, is asked for, and min_score is left out unless given.
+fn ksea_options(req: &HttpRequest<super::State>) -> ::errors::Result<analysis::KseaOptions> {
+    let ptm_type;
+    match req.query().get("ptm_type") {
+        Some(value) => {
+            let value = value.trim().to_lowercase();
+            if value == "all" {
+                ptm_type = None;
+            }else if misc::get_ptm_event_label(&value).is_some() {
+                ptm_type = Some(value);
+            }else{
+                return Err(::errors::ErrorKind::BadRequest(format!("Unknown ptm_type {ptm_type}, expected all or one of {ptm_types}",ptm_type=value,ptm_types=misc::ptm_event_names().join(", "))).into());
+            }
+        },
+        None => {
+            ptm_type = Some(String::from("phosphorylation"));
+        }
+    }
+
+    let min_score;
+    match req.query().get("min_score") {
+        Some(_) => {
+            min_score = Some(misc::get_i64_from_param(req.query(),"min_score",0)?);
+        },
+        None => {
+            min_score = None;
+        }
+    }
 
+    let min_sites = misc::get_i64_from_param(req.query(),"min_sites",1)?;
+    if min_sites < 1 {
+        return Err(::errors::ErrorKind::BadRequest(format!("min_sites must be at least 1, got {min_sites}",min_sites=min_sites)).into());
+    }
+
+    return Ok(analysis::KseaOptions {
+        ptm_type: ptm_type,
+        min_score: min_score,
+        min_sites: min_sites as usize
+    });
+}
 
+// The options of a network export. The network is expanded once from the seeds unless another
+// depth is asked for, and min_score only applies to enzyme-substrate edges.
+fn network_options(req: &HttpRequest<super::State>) -> ::errors::Result<network::NetworkOptions> {
+    let depth = misc::get_i64_from_param(req.query(),"depth",network::DEFAULT_DEPTH)?;
+    if depth < 1 || depth > network::MAX_DEPTH {
+        return Err(::errors::ErrorKind::BadRequest(format!("depth must be between 1 and {max_depth}, got {depth}",max_depth=network::MAX_DEPTH,depth=depth)).into());
+    }
+
+    return Ok(network::NetworkOptions {
+        depth: depth as usize,
+        ptm_types: ptm_types_param(req)?,
+        min_score: misc::get_i64_from_param(req.query(),"min_score",0)?
+    });
+}
 
+//the full Content-Type header, parameters included, since multipart bodies need their boundary
+fn request_content_type(req: &HttpRequest<super::State>) -> String {
+    match req.headers().get(http::header::CONTENT_TYPE) {
+        Some(value) => {
+            return String::from(value.to_str().unwrap_or(""));
         },
+        None => {
+            return String::new();
+        }
+    }
+}
 
+fn job_response(context: &problem::RequestContext, builder: &mut dev::HttpResponseBuilder, job: &Job) -> HttpResponse {
+    match serde_json::to_string_pretty(job) {
+        Ok(job_serialized) => {
+            return builder
+                    .force_close()
+                    .header(http::header::CONTENT_TYPE, "application/json")
+                    .body(job_serialized);
+        },
         Err(error) => {
-            HttpResponse::InternalServerError().force_close().header(http::header::CONTENT_TYPE, "text/plain").body(format!("{}",error))
+            return problem::internal(context,error);
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn not_found_controller(req: HttpRequest<super::State>) -> HttpResponse {
+    //get the request context used by the error responses
+    let context = problem::context(&req);
+
+    return problem::not_found(&context,format!("No resource matches {path}",path=req.path()));
+}
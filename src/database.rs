@@ -1,12 +1,12 @@
 use models::*;
 use errors::*;
 use misc;
-use postgres;
-use oracle;
+use postgres_backend::PostgresBackend;
+use oracle_backend::OracleBackend;
+use sqlite_backend::SqliteBackend;
 use std::collections::HashMap;
-use std::rc::Rc;
-use std::cell::RefCell;
-use query_builder;
+use std::time::Duration;
+use r2d2;
 
 #[derive(Clone)]
 pub struct DBParams {
@@ -18,12 +18,44 @@ pub struct DBParams {
         pub db_name: String
 }
 
-#[derive(Debug)]
-pub enum Engine {
-    Postgres,
-    Oracle
+#[derive(Clone)]
+pub struct PoolParams {
+        pub min_idle: u32,
+        pub max_size: u32,
+        pub idle_timeout: u64,
+        pub connection_timeout: u64
 }
 
+//number of query substrates sent in a single batch query. Every substrate binds three parameters,
+//which keeps a batch query below the 999 parameter limit of sqlite.
+pub const BATCH_QUERY_SIZE: usize = 300;
+
+//every token of an id mapping is bound three times, so fewer tokens are looked up at once
+pub const MAPPING_QUERY_SIZE: usize = BATCH_QUERY_SIZE / 3;
+
+//every pmid is matched with a pattern against the pmids of all the rows, so few pmids are looked up at once
+pub const CITED_QUERY_SIZE: usize = MAPPING_QUERY_SIZE;
+
+//largest distance in positions allowed between a query site and the sites matched to it. Every
+//query substrate is looked up at 2 * tolerance + 1 positions.
+pub const MAX_TOLERANCE: i64 = 10;
+
+//a site as bound in the batch queries, made of the substrate accession, the residue and the position
+pub type SiteKey = (String,String,i64);
+
+//the records citing any of a list of pmids, as returned by Backend::get_cited_records
+#[derive(Debug, Default)]
+pub struct CitedRecords {
+    pub ptm_events: Vec<PublicationEvent>,
+    pub enzyme_substrates: Vec<BatchPTMEnzyme>,
+    pub proteoforms: Vec<Proteoform>,
+    pub proteoform_ppis: Vec<ProteoformPPI>,
+    pub ptm_ppis: Vec<PTMPPI>,
+}
+
+pub type Pool = r2d2::Pool<ConnectionManager>;
+pub type PooledConnection = r2d2::PooledConnection<ConnectionManager>;
+
 pub trait MyRow<'a>{
     fn get_string(&self,column_name: &str) -> Option<String>;
     fn get_string_unwrapped(&self,column_name: &str) -> String;
@@ -31,344 +63,316 @@ pub trait MyRow<'a>{
     fn get_bool(&self,column_name: &str) -> Option<bool>;
 }
 
-impl<'a> MyRow<'a> for postgres::rows::Row<'a> {
-    fn get_string(&self,column_name: &str) -> Option<String>{
-        return self.get(column_name);
+/// A storage engine able to answer every query the API needs.
+///
+/// It is implemented once for every sql_backend::Connector, each engine providing its connection
+/// and SQL dialect in its own module, while the row builders in this module are shared between all of them.
+pub trait Backend: Send {
+    fn is_valid(&self) -> Result<()>;
+
+    fn has_broken(&self) -> bool {
+        return false;
     }
 
-    fn get_string_unwrapped(&self,column_name: &str) -> String {
-        return self.get(column_name);
+    fn get_info(&self, id: &str) -> Result<Option<Info>>;
+
+    fn get_pro_info(&self, id: &str) -> Result<Option<Pro>>;
+
+    // The info of every id that is an entry, with its PRO info, reading the entries and their PRO
+    // entries in one query for every BATCH_QUERY_SIZE ids. PRO entries asked for are left out.
+    fn get_infos(&self, ids: &Vec<String>) -> Result<Vec<Info>>;
+
+    //the entries that could match the tokens of an id mapping, looked up in batches of MAPPING_QUERY_SIZE tokens
+    fn get_mapping_candidates(&self, tokens: &Vec<String>) -> Result<Vec<MappedEntry>>;
+
+    fn entry_exists(&self, id: &str) -> Result<bool>;
+
+    fn get_entry_candidates(&self, pattern: &str) -> Result<Vec<Suggestion>>;
+
+    //the ids that are known entries, looked up in batches of BATCH_QUERY_SIZE ids
+    fn get_known_entries(&self, ids: &Vec<String>) -> Result<Vec<String>>;
+
+    //the number of substrates for every list of ptm labels (list_as_substrate), among the substrates of the taxons if any are given
+    fn get_substrate_label_counts(&self, organism_taxons: &Vec<i32>) -> Result<Vec<(Option<String>,i64)>>;
+
+    //the ptm labels of the ids that are substrates of the taxons, looked up in batches of BATCH_QUERY_SIZE ids
+    fn get_substrate_labels(&self, ids: &Vec<String>, organism_taxons: &Vec<i32>) -> Result<Vec<(String,Option<String>)>>;
+
+    //fails with ErrorKind::UnknownEntry, carrying the closest known entries, when no entry matches the id
+    fn check_entry(&self, id: &str) -> Result<()> {
+        if self.entry_exists(id)? {
+            return Ok(());
+        }
+        return Err(self.unknown_entry(id));
     }
 
-    fn get_i64(&self,column_name: &str) -> Option<i64>{
-        let value: Option<i64> = self.get(column_name);
-        return value;
+    //the ErrorKind::UnknownEntry of the id, carrying the closest known entries
+    fn unknown_entry(&self, id: &str) -> Error {
+        let mut suggestions: Vec<Suggestion> = Vec::new();
+        match misc::suggestion_pattern(id) {
+            Some(pattern) => {
+                match self.get_entry_candidates(&pattern) {
+                    Ok(candidates) => {
+                        suggestions = misc::rank_suggestions(id,candidates);
+                    },
+                    Err(error) => {
+                        return error;
+                    }
+                }
+            },
+            None => {}
+        }
+
+        return ErrorKind::UnknownEntry(String::from(id),suggestions).into();
     }
 
-    fn get_bool(&self,column_name: &str) -> Option<bool>{
-        return self.get(column_name);
+    fn search_count(&self,
+                    search_term: &str,
+                    term_type: &str,
+                    role: &str,
+                    ptm_types: &Vec<String>,
+                    organism_taxons: &Vec<i32>) -> Result<i64>;
+
+    //hands every search result to handle_item as soon as it is read, without collecting them
+    fn search_each(&self,
+                   search_term: &str,
+                   term_type: &str,
+                   role: &str,
+                   ptm_types: &Vec<String>,
+                   organism_taxons: &Vec<i32>,
+                   paginate: bool,
+                   offset: i32,
+                   limit: i32,
+                   handle_item: &mut FnMut(SearchResult) -> Result<()>) -> Result<()>;
+
+    fn search(&self,
+              search_term: &str,
+              term_type: &str,
+              role: &str,
+              ptm_types: &Vec<String>,
+              organism_taxons: &Vec<i32>,
+              paginate: bool,
+              offset: i32,
+              limit: i32) -> Result<(i64,Vec<SearchResult>)> {
+        let count = self.search_count(search_term,term_type,role,ptm_types,organism_taxons)?;
+
+        let mut search_results: Vec<SearchResult> = Vec::new();
+        self.search_each(search_term,term_type,role,ptm_types,organism_taxons,paginate,offset,limit,&mut |search_result| {
+            search_results.push(search_result);
+            return Ok(());
+        })?;
+
+        return Ok((count,search_results));
     }
-}
 
-impl<'a> MyRow<'a> for oracle::Row {
-    fn get_string(&self,column_name: &str) -> Option<String>{
-        let result = self.get(column_name);
-        match result {
-            Ok(val) => {
-                return Some(val);
-            },
-            Err(_error) => {
-                return None;
-            }
-        };
+    fn get_sub_forms(&self, id: &str) -> Result<Vec<String>>;
+
+    fn get_events_for_sub_form(&self, sub_form: &str) -> Result<(Vec<SubstrateEvent>,HashMap<String,i64>)>;
+
+    fn get_substrate_events(&self, id: &str) -> Result<HashMap<String,Vec<SubstrateEvent>>> {
+        //get a list of forms for the given id
+        let sub_forms = self.get_sub_forms(id)?;
+
+        let mut substrate_events: HashMap<String,Vec<SubstrateEvent>> = HashMap::new();
+
+        for sub_form in sub_forms {
+            let events = self.get_sub_form_events(&sub_form)?;
+            substrate_events.insert(sub_form,events);
+        }
+
+        return Ok(substrate_events);
     }
 
-    fn get_string_unwrapped(&self,column_name: &str) -> String {
-        let result = self.get(column_name);
-        match result {
-            Ok(val) => {
-                return val;
-            },
-            Err(error) => {
-                panic!("{}",error);
-            }
-        };
+    //the scored events of a single form, e.g. Q15796 or its isoform Q15796-2
+    fn get_sub_form_events(&self, sub_form: &str) -> Result<Vec<SubstrateEvent>> {
+        let (mut events, pmid_stats) = self.get_events_for_sub_form(sub_form)?;
+        score_events(&mut events,&pmid_stats)?;
+        return Ok(events);
     }
 
-    fn get_i64(&self,column_name: &str) -> Option<i64>{
-        let result = self.get(column_name);
-        match result {
-            Ok(val) => {
-                return Some(val);
-            },
-            Err(_error) => {
-                return None;
+    fn get_enzyme_events(&self, id: &str) -> Result<Vec<EnzymeEvent>>;
+
+    fn get_proteoforms(&self, id: &str) -> Result<Vec<Proteoform>>;
+
+    fn get_proteoformppis(&self, id: &str) -> Result<Vec<ProteoformPPI>>;
+
+    fn get_ptmppis(&self, id: &str) -> Result<Vec<PTMPPI>>;
+
+    //hands every ptm enzyme with at least one enzyme found at one of the sites to handle_item, along with the site it was found at
+    fn ptm_enzyme_sites_each(&self, substrate_keys: &Vec<SiteKey>, handle_item: &mut FnMut(SiteKey,BatchPTMEnzyme) -> Result<()>) -> Result<()>;
+
+    // Hands every substrate site of the enzymes to handle_item as soon as it is read, once for every
+    // ptm type, limited to the given ptm types (lower case event names) if any. Enzymes are looked up
+    // in batches of BATCH_QUERY_SIZE ids.
+    fn enzyme_substrates_each(&self, enzyme_ids: &Vec<String>, ptm_types: &Vec<String>, handle_item: &mut FnMut(BatchPTMEnzyme) -> Result<()>) -> Result<()>;
+
+    //hands every ptm ppi found at one of the sites to handle_item, along with the site it was found at
+    fn ptm_ppi_sites_each(&self, substrate_keys: &Vec<SiteKey>, handle_item: &mut FnMut(SiteKey,BatchPTMPPI) -> Result<()>) -> Result<()>;
+
+    // Hands every ptm enzyme found within tolerance positions of a query substrate to handle_item as
+    // soon as it is read, once for every query substrate it matches, with the query site and the offset.
+    fn ptm_enzymes_each(&self, query_substrates: &Vec<QuerySubstrate>, tolerance: i64, handle_item: &mut FnMut(BatchPTMEnzyme) -> Result<()>) -> Result<()> {
+        let site_matcher = misc::SiteMatcher::new(query_substrates,tolerance);
+        return self.ptm_enzyme_sites_each(&site_matcher.substrate_keys(),&mut |site_key,ptm_enzyme| {
+            for (query_site,offset) in site_matcher.query_sites(&site_key) {
+                let mut matched_enzyme = ptm_enzyme.clone();
+                matched_enzyme.query_site = Some(query_site);
+                matched_enzyme.offset = Some(offset);
+                handle_item(matched_enzyme)?;
             }
-        };
+            return Ok(());
+        });
     }
 
-    fn get_bool(&self,column_name: &str) -> Option<bool>{
-        let result = self.get(column_name);
-        match result {
-            Ok(val) => {
-                return Some(val);
-            },
-            Err(_error) => {
-                return None;
+    fn get_ptm_enzymes(&self, query_substrates: &Vec<QuerySubstrate>, tolerance: i64) -> Result<Vec<BatchPTMEnzyme>> {
+        let mut ptm_enzymes: Vec<BatchPTMEnzyme> = Vec::new();
+        self.ptm_enzymes_each(query_substrates,tolerance,&mut |ptm_enzyme| {
+            ptm_enzymes.push(ptm_enzyme);
+            return Ok(());
+        })?;
+        return Ok(ptm_enzymes);
+    }
+
+    fn get_ptm_ppi(&self, query_substrates: &Vec<QuerySubstrate>, tolerance: i64) -> Result<Vec<BatchPTMPPI>> {
+        let site_matcher = misc::SiteMatcher::new(query_substrates,tolerance);
+
+        let mut ptm_ppis: Vec<BatchPTMPPI> = Vec::new();
+        self.ptm_ppi_sites_each(&site_matcher.substrate_keys(),&mut |site_key,ptm_ppi| {
+            for (query_site,offset) in site_matcher.query_sites(&site_key) {
+                let mut matched_ppi = ptm_ppi.clone();
+                matched_ppi.query_site = Some(query_site);
+                matched_ppi.offset = Some(offset);
+                ptm_ppis.push(matched_ppi);
             }
-        };
+            return Ok(());
+        })?;
+        return Ok(ptm_ppis);
     }
-}
 
+    fn get_sequences(&self, id: &str) -> Result<Vec<Sequence>>;
 
-pub struct Connection {
-    pub engine: Engine,
-    pub pg_conn: Option<postgres::Connection>,
-    pub oracle_conn: Option<oracle::Connection>
-}
+    //the sequences of the entries and of their isoforms, looked up in batches of BATCH_QUERY_SIZE accessions
+    fn get_entry_sequences(&self, accessions: &Vec<String>) -> Result<Vec<Sequence>>;
 
-pub fn connect(db_params: &DBParams) -> Result<Connection> {
-    if db_params.engine == "oracle" {
-        let connect_string = format!("{host}:{port}/{db_name}",host=db_params.host,port=db_params.port,db_name=db_params.db_name);
-        let connect_result = oracle::Connection::connect(&db_params.user, &db_params.pass, &connect_string, &[]);
-        match connect_result {
-            Ok(conn) => {
-                let connection = Connection {
-                    engine: Engine::Oracle,
-                    pg_conn: None,
-                    oracle_conn: Some(conn)
-                };
-                return Ok(connection);
-            },
-            Err(error) => {
-                error!("{}",error);
-                error!("Connect string - {}",connect_string);
-                return Err(format!("{}",error).into());
-            }
+    //the decorations of every site of the form, one for every event name, not yet marked conserved
+    fn get_site_decorations(&self, form_id: &str) -> Result<Vec<(i64,String,Decoration)>>;
+
+    //the positions and residues of the entry having at least one event
+    fn get_decorated_sites(&self, id: &str) -> Result<Vec<(i64,String)>>;
+
+    // The decorations of every site of the form, keyed by position and residue. A site is conserved
+    // when the entry has an event at the same position and residue, and a conserved site without
+    // decoration of its own gets an empty one. Two queries are run for the whole form, so that an
+    // alignment is decorated without a query for each of its residues.
+    fn get_decorations(&self, id: &str, form_id: &str) -> Result<HashMap<(i64,String),Vec<Decoration>>> {
+        let conserved_sites = self.get_decorated_sites(id)?;
+
+        let mut decorations: HashMap<(i64,String),Vec<Decoration>> = HashMap::new();
+        for (position,residue,mut decoration) in self.get_site_decorations(form_id)? {
+            let site_key = (position,residue);
+            decoration.is_conserved = conserved_sites.contains(&site_key);
+            decorations.entry(site_key).or_insert(Vec::new()).push(decoration);
         }
-    }else{
-        let connect_string = format!("postgres://{user}:{pass}@{host}:{port}/{database}",user=db_params.user,
-                                     pass=db_params.pass,
-                                     host=db_params.host,
-                                     port=db_params.port,
-                                     database=db_params.db_name
-        );
-        let connect_result = postgres::Connection::connect(connect_string.clone(),postgres::TlsMode::None);
-        match connect_result {
-            Ok(conn) => {
-                let connection = Connection {
-                    engine: Engine::Postgres,
-                    pg_conn: Some(conn),
-                    oracle_conn: None
+
+        for site_key in conserved_sites {
+            if !decorations.contains_key(&site_key) {
+                let decoration = Decoration {
+                    ptm_type: Some(String::from("")),
+                    source: Vec::new(),
+                    pmids: Vec::new(),
+                    is_conserved: true
                 };
-                return Ok(connection);
-            },
-            Err(error) => {
-                error!("{}",error);
-                error!("Connect string - {}",connect_string);
-                return Err(format!("{}",error).into());
+                decorations.insert(site_key,vec![decoration]);
             }
         }
+
+        return Ok(decorations);
     }
-}
 
-macro_rules! execute_query {
-    ($builder_func:ident,$conn:ident,$query_str:ident,$params:expr) => (
-            match $conn.engine {
-                Engine::Postgres => {
-                    match $conn.pg_conn {
-                        Some(ref pg_conn) => {
-                            let rows_result = pg_conn.query(&$query_str,$params);
-                            match rows_result {
-                                Ok(rows) => {
-                                    //check if we have any results
-                                    if !rows.is_empty() {
-                                        //get the first row
-                                        let row = rows.get(0);
-                                        let value = $builder_func(&row)?;
-                                        Ok(Some(value))
-                                    }else{
-                                        Ok(None)
-                                    }
-                                },
-                                Err(ref error) => {
-                                    Err(format!("{}",error).into())
-                                }
-                            }
-                        },
-                        None => {
-                            //Invalid database engine
-                            let message = String::from("Postgres engine is None");
-                            error!("{}",message);
-                            Err(message.into())
-                        }
-                    }
-                },
+    fn get_variants(&self, id: &str) -> Result<Vec<Variant>>;
 
-                Engine::Oracle => {
-                    match $conn.oracle_conn {
-                        Some(ref oracle_conn) => {
-                            let query_result = oracle_conn.query(&$query_str,$params);
-                            match query_result {
-                                Ok(rows_result) => {
-                                    for row_result in rows_result {
-                                        match row_result {
-                                            Ok(row) => {
-                                                let info = $builder_func(&row)?;
-                                                return Ok(Some(info));
-                                            },
-                                            Err(error) => {
-                                                error!("{}",error);
-                                                return Err(format!("{}",error).into());
-                                            }
-                                        }
-                                    }
-
-                                    //if we have reached here, then that means row count was zero so we return error
-                                    Ok(None)
-
-                                },
-                                Err(error) => {
-                                    Err(format!("{}",error).into())
-                                }
-                            }
-                        },
-                        None => {
-                            //Invalid database engine
-                            let message = String::from("Oracle engine is None");
-                            error!("{}",message);
-                            Err(message.into())
-                        }
-                    }
+    //the ptm events, enzyme-substrate relations, proteoforms and ppis citing any of the pmids, looked up in batches of CITED_QUERY_SIZE pmids
+    fn get_cited_records(&self, pmids: &Vec<String>) -> Result<CitedRecords>;
 
-                }
-            }
-    )
+    //the enzyme-substrate relations where the enzyme or the substrate is one of the ids, of the ptm types if any are given, looked up in batches of BATCH_QUERY_SIZE ids
+    fn get_enzyme_relations(&self, ids: &Vec<String>, ptm_types: &Vec<String>) -> Result<Vec<BatchPTMEnzyme>>;
+
+    //the ptm dependent ppis where either partner is one of the ids, looked up in batches of BATCH_QUERY_SIZE ids
+    fn get_ptm_ppi_relations(&self, ids: &Vec<String>) -> Result<Vec<BatchPTMPPI>>;
 }
 
-macro_rules! execute_query_bulk {
-    ($build_item:ident, $conn:ident,$query_str:ident,$list:ident,$params:expr) => (
-            match $conn.engine {
-                Engine::Postgres => {
-
-                    match $conn.pg_conn {
-                        Some(ref pg_conn) => {
-                            //execute the query                    
-                            let rows_result = pg_conn.query(&$query_str,$params);
-                            match rows_result {
-                                Ok(rows) => {
-                                    for row in rows.iter() {
-                                        //bult the item from row
-                                        
-                                        let item = $build_item(&row)?;
-                                        $list.push(item);
-                                    }
-                                },
-                                Err(error) => {
-                                    return Err(format!("{}",error).into());
-                                }
-                            };
-                        },
-                        None => {
-                            let message = String::from("Postgres engine is None");
-                            error!("{}",message);
-                            return Err(message.into());
-                        }
-                    }
-                },
-                Engine::Oracle => {
-                    match $conn.oracle_conn {
-                        Some(ref oracle_conn) => {
-                            //execute the query
-                            let query_result = oracle_conn.query(&$query_str,$params);
-                            match query_result {
-                                Ok(rows_result) => {
-                                    for row_result in rows_result {
-                                        match row_result {
-                                            Ok(row) => {
-                                                // Get the item from the row
-                                                let item = $build_item(&row)?;
-                                                $list.push(item);
-                                            },
-                                            Err(error) => {
-                                                error!("{}",error);
-                                                return Err(format!("{}",error).into());
-                                            }
-                                        }
-                                    };
-
-                                },
-                                Err(error) => {
-                                    return Err(format!("{}",error).into());
-                                }
-                            }
-                        },
-                        None => {
-                            //Invalid database engine
-                            let message = String::from("Oracle engine is None");
-                            error!("{}",message);
-                            return Err(message.into());
-                        }
-                    }
-                }
-            }
-    );
+pub type Connection = Box<Backend>;
+
+pub fn connect(db_params: &DBParams) -> Result<Connection> {
+    if db_params.engine == "oracle" {
+        let backend = OracleBackend::connect(db_params)?;
+        return Ok(Box::new(backend));
+    }else if db_params.engine == "sqlite" {
+        let backend = SqliteBackend::connect(db_params)?;
+        return Ok(Box::new(backend));
+    }else{
+        let backend = PostgresBackend::connect(db_params)?;
+        return Ok(Box::new(backend));
+    }
 }
 
-macro_rules! execute_query_callback {
-    ($callback:ident, $conn:ident,$query_str:ident,$params:expr) => (
-            match $conn.engine {
-                Engine::Postgres => {
-
-                    match $conn.pg_conn {
-                        Some(ref pg_conn) => {
-                            //execute the query                    
-                            let rows_result = pg_conn.query(&$query_str,$params);
-                            match rows_result {
-                                Ok(rows) => {
-                                    for row in rows.iter() {
-                                        $callback(&row);
-                                    }
-                                },
-                                Err(error) => {
-                                    return Err(format!("{}",error).into());
-                                }
-                            };
-                        },
-                        None => {
-                            let message = String::from("Postgres engine is None");
-                            error!("{}",message);
-                            return Err(message.into());
-                        }
-                    }
-                },
-                Engine::Oracle => {
-                    match $conn.oracle_conn {
-                        Some(ref oracle_conn) => {
-                            //execute the query
-                            let query_result = oracle_conn.query(&$query_str,$params);
-                            match query_result {
-                                Ok(rows_result) => {
-                                    for row_result in rows_result {
-                                        match row_result {
-                                            Ok(row) => {
-                                                $callback(&row);
-                                            },
-                                            Err(error) => {
-                                                error!("{}",error);
-                                                return Err(format!("{}",error).into());
-                                            }
-                                        }
-                                    };
-
-                                },
-                                Err(error) => {
-                                    return Err(format!("{}",error).into());
-                                }
-                            }
-                        },
-                        None => {
-                            //Invalid database engine
-                            let message = String::from("Oracle engine is None");
-                            error!("{}",message);
-                            return Err(message.into());
-                        }
-                    }
-                }
-            }
-    );
+pub struct ConnectionManager {
+    db_params: DBParams
 }
 
-pub fn get_info(id: &str, conn: &Connection) -> Result<Option<Info>> {
-    //perform the query
-    let query_str = query_builder::info(&conn.engine);
-    let closure = |row: &MyRow| -> Result<Info> {
-        return build_info(id, conn, row);
+impl r2d2::ManageConnection for ConnectionManager {
+    type Connection = Connection;
+    type Error = Error;
+
+    fn connect(&self) -> Result<Connection> {
+        return connect(&self.db_params);
+    }
+
+    fn is_valid(&self, conn: &mut Connection) -> Result<()> {
+        return conn.is_valid();
+    }
+
+    fn has_broken(&self, conn: &mut Connection) -> bool {
+        return conn.has_broken();
+    }
+}
+
+pub fn create_pool(db_params: &DBParams, pool_params: &PoolParams) -> Result<Pool> {
+    let manager = ConnectionManager {
+        db_params: db_params.clone()
     };
-    return execute_query!(closure,conn,query_str,&[&String::from(id)]);
+
+    let pool_result = r2d2::Pool::builder()
+                        .min_idle(Some(pool_params.min_idle))
+                        .max_size(pool_params.max_size)
+                        .idle_timeout(Some(Duration::from_secs(pool_params.idle_timeout)))
+                        .connection_timeout(Duration::from_secs(pool_params.connection_timeout))
+                        .build(manager);
+
+    match pool_result {
+        Ok(pool) => {
+            return Ok(pool);
+        },
+        Err(error) => {
+            error!("{}",error);
+            return Err(format!("{}",error).into());
+        }
+    }
+}
+
+pub fn get_connection(pool: &Pool) -> Result<PooledConnection> {
+    match pool.get() {
+        Ok(conn) => {
+            return Ok(conn);
+        },
+        Err(error) => {
+            error!("{}",error);
+            return Err(ErrorKind::PoolUnavailable(format!("{}",error)).into());
+        }
+    }
 }
 
-fn build_info(id: &str,conn: &Connection,row: &MyRow) -> Result<Info>{
+pub fn build_info(row: &MyRow,pro: Option<Pro>) -> Result<Info>{
     //build the organism
     let organism = Organism {
         taxon_code: row.get_string("taxon_code"),
@@ -376,9 +380,6 @@ fn build_info(id: &str,conn: &Connection,row: &MyRow) -> Result<Info>{
         common_name: row.get_string("taxon_common"),
     };
 
-    //get the pro info
-    let pro = get_pro_info(id,&conn)?;
-
     //get synonyms
     let synonymns_str = row.get_string("gene_syn");
     let synonymns = misc::to_vec_string(&synonymns_str, "|");
@@ -397,19 +398,52 @@ fn build_info(id: &str,conn: &Connection,row: &MyRow) -> Result<Info>{
     return Ok(info);
 }
 
+// The pattern matching the term anywhere in a column, with LIKE ... ESCAPE '\'. The wildcards
+// and the escape character of the term are escaped, so that it is matched literally.
+pub fn like_pattern(term: &str) -> String {
+    return format!("%{term}%",term=escape_like(term));
+}
 
-pub fn get_pro_info(id: &str, conn: &Connection) -> Result<Option<Pro>> {
-    //construct the pro id
-    let pro_id = format!("PR:{id}",id=id);
+//the term with the wildcards and the escape character of LIKE ... ESCAPE '\' escaped
+pub fn escape_like(term: &str) -> String {
+    return term.replace("\\","\\\\").replace("%","\\%").replace("_","\\_");
+}
 
-    //build query str
-    let query_str = query_builder::pro_info(&conn.engine);
-        
-    return execute_query!(build_pro_info,conn,query_str,&[&pro_id])
+//the pattern matching the item in a comma separated list wrapped in commas, like ',' || list || ','
+pub fn list_item_pattern(item: &str) -> String {
+    return format!("%,{item},%",item=escape_like(item));
+}
+
+//the pattern matching the isoforms of an entry, like Q15796-%
+pub fn isoform_pattern(accession: &str) -> String {
+    return format!("{accession}-%",accession=escape_like(accession));
+}
 
+//the pro entry code of an entry, as looked up by get_pro_info
+pub fn pro_entry_code(id: &str) -> String {
+    return format!("PR:{id}",id=id);
+}
+
+// Reads a row of a batch info query, into the infos when it is the entry of one of the ids and into
+// the pro infos, by entry code, when it is a PRO entry
+pub fn build_entry_or_pro(row: &MyRow, ids: &[String], infos: &mut Vec<Info>, pros: &mut HashMap<String,Pro>) -> Result<()> {
+    let entry_code = row.get_string_unwrapped("iptm_entry_code");
+    if row.get_string("iptm_entry_type") == Some(String::from("pro_id")) {
+        pros.insert(entry_code,build_pro_info(row)?);
+    }else if ids.contains(&entry_code) {
+        infos.push(build_info(row,None)?);
+    }
+    return Ok(());
 }
 
-fn build_pro_info(row: &MyRow) -> Result<Pro> {
+//hands every info its pro info
+pub fn add_pro_infos(infos: &mut Vec<Info>, mut pros: HashMap<String,Pro>) {
+    for info in infos.iter_mut() {
+        info.pro = pros.remove(&pro_entry_code(&info.uniprot_ac));
+    }
+}
+
+pub fn build_pro_info(row: &MyRow) -> Result<Pro> {
     let pro  = Pro {
         id: row.get_string_unwrapped("iptm_entry_code"),
         name: row.get_string("protein_name"),
@@ -420,100 +454,81 @@ fn build_pro_info(row: &MyRow) -> Result<Pro> {
     return Ok(pro);
 }
 
+pub fn build_entry_count(row: &MyRow) -> Result<i64> {
+    let count = row.get_i64("entry_count").unwrap_or_default();
+    return Ok(count);
+}
 
-pub fn search(search_term: &str,
-              term_type: &str,
-              role: &str,
-              ptm_types: &Vec<String>,
-              organism_taxons: &Vec<i32>,
-              paginate: bool,
-              offset: i32,
-              limit: i32,
-              conn: &Connection) -> Result<(i64,Rc<RefCell<Vec<SearchResult>>>)>{
-
-    //build the queries
-    let query_str = query_builder::search(term_type,role,ptm_types,organism_taxons,paginate,offset,limit,&conn.engine);
-         
-    let search_results: Rc<RefCell<Vec<SearchResult>>> = Rc::new(RefCell::new(Vec::new()));
-    let count: i64;
-
-    let handle_search_row = |row:&MyRow| {
-        // Get the search result from the row
-        let search_result_opt = get_search_result(row);
-        match search_result_opt {
-            Some(value) => {
-                search_results.borrow_mut().push(value);
-            },
-            None => {
-            }
-        }
+pub fn build_entry_code(row: &MyRow) -> Result<String> {
+    return Ok(row.get_string_unwrapped("iptm_entry_code"));
+}
+
+pub fn build_label_count(row: &MyRow) -> Result<(Option<String>,i64)> {
+    let count = row.get_i64("entry_count").unwrap_or_default();
+    return Ok((row.get_string("list_as_substrate"),count));
+}
+
+pub fn build_entry_labels(row: &MyRow) -> Result<(String,Option<String>)> {
+    return Ok((row.get_string_unwrapped("iptm_entry_code"),row.get_string("list_as_substrate")));
+}
+
+pub fn build_mapped_entry(row: &MyRow) -> Result<MappedEntry> {
+    let organism = Organism {
+        taxon_code: row.get_string("taxon_code"),
+        species: row.get_string("taxon_species"),
+        common_name: row.get_string("taxon_common"),
     };
 
-    let search_term_formatted;
-    match &conn.engine {
-        Engine::Postgres => {
-            search_term_formatted = format!("%{search_term}%",search_term=search_term);
-        },
-        Engine::Oracle => {
-            if search_term.is_empty(){
-                search_term_formatted = String::from(".*");
-            }else{
-                search_term_formatted = String::from(search_term);
-            }
-        }
+    let synonym_str: Option<String> = row.get_string("gene_syn");
+    let mapped_entry = MappedEntry {
+        iptm_entry_code: row.get_string_unwrapped("iptm_entry_code"),
+        entry_type: row.get_string("iptm_entry_type"),
+        gene_name: row.get_string("gene_name"),
+        synonyms: misc::to_vec_string(&synonym_str,"|"),
+        organism: organism
+    };
+    return Ok(mapped_entry);
+}
+
+//the parameters of a mapping candidates query, see query_builder::mapping_candidates
+pub fn mapping_params(tokens: &[String]) -> Vec<String> {
+    let mut params: Vec<String> = Vec::new();
+    for token in tokens {
+        params.push(token.to_uppercase());
     }
-    
-    let count_option = get_search_count(search_term_formatted.clone(),term_type,role,ptm_types,organism_taxons,conn)?;
-    match count_option {
-        Some(value) => {
-            count = value;
-        },
-        None => {
-            count = 0;
-        },
+    for token in tokens {
+        params.push(token.to_uppercase());
     }
-
-    if term_type == "All" {
-        execute_query_callback!(handle_search_row,conn,query_str,&[&search_term_formatted,&search_term_formatted,&search_term_formatted]);
-        return Ok((count.clone(),search_results.clone()));
-    }else if term_type == "UniprotID" {
-        execute_query_callback!(handle_search_row,conn,query_str,&[&search_term_formatted]);
-        return Ok((count.clone(),search_results.clone()));        
-    }else if term_type == "Protein/Gene Name" {
-        execute_query_callback!(handle_search_row,conn,query_str,&[&search_term_formatted,&search_term_formatted]);
-        return Ok((count.clone(),search_results.clone()));
-    }else{
-        return Ok((count.clone(),search_results.clone()));
+    for token in tokens {
+        params.push(format!("%|{token}|%",token=escape_like(&token.to_uppercase())));
     }
+    return params;
 }
 
-fn get_search_count(search_term_formatted: String,
-              term_type: &str,
-              role: &str,
-              ptm_types: &Vec<String>,
-              organism_taxons: &Vec<i32>,
-              conn: &Connection
-              ) -> Result<Option<i64>> 
-{
-    let count_query_str = query_builder::search_count(term_type,role,ptm_types,organism_taxons,&conn.engine);
-    if term_type == "All" {
-        return execute_query!(build_search_count,conn,count_query_str,&[&search_term_formatted,&search_term_formatted,&search_term_formatted]);
-    }else if term_type == "UniprotID" {
-        return execute_query!(build_search_count,conn,count_query_str,&[&search_term_formatted,&search_term_formatted,&search_term_formatted]);        
-    }else if term_type == "Protein/Gene Name" {
-        return execute_query!(build_search_count,conn,count_query_str,&[&search_term_formatted,&search_term_formatted,&search_term_formatted]);
-    }else{
-        return execute_query!(build_search_count,conn,count_query_str,&[&search_term_formatted,&search_term_formatted,&search_term_formatted]);
+//the parameters of the cited records queries, see query_builder::cited_events
+pub fn cited_params(pmids: &[String]) -> Vec<String> {
+    let mut params: Vec<String> = Vec::new();
+    for pmid in pmids {
+        params.push(format!("%,{pmid},%",pmid=pmid.trim()));
     }
+    return params;
+}
 
+pub fn build_suggestion(row: &MyRow) -> Result<Suggestion> {
+    let suggestion = Suggestion {
+        uniprot_ac: row.get_string_unwrapped("iptm_entry_code"),
+        uniprot_id: row.get_string("uniprot_id"),
+        gene_name: row.get_string("gene_name")
+    };
+    return Ok(suggestion);
 }
 
-fn build_search_count(row: &MyRow) -> Result<i64> {
+pub fn build_search_count(row: &MyRow) -> Result<i64> {
     let count = row.get_i64("search_count").unwrap_or_default();
     return Ok(count);
 }
 
-fn get_search_result(row: &MyRow) -> Option<SearchResult> {
+pub fn build_search_result(row: &MyRow) -> Result<SearchResult> {
     let organism = Organism {
         taxon_code: row.get_string("taxon_code"),
         species: row.get_string("taxon_species"),
@@ -539,179 +554,91 @@ fn get_search_result(row: &MyRow) -> Option<SearchResult> {
         isoforms: row.get_i64("num_form")
     };
 
-    return Some(search_result);
+    return Ok(search_result);
 }
 
-pub fn get_substrate_events(id: &str, conn: &Connection) -> Result<HashMap<String,Vec<SubstrateEvent>>> {
-     //get a list of forms for the given id
-     let sub_forms_result = get_sub_forms(id,conn);  
-     let sub_forms: Vec<String>;
-     match sub_forms_result {
-         Ok(value) => {
-            sub_forms = value;
-         },
-         Err(error) => {
-             return Err(format!("{}",error).into());
-         }
-     };
-
-     let mut substrate_events: HashMap<String,Vec<SubstrateEvent>> = HashMap::new();
-    
-     for sub_form in sub_forms {
-        let result = get_events_for_sub_form(&sub_form,conn);
-
-        let mut events;
-        let mut pmid_stats;
-
-        match result {
-            Ok(value) => {
-                events = value.0;
-                pmid_stats = value.1;
-            },
-            Err(error) => {
-                    return Err(format!("{}",error).into());    
-            }
-        }
-
-        for event in &mut events {
-            let mut sources: Vec<String> = Vec::new();
-            for source in &event.sources {
-                match source.label {
-                    Some(ref value) => {
-                        sources.push(value.clone());
-                    },
-                    None => {
-                        return Err("label for source is null".into());
-                    }
+fn score_events(events: &mut Vec<SubstrateEvent>,pmid_stats: &HashMap<String,i64>) -> Result<()> {
+    for event in events {
+        let mut sources: Vec<String> = Vec::new();
+        for source in &event.sources {
+            match source.label {
+                Some(ref value) => {
+                    sources.push(value.clone());
+                },
+                None => {
+                    return Err("label for source is null".into());
                 }
-                
             }
-            let score = misc::calculate_score(&pmid_stats,&event.pmids,&sources);
-            event.score = Some(score);
+
         }
+        let score = misc::calculate_score(pmid_stats,&event.pmids,&sources);
+        event.score = Some(score);
+    }
 
-        substrate_events.insert(sub_form,events);
-
-     }
-
-     return Ok(substrate_events);
-} 
-
-pub fn get_enzyme_events(id: &str, conn: &Connection) -> Result<Vec<EnzymeEvent>> {
-    let mut enzyme_events: Vec<EnzymeEvent> = Vec::new();
-
-    let query_str = format!("SELECT SUB_FORM_CODE,SUB_SYMBOL,RESIDUE,POSITION,SOURCE_LABEL,PMIDS \
-                    FROM MV_EVENT \
-                    where ENZ_CODE = '{id}' AND EVENT_LABEL = 'p' \
-                    ORDER BY RESIDUE,POSITION,EVENT_NAME",id=id);
-
-    match conn.engine {
-        Engine::Postgres => {
-            match conn.pg_conn {
-                Some(ref pg_conn) => {
-                    //execute the query                    
-                    let rows_result = pg_conn.query(&query_str,&[]);
-                    match rows_result {
-                        Ok(rows) => {
-                            for row in rows.iter() {
-
-                                // get substrate
-                                let substrate = row.get_string("SUB_FORM_CODE");
-
-                                // get substrate symbol
-                                let substrate_sym = row.get_string("SUB_SYMBOL");
-
-                                // get residue and site
-                                let mut site: Option<String> = None;
-                                let residue_option = row.get_string("residue");
-                                match residue_option {
-                                    Some(residue) => {
-
-                                        // get position
-                                        let position_option = row.get_i64("position");
-
-                                        match position_option {
-                                            Some (position) => {
-                                                site = Some(format!("{residue}{position}",residue=residue,position=position));
-                                            },
-                                            None => {
-                                                // pass
-                                            }
-                                        }
-                                    },
-                                    None => {
-                                        //pass
-                                    }
-                                }
-
-                                // get sources
-                                let mut sources: Vec<Source> = Vec::new();
-                                let source_label  = row.get_string("source_label");
-                                let source = misc::get_source(source_label);
-                                match source {
-                                    Some(value) => {
-                                        sources.push(value);
-                                    },
-                                    None => {
-
-                                    }
-                                }
-
-                                // get pmids
-                                let pmid_option = row.get_string("pmids");
-                                let pmids = misc::to_pmid_list(pmid_option);
-
-                                // build the enzyme event struct
-                                let enzyme_event = EnzymeEvent {
-                                    substrate: substrate,
-                                    substrate_symbol: substrate_sym,
-                                    site: site.clone(),
-                                    score: Some(0),
-                                    sources: sources,
-                                    pmids: pmids
-                                };
-
-                                if site != None {
-                                    enzyme_events.push(enzyme_event);
-                                }
+    return Ok(());
+}
 
+pub fn build_enzyme_event(row: &MyRow) -> Result<EnzymeEvent> {
+    // get substrate
+    let substrate = row.get_string("SUB_FORM_CODE");
 
-                            }
-                        },
-                        Err(error) => {
-                            return Err(format!("{}",error).into());
-                        }
-                    };
+    // get substrate symbol
+    let substrate_sym = row.get_string("SUB_SYMBOL");
+
+    // get residue and site
+    let mut site: Option<String> = None;
+    let residue_option = row.get_string("residue");
+    match residue_option {
+        Some(residue) => {
+
+            // get position
+            let position_option = row.get_i64("position");
+
+            match position_option {
+                Some (position) => {
+                    site = Some(format!("{residue}{position}",residue=residue,position=position));
                 },
                 None => {
-                    let message = String::from("Postgres engine is None");
-                    error!("{}",message);
-                    return Err(message.into());
+                    // pass
                 }
             }
         },
-        Engine::Oracle => {
-            let message = String::from("Unsupported with oracle engine");
-            error!("{}",message);
-            return Err(message.into());
+        None => {
+            //pass
         }
     }
 
-    return Ok(enzyme_events);
-} 
+    // get sources
+    let mut sources: Vec<Source> = Vec::new();
+    let source_label  = row.get_string("source_label");
+    let source = misc::get_source(source_label);
+    match source {
+        Some(value) => {
+            sources.push(value);
+        },
+        None => {
 
-pub fn get_sub_forms(id: &str,conn: &Connection) -> Result<Vec<String>>{
-    let query_str = query_builder::sub_forms(&conn.engine);
-  
-    let mut sub_forms: Vec<String> = Vec::new();
+        }
+    }
 
-    execute_query_bulk!(build_sub_form,conn,query_str,sub_forms,&[&String::from(id)]);
+    // get pmids
+    let pmid_option = row.get_string("pmids");
+    let pmids = misc::to_pmid_list(pmid_option);
 
-    return Ok(sub_forms);    
+    // build the enzyme event struct
+    let enzyme_event = EnzymeEvent {
+        substrate: substrate,
+        substrate_symbol: substrate_sym,
+        site: site,
+        score: Some(0),
+        sources: sources,
+        pmids: pmids
+    };
 
+    return Ok(enzyme_event);
 }
 
-fn build_sub_form(row: &MyRow) -> Result<String> {
+pub fn build_sub_form(row: &MyRow) -> Result<String> {
     let sub_form_code_option = row.get_string("sub_form_code");
     let sub_form_code;
 
@@ -727,83 +654,8 @@ fn build_sub_form(row: &MyRow) -> Result<String> {
     return Ok(sub_form_code);
 }
 
-fn get_events_for_sub_form(sub_form: &str,conn: &Connection) -> Result<(Vec<SubstrateEvent>,HashMap<String,i64>)>{
-    let query_str = format!("SELECT RESIDUE,POSITION,EVENT_NAME,ENZ_CODE,ENZ_TYPE,ENZ_SYMBOL,SOURCE_LABEL,PMIDS,NUM_SUBSTRATES \
-                    FROM MV_EVENT \
-                    where SUB_FORM_CODE = '{id}' \
-                    ORDER BY RESIDUE,POSITION,EVENT_NAME",id=sub_form);
-
-    let mut events: Vec<SubstrateEvent> = Vec::new();
-    let mut pmid_stats: HashMap<String,i64> = HashMap::new();
-
-        match conn.engine {
-        Engine::Postgres => {
-
-            match conn.pg_conn {
-                Some(ref pg_conn) => {
-                    //execute the query                    
-                    let rows_result = pg_conn.query(&query_str,&[]);
-                    match rows_result {
-                        Ok(rows) => {
-                            for row in rows.iter() {
-                                //update the events and pmid stats
-                                update_events(&row,&mut events,&mut pmid_stats);
-                            }
-                        },
-                        Err(error) => {
-                            return Err(format!("{}",error).into());
-                        }
-                    };
-                },
-                None => {
-                    let message = String::from("Postgres engine is None");
-                    error!("{}",message);
-                    return Err(message.into());
-                }
-            }
-        },
-        Engine::Oracle => {
-            match conn.oracle_conn {
-                Some(ref oracle_conn) => {
-                    //execute the query
-                    let query_result = oracle_conn.query(&query_str,&[]);
-                    match query_result {
-                        Ok(rows_result) => {
-                            for row_result in rows_result {
-                                match row_result {
-                                    Ok(row) => {
-                                        //update the events and pmid stats
-                                        update_events(&row,&mut events,&mut pmid_stats);                                        
-                                    },
-                                    Err(error) => {
-                                        error!("{}",error);
-                                        return Err(format!("{}",error).into());
-                                    }
-                                }
-                            };
-
-                        },
-                        Err(error) => {
-                            return Err(format!("{}",error).into());
-                        }
-                    }
-                },
-                None => {
-                    //Invalid database engine
-                    let message = String::from("Oracle engine is None");
-                    error!("{}",message);
-                    return Err(message.into());
-                }
-            }
-        }
-    }
-
-    return Ok((events,pmid_stats));
-
-}
+pub fn update_events(row: &MyRow,events: &mut Vec<SubstrateEvent>,pmid_stats: &mut HashMap<String,i64>){
 
-fn update_events(row: &MyRow,events: &mut Vec<SubstrateEvent>,pmid_stats: &mut HashMap<String,i64>){
-    
     //if events is not empty
     let events_count = events.len();
     if events_count != 0 {
@@ -819,7 +671,7 @@ fn update_events(row: &MyRow,events: &mut Vec<SubstrateEvent>,pmid_stats: &mut H
 
         //if it is the same event
         if current_event.site == previous_event.site && current_event.ptm_type == previous_event.ptm_type {
-            
+
             let previous_event_mut = &mut events.get_mut(events_count - 1).unwrap();
 
             //update enzymes
@@ -833,7 +685,7 @@ fn update_events(row: &MyRow,events: &mut Vec<SubstrateEvent>,pmid_stats: &mut H
             for source in current_event.sources {
                 if !&previous_event.sources.contains(&source) {
                     previous_event_mut.sources.push(source);
-                }     
+                }
             }
 
             //update the pmid
@@ -859,7 +711,7 @@ fn update_events(row: &MyRow,events: &mut Vec<SubstrateEvent>,pmid_stats: &mut H
 fn build_event(row: &MyRow) -> SubstrateEvent {
     let residue = row.get_string("residue");
     let site;
-    let ptm_type;  
+    let ptm_type;
 
     let mut enzymes: Vec<Enzyme> = Vec::new();
     let mut sources: Vec<Source> = Vec::new();
@@ -876,7 +728,7 @@ fn build_event(row: &MyRow) -> SubstrateEvent {
                     site = None;
                     ptm_type = None;
                 }
-            }            
+            }
         },
         & None => {
             site = None;
@@ -904,7 +756,7 @@ fn build_event(row: &MyRow) -> SubstrateEvent {
 
                 }
             }
-            
+
         },
         None => {
 
@@ -925,7 +777,7 @@ fn build_event(row: &MyRow) -> SubstrateEvent {
     let pmid_option = row.get_string("pmids");
     let pmids = misc::to_pmid_list(pmid_option);
 
-    
+
     let event = SubstrateEvent {
         residue: residue,
         site: site,
@@ -933,8 +785,8 @@ fn build_event(row: &MyRow) -> SubstrateEvent {
         score:Some(0),
         sources: sources,
         enzymes: enzymes,
-        pmids: pmids 
-        
+        pmids: pmids
+
     };
     return event;
 
@@ -942,13 +794,13 @@ fn build_event(row: &MyRow) -> SubstrateEvent {
 
 fn update_pmid_stats(row: &MyRow,pmids: &Vec<String>,pmid_stats: &mut HashMap<String,i64>) {
     let num_substrates_str = row.get_string("num_substrates");
-    
+
     let num_substrates = misc::to_vec_i64(&num_substrates_str,"|");
     let mut index = 0;
     for pmid in pmids {
         match num_substrates.get(index) {
             Some(value) => {
-                pmid_stats.insert(pmid.clone(),*value); 
+                pmid_stats.insert(pmid.clone(),*value);
             },
             None => {
                 pmid_stats.insert(pmid.clone(),0);
@@ -957,32 +809,11 @@ fn update_pmid_stats(row: &MyRow,pmids: &Vec<String>,pmid_stats: &mut HashMap<St
 
        index = index + 1;
 
-    }   
-
-}
-
-
-pub fn get_proteoforms(id: &str, conn: &Connection) -> Result<Vec<Proteoform>> {
-    let query_str = query_builder::proteoforms(&conn.engine);    
-    let mut proteoforms : Vec<Proteoform> = Vec::new();
-
-    let formatted_id;
-    match &conn.engine {
-        Engine::Postgres => {
-            formatted_id = format!("%{id}%",id=id);
-        },
-        Engine::Oracle => {
-            formatted_id = String::from(id);
-        }
     }
 
-    execute_query_bulk!(build_proteoform,conn,query_str,proteoforms,&[&formatted_id]);
-
-    return Ok(proteoforms);
-
 }
 
-fn build_proteoform(row: &MyRow) -> Result<Proteoform> {
+pub fn build_proteoform(row: &MyRow) -> Result<Proteoform> {
         // build enzyme
         let enzyme = Protein {
             pro_id: row.get_string("enz_code"),
@@ -1011,31 +842,7 @@ fn build_proteoform(row: &MyRow) -> Result<Proteoform> {
         return Ok(proteoform);
 }
 
-
-pub fn get_proteoformppis(id: &str, conn: &Connection) -> Result<Vec<ProteoformPPI>> {
-
-    // build the query
-    let query_str = query_builder::proteoformppi(&conn.engine);
-
-    let mut proteoformsppi : Vec<ProteoformPPI> = Vec::new();
-
-    let formatted_id;
-    match &conn.engine {
-        Engine::Postgres => {
-            formatted_id = format!("%{id}%",id=id);
-        },
-        Engine::Oracle => {
-            formatted_id = String::from(id);
-        }
-    }
-
-    execute_query_bulk!(build_proteoform_ppi,conn,query_str,proteoformsppi,&[&formatted_id]);    
-
-    return Ok(proteoformsppi);
-
-}
-
-fn build_proteoform_ppi(row: &MyRow) -> Result<ProteoformPPI>{
+pub fn build_proteoform_ppi(row: &MyRow) -> Result<ProteoformPPI>{
         //build protein 1
         let protein_1 = Protein{
             pro_id: row.get_string("sub_code"),
@@ -1064,24 +871,12 @@ fn build_proteoform_ppi(row: &MyRow) -> Result<ProteoformPPI>{
             protein_2: Some(protein_2),
             relation: relation,
             source: misc::get_source(source_label),
-            pmids: pmids,    
+            pmids: pmids,
         };
         return Ok(proteoformppi);
 }
 
-pub fn get_ptmppis(id: &str, conn: &Connection) -> Result<Vec<PTMPPI>> {
-    //build the query
-    let query_str = query_builder::ptmppi(&conn.engine);
-    
-    let mut ptmppis : Vec<PTMPPI> = Vec::new();
-
-    execute_query_bulk!(build_pptm_ppi,conn,query_str,ptmppis,&[&String::from(id)]);    
-
-    return Ok(ptmppis);
-
-}
-
-fn build_pptm_ppi(row: &MyRow) -> Result<PTMPPI> {
+pub fn build_pptm_ppi(row: &MyRow) -> Result<PTMPPI> {
         //build substrate
         let substrate = Entity{
             uniprot_id: row.get_string("ppi_sub_code"),
@@ -1130,46 +925,8 @@ fn build_pptm_ppi(row: &MyRow) -> Result<PTMPPI> {
 
 }
 
-
-pub fn get_ptm_enzymes(query_substrates: &Vec<QuerySubstrate>, conn: &Connection) -> Result<Vec<BatchPTMEnzyme>> {
-    //convert query_substrates to tuples string
-    let query_substrates_str = misc::query_substrates_to_tuple_str(query_substrates);
-
-    //build the query string
-    let query_str;
-    match conn.engine {
-        Engine::Postgres => {
-            query_str = format!("SELECT event_name,sub_code,sub_symbol,residue,position,enz_code,enz_symbol, \
-                string_agg(source_label,',' ORDER BY source_label) as source_label, \
-                string_agg(num_substrates,'|' ORDER BY source_label) as num_substrates, \
-                string_agg(pmids,',' ORDER BY source_label) as pmids \
-                FROM MV_EVENT \
-                where (sub_code,residue,position) in ({tuples}) and enz_code is not NULL \
-                GROUP BY(enz_code,enz_symbol,sub_code,sub_symbol,residue,position,event_name)",tuples=query_substrates_str); 
-        },
-        Engine::Oracle => {
-            query_str = format!("SELECT event_name,sub_code,sub_symbol,residue,position,enz_code,enz_symbol, \
-                LISTAGG(source_label,',') WITHIN GROUP (ORDER BY source_label) as source_label, \
-                LISTAGG(num_substrates,'|') WITHIN GROUP (ORDER BY source_label) as num_substrates, \
-                LISTAGG(pmids,',') WITHIN GROUP (ORDER BY source_label) as pmids \
-                FROM MV_EVENT \
-                where (sub_code,residue,position) in ({tuples}) and enz_code is not NULL \
-                GROUP BY(enz_code,enz_symbol,sub_code,sub_symbol,residue,position,event_name)",tuples=query_substrates_str);
-        }
-    }
-
-    let mut ptm_enzymes: Vec<BatchPTMEnzyme> = Vec::new();
-
-    execute_query_bulk!(build_ptm_enzyme,conn,query_str,ptm_enzymes,&[]);    
-
-    let ptm_enzymes_filtered = ptm_enzymes.into_iter().filter(|batch_ptm_enzyme| misc::filter_empty_enzymes(batch_ptm_enzyme)).collect::<Vec<BatchPTMEnzyme>>();
-
-    Ok(ptm_enzymes_filtered)
-
-}
-
-fn build_ptm_enzyme(row: &MyRow) -> Result<BatchPTMEnzyme> {
-        //build enzyme                
+pub fn build_ptm_enzyme(row: &MyRow) -> Result<BatchPTMEnzyme> {
+        //build enzyme
         let enzyme = Entity{
             name: row.get_string("enz_symbol"),
             uniprot_id: row.get_string("enz_code")
@@ -1219,7 +976,7 @@ fn build_ptm_enzyme(row: &MyRow) -> Result<BatchPTMEnzyme> {
 
         //get number of substrates
         let num_substrates_str:Option<String> = row.get_string("num_substrates");
-        let num_substrates = misc::to_vec_i64(&num_substrates_str,"|");        
+        let num_substrates = misc::to_vec_i64(&num_substrates_str,"|");
 
         //calculate score
         let score = misc::calculate_score_batch_ptm_enzymes(num_substrates,&sources_labels,&pmids);
@@ -1233,31 +990,73 @@ fn build_ptm_enzyme(row: &MyRow) -> Result<BatchPTMEnzyme> {
             substrate: Some(substrate),
             source: sources,
             pmids: pmids,
-            score: score
+            score: score,
+            query_site: None,
+            offset: None
         };
 
         return Ok(ptm_enzyme);
 }
 
+pub fn build_cited_event(row: &MyRow) -> Result<PublicationEvent> {
+        //build substrate
+        let substrate = Entity {
+            name: row.get_string("sub_symbol"),
+            uniprot_id: row.get_string("sub_code")
+        };
 
-pub fn get_ptm_ppi(query_substrates: &Vec<QuerySubstrate>, conn: &Connection) -> Result<Vec<BatchPTMPPI>> {
-    //convert query_substrates to tuples string
-    let query_substrates_str = misc::query_substrates_to_tuple_str(query_substrates);
+        //sources, every row of the event has its own source
+        let source_label_str: Option<String> = row.get_string("source_label");
+        let mut sources: Vec<Source> = Vec::new();
+        for source_label in misc::remove_duplicates(&misc::to_vec_string(&source_label_str,",")) {
+            match misc::get_source(Some(source_label)) {
+                Some(val) => {sources.push(val)},
+                None => {}
+            }
+        }
 
-    //build the query string
-    let query_str = format!("SELECT * FROM MV_EFIP \
-                where (ptm_sub_code,ptm_residue,ptm_position) in ({tuples})",tuples=query_substrates_str);
+        //site
+        let residue: Option<String> = row.get_string("residue");
+        let position: Option<i64> = row.get_i64("position");
+        let site;
+        match (residue,position) {
+            (Some(residue_value),Some(pos_value)) => {
+                site = Some(format!("{residue}{pos}",residue=residue_value,pos=pos_value));
+            },
+            _ => {
+                site = None;
+            }
+        }
 
-    let mut ptm_ppis: Vec<BatchPTMPPI> = Vec::new();
+        //pmids
+        let pmids = misc::remove_duplicates(&misc::to_pmid_list(row.get_string("pmids")));
 
-    execute_query_bulk!(build_ptm_ppi,conn,query_str,ptm_ppis,&[]);
+        let cited_event = PublicationEvent {
+            substrate: Some(substrate),
+            ptm_type: row.get_string("event_name"),
+            site: site,
+            site_position: position,
+            sources: sources,
+            pmids: pmids
+        };
 
-    Ok(ptm_ppis)
+        return Ok(cited_event);
+}
 
+//the site a row of a batch query was found at, read from the columns matched against the site keys
+pub fn build_site_key(row: &MyRow, substrate_ac_column: &str, residue_column: &str, position_column: &str) -> Option<SiteKey> {
+    match (row.get_string(substrate_ac_column),row.get_string(residue_column),row.get_i64(position_column)) {
+        (Some(substrate_ac),Some(residue),Some(position)) => {
+            return Some((substrate_ac,residue,position));
+        },
+        _ => {
+            return None;
+        }
+    }
 }
 
-fn build_ptm_ppi(row: &MyRow) -> Result<BatchPTMPPI> {
-        //build interactant                
+pub fn build_ptm_ppi(row: &MyRow) -> Result<BatchPTMPPI> {
+        //build interactant
         let interactant = Entity{
             name: row.get_string("ppi_pr_symbol"),
             uniprot_id: row.get_string("ppi_pr_code")
@@ -1303,74 +1102,31 @@ fn build_ptm_ppi(row: &MyRow) -> Result<BatchPTMPPI> {
             interactant: Some(interactant),
             substrate: Some(substrate),
             source: misc::get_source(source_label),
-            pmids: misc::to_vec_string(&pmid_str,",")
+            pmids: misc::to_vec_string(&pmid_str,","),
+            query_site: None,
+            offset: None
         };
 
         return Ok(ptm_ppi);
 }
 
-pub fn get_sequences(id: &str, conn: &Connection) -> Result<Vec<Sequence>> {
-    //perform the query
-    let mut sequences: Vec<Sequence> = Vec::new();
-    let query_str = query_builder::get_sequences(&conn.engine);
-    let id_formatted = format!("%{id}%",id=String::from(id));
-    execute_query_bulk!(build_sequences,conn,query_str,sequences,&[&id_formatted]);
-
-    return Ok(sequences);
-}   
-
-fn build_sequences(row: &MyRow) -> Result<Sequence> {
+pub fn build_sequences(row: &MyRow) -> Result<Sequence> {
     let id = row.get_string_unwrapped("id");
     let sequence_str = row.get_string_unwrapped("seq");
 
     let sequence = Sequence {
         id: id,
         sequence : sequence_str
-    };  
+    };
 
     return Ok(sequence);
 
 }
 
-pub fn get_decorations(id: &str, form_id: &str, position: i64,residue: &str, conn: &Connection) -> Result<Vec<Decoration>> {
-    //perform the query
-    let mut decorations: Vec<Decoration> = Vec::new();
-    let query_str = query_builder::get_decorations(&conn.engine);
-    let is_conserved;
-    match get_decorations_count(id, position, residue, conn) {
-        Ok(val) => {
-            if val.unwrap_or(0) > 0 {
-                is_conserved = true;
-            }else{
-                is_conserved = false;
-            }
-        },
-        Err(_) => {
-            is_conserved = false;
-        }
-    }
-
-    let clousure = |row: &MyRow| build_decoration(row,is_conserved);
-
-    execute_query_bulk!(clousure,conn,query_str,decorations,&[&String::from(form_id),&position,&String::from(residue)]);
-
-    if is_conserved && decorations.len() == 0 {
-        let decoration = Decoration {
-            ptm_type: Some(String::from("")),
-            source: Vec::new(),
-            pmids: Vec::new(),
-            is_conserved: is_conserved
-        };
-        decorations.push(decoration);
-    }
-
-    return Ok(decorations);
-}
-
 pub fn build_decoration(row: &MyRow,is_conserved: bool) -> Result<Decoration> {
 
     let source_labels = misc::to_vec_string(&row.get_string("source_labels"),",");
-    
+
     let mut sources: Vec<Source> = Vec::new();
     for source_label in source_labels {
         let source = misc::get_source(Some(source_label));
@@ -1391,75 +1147,33 @@ pub fn build_decoration(row: &MyRow,is_conserved: bool) -> Result<Decoration> {
         is_conserved: is_conserved
     };
 
-    return Ok(decoration);    
+    return Ok(decoration);
 
 }
 
-pub fn get_decorations_count(id: &str, position: i64,residue: &str, conn: &Connection) -> Result<Option<i64>> {
-    let query_str = query_builder::get_decorations_count(&conn.engine);
-    
-    let closure = |row: &MyRow| -> Result<i64> {
-        let result = row.get_i64("count");
-        match result {
-            Some(val) => {
-                return Ok(val);
-            },
-            None => {
-                return Ok(0);
-            }
-        }
+pub fn build_variant(row: &MyRow) -> Result<Variant> {
+    let variant = Variant {
+        ac: row.get_string("ac"),
+        position: row.get_i64("position").unwrap_or_default() as i32,
+        residue_sequence: row.get_string("residue_sequence"),
+        residue_mutated: row.get_string("residue_mutated"),
+        disease: row.get_string("disease"),
+        sample_source: row.get_string("sample_source"),
+        pmid: row.get_string("pmid")
     };
 
-    return execute_query!(closure,conn,query_str,&[&id,&position,&residue]);
+    return Ok(variant);
 }
 
-pub fn get_variants(id: &str,conn: &Connection) -> Result<Vec<Variant>>{
-    let query_str = format!("SELECT * \
-                    FROM BIOMUTA \
-                    where ac like '%{id}%' ",id=id);
-    println!("{}",query_str);
-
-    let mut variants: Vec<Variant> = Vec::new();
-
-        match conn.engine {
-        Engine::Postgres => {
-
-            match conn.pg_conn {
-                Some(ref pg_conn) => {
-                    //execute the query                    
-                    let rows_result = pg_conn.query(&query_str,&[]);
-                    match rows_result {
-                        Ok(rows) => {
-                            for row in rows.iter() {
-                                let variant = Variant {
-                                    ac: row.get("ac"),
-                                    position: row.get("position"),
-                                    residue_sequence: row.get("residue_sequence"),
-                                    residue_mutated: row.get("residue_mutated"),
-                                    disease: row.get("disease"),
-                                    sample_source: row.get("sample_source"),
-                                    pmid: row.get("pmid")
-                                };
-                                variants.push(variant);
-                            }
-                        },
-                        Err(error) => {
-                            return Err(format!("{}",error).into());
-                        }
-                    };
-                },
-                None => {
-                    let message = String::from("Postgres engine is None");
-                    error!("{}",message);
-                    return Err(message.into());
-                }
-            }
-        },
-        Engine::Oracle => {
-            
-        }
-    }
-
-    return Ok(variants);
+pub fn build_site_decoration(row: &MyRow) -> Result<(i64,String,Decoration)> {
+    let position = row.get_i64("position").unwrap_or_default();
+    let residue = row.get_string("residue").unwrap_or_default();
+    let decoration = build_decoration(row,false)?;
+    return Ok((position,residue,decoration));
+}
 
-}
\ No newline at end of file
+pub fn build_decorated_site(row: &MyRow) -> Result<(i64,String)> {
+    let position = row.get_i64("position").unwrap_or_default();
+    let residue = row.get_string("residue").unwrap_or_default();
+    return Ok((position,residue));
+}
@@ -1,8 +1,83 @@
 // Create the Error, ErrorKind, ResultExt, and Result types
+//
+// Every error that can reach a client has its own kind, so that problem::response can map it to
+// an HTTP status and a stable code. Plain string errors are treated as internal errors.
 error_chain!{
     foreign_links {
         IOError(::std::io::Error);
         PostgresError(::postgres::Error);
         Utf8Error(::std::string::FromUtf8Error);
     }
-}
\ No newline at end of file
+
+    errors {
+        BadRequest(detail: String) {
+            description("bad request")
+            display("{}", detail)
+        }
+
+        NotFound(detail: String) {
+            description("not found")
+            display("{}", detail)
+        }
+
+        UnknownEntry(id: String, suggestions: Vec<::models::Suggestion>) {
+            description("unknown entry")
+            display("No entry matches {}", id)
+        }
+
+        NotAcceptable(detail: String) {
+            description("not acceptable")
+            display("{}", detail)
+        }
+
+        UnsupportedMediaType(detail: String) {
+            description("unsupported media type")
+            display("{}", detail)
+        }
+
+        Unprocessable(detail: String) {
+            description("unprocessable entity")
+            display("{}", detail)
+        }
+
+        RejectedRows(rejected_rows: Vec<::models::RejectedRow>) {
+            description("rejected rows")
+            display("{} rows of the batch were rejected", rejected_rows.len())
+        }
+
+        Conflict(detail: String) {
+            description("conflict")
+            display("{}", detail)
+        }
+
+        PayloadTooLarge(detail: String) {
+            description("payload too large")
+            display("{}", detail)
+        }
+
+        Database(message: String) {
+            description("database error")
+            display("Database error - {}", message)
+        }
+
+        Timeout(message: String) {
+            description("database query timed out")
+            display("Database query timed out - {}", message)
+        }
+
+        PoolUnavailable(message: String) {
+            description("database connection pool unavailable")
+            display("Database connection pool unavailable - {}", message)
+        }
+
+        QueueFull(message: String) {
+            description("job queue full")
+            display("Job queue full - {}", message)
+        }
+
+        StreamsBusy(message: String) {
+            description("every stream producer busy")
+            display("Every stream producer is busy - {}", message)
+        }
+    }
+}
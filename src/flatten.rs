@@ -1,17 +1,66 @@
 use models::*;
 use misc;
 use std::collections::HashMap;
+use render::{Flatten, Rows};
+
+impl Flatten for Info {
+    type Flat = InfoFlat;
+
+    fn flatten(&self) -> InfoFlat {
+        //flatten pro
+        let pro_id;
+        let pro_name;
+        let pro_definition;
+        let pro_short_label;
+        let pro_category;
+        match &self.pro {
+            &Some(ref pro) => {
+                pro_id = Some(pro.id.clone());
+                pro_name = pro.name.clone();
+                pro_definition = pro.definition.clone();
+                pro_short_label = pro.short_label.clone();
+                pro_category = pro.category.clone();
+            },
+            &None => {
+                pro_id = None;
+                pro_name = None;
+                pro_definition = None;
+                pro_short_label = None;
+                pro_category = None;
+            }
+        }
+
+        //build flat info
+        let info_flat = InfoFlat {
+            uniprot_ac: self.uniprot_ac.clone(),
+            uniprot_id: self.uniprot_id.clone(),
+            protein_name: self.protein_name.clone(),
+            gene_name: self.gene_name.clone(),
+            synonyms: misc::str_vec_to_str(&self.synonyms),
+            organism_taxon_code: self.organism.taxon_code.clone(),
+            organism_species: self.organism.species.clone(),
+            organism_common_name: self.organism.common_name.clone(),
+            pro_id: pro_id,
+            pro_name: pro_name,
+            pro_definition: pro_definition,
+            pro_short_label: pro_short_label,
+            pro_category: pro_category,
+        };
+
+        return info_flat;
+    }
+}
 
-pub fn search_results(search_results: &Vec<SearchResult>) -> Vec<SearchResultFlat> {
-    let mut search_results_flat: Vec<SearchResultFlat> = Vec::new();
+impl Flatten for SearchResult {
+    type Flat = SearchResultFlat;
 
-    for search_result in search_results {
+    fn flatten(&self) -> SearchResultFlat {
         //flatten organism
         let taxon_code;
         let species;
         let common_name;
 
-        match &search_result.organism {
+        match &self.organism {
             &Some(ref organism) => {
                 taxon_code = organism.taxon_code.clone();
                 species = organism.species.clone();
@@ -25,134 +74,135 @@ pub fn search_results(search_results: &Vec<SearchResult>) -> Vec<SearchResultFla
         }
 
         //flatten synonyms
-        let synonyms_flat = misc::str_vec_to_str(&search_result.synonyms);
+        let synonyms_flat = misc::str_vec_to_str(&self.synonyms);
 
         //build flat search result 
         let search_result_flat = SearchResultFlat {
-            iptm_id : search_result.iptm_id.clone(),
-            protein_name: search_result.protein_name.clone(),
-            gene_name: search_result.gene_name.clone(),
+            iptm_id : self.iptm_id.clone(),
+            protein_name: self.protein_name.clone(),
+            gene_name: self.gene_name.clone(),
             synonyms: Some(synonyms_flat),
             organism_taxon_code: taxon_code,
             organism_species: species,
             organism_common_name: common_name,
-            substrate_role: search_result.substrate_role.clone(),
-            substrate_num: search_result.substrate_num.clone(),
-            enzyme_role: search_result.enzyme_role.clone(),
-            enzyme_num: search_result.enzyme_num.clone(),
-            ptm_dependent_ppi_role : search_result.ptm_dependent_ppi_role.clone(),
-            ptm_dependent_ppi_num : search_result.ptm_dependent_ppi_num.clone(),
-            sites: search_result.sites.clone(),
-            isoforms: search_result.isoforms.clone(),
+            substrate_role: self.substrate_role.clone(),
+            substrate_num: self.substrate_num.clone(),
+            enzyme_role: self.enzyme_role.clone(),
+            enzyme_num: self.enzyme_num.clone(),
+            ptm_dependent_ppi_role : self.ptm_dependent_ppi_role.clone(),
+            ptm_dependent_ppi_num : self.ptm_dependent_ppi_num.clone(),
+            sites: self.sites.clone(),
+            isoforms: self.isoforms.clone(),
         };
 
-        // add to vector
-        search_results_flat.push(search_result_flat);
-
+        return search_result_flat;
     }
-
-    return search_results_flat;
-
 }
 
-pub fn substrate_events(substrate_events: &HashMap<String,Vec<SubstrateEvent>>) -> Vec<SubstrateEventFlat> {
-    
-    let mut substrate_events_flat:Vec<SubstrateEventFlat> = Vec::new();
+// The sub form is not part of the event, it is filled in from the key of the events map
+impl Flatten for SubstrateEvent {
+    type Flat = SubstrateEventFlat;
 
-    for sub_from in substrate_events.iter() {
-        let events = sub_from.1;
-        for event in events {
+    fn flatten(&self) -> SubstrateEventFlat {
+        //flatten enzymes
+        let mut enzymes_str = String::new();
+        for (index,enzyme) in self.enzymes.iter().enumerate() {
             
-            //flatten enzymes
-            let mut enzymes_str = String::new();
-            for (index,enzyme) in event.enzymes.iter().enumerate() {
-                
-                let mut enzyme_name = String::new();
-                let mut enzyme_id = String::new();
-                let mut enzyme_type = String::new();
-
-                match enzyme.name {
-                    Some(ref value) => {
-                        enzyme_name = value.clone();
-                    },
-                    None => {
-
-                    }
-                }
+            let mut enzyme_name = String::new();
+            let mut enzyme_id = String::new();
+            let mut enzyme_type = String::new();
+
+            match enzyme.name {
+                Some(ref value) => {
+                    enzyme_name = value.clone();
+                },
+                None => {
 
-                match enzyme.id {
-                    Some(ref value) => {
-                        enzyme_id = value.clone();
-                    },
-                    None => {
-                        
-                    }
                 }
+            }
 
-                match enzyme.enz_type {
-                    Some(ref value) => {
-                        enzyme_type = value.clone();
-                    },
-                    None => {
-                        
-                    }
+            match enzyme.id {
+                Some(ref value) => {
+                    enzyme_id = value.clone();
+                },
+                None => {
+                    
                 }
+            }
 
-                let current_str = format!("[{name},{id},{enz_type}]",name=enzyme_name,id=enzyme_id,enz_type=enzyme_type);
-                if index == 0 {
-                    enzymes_str = current_str;
-                }else{
-                    enzymes_str = format!("{prev_str},{curr_str}",prev_str=enzymes_str,curr_str=current_str);
+            match enzyme.enz_type {
+                Some(ref value) => {
+                    enzyme_type = value.clone();
+                },
+                None => {
+                    
                 }
-            };
-
-            let event_flat = SubstrateEventFlat {
-                sub_form: Some(sub_from.0.clone()),
-                residue: event.residue.clone(),
-                site: event.site.clone(),
-                ptm_type: event.ptm_type.clone(),
-                score: event.score.clone(),
-                sources: Some(sources(&event.sources)),
-                enzymes: Some(enzymes_str),
-                pmids: Some(misc::str_vec_to_str(&event.pmids))
-            };
-
-            substrate_events_flat.push(event_flat);
-
-        }   
-    }
+            }
 
-    return substrate_events_flat;
+            let current_str = format!("[{name},{id},{enz_type}]",name=enzyme_name,id=enzyme_id,enz_type=enzyme_type);
+            if index == 0 {
+                enzymes_str = current_str;
+            }else{
+                enzymes_str = format!("{prev_str},{curr_str}",prev_str=enzymes_str,curr_str=current_str);
+            }
+        };
+
+        let event_flat = SubstrateEventFlat {
+            sub_form: None,
+            residue: self.residue.clone(),
+            site: self.site.clone(),
+            ptm_type: self.ptm_type.clone(),
+            score: self.score.clone(),
+            sources: Some(sources(&self.sources)),
+            enzymes: Some(enzymes_str),
+            pmids: Some(misc::str_vec_to_str(&self.pmids))
+        };
 
+        return event_flat;
+    }
 }
 
+//events keyed by sub form, as returned by Backend::get_substrate_events
+impl Rows for HashMap<String,Vec<SubstrateEvent>> {
+    type Row = SubstrateEventFlat;
+
+    fn rows(&self) -> Vec<SubstrateEventFlat> {
+        let mut substrate_events_flat: Vec<SubstrateEventFlat> = Vec::new();
+        for (sub_form,events) in self.iter() {
+            for event in events {
+                let mut event_flat = event.flatten();
+                event_flat.sub_form = Some(sub_form.clone());
+                substrate_events_flat.push(event_flat);
+            }
+        }
+        return substrate_events_flat;
+    }
+}
 
-pub fn enzyme_events(enzyme_events: &Vec<EnzymeEvent>) -> Vec<EnzymeEventFlat> {
-    let mut enzyme_events_flat : Vec<EnzymeEventFlat> = Vec::new();
+impl Flatten for EnzymeEvent {
+    type Flat = EnzymeEventFlat;
 
-    for enzyme_event in enzyme_events {
+    fn flatten(&self) -> EnzymeEventFlat {
         let event_flat = EnzymeEventFlat {
-            substrate: enzyme_event.substrate.clone(),
-            substrate_symbol: enzyme_event.substrate_symbol.clone(),
-            site: enzyme_event.site.clone(),
-            score: enzyme_event.score.clone(),
-            sources: Some(sources(&enzyme_event.sources)),
-            pmids: Some(misc::str_vec_to_str(&enzyme_event.pmids))
+            substrate: self.substrate.clone(),
+            substrate_symbol: self.substrate_symbol.clone(),
+            site: self.site.clone(),
+            score: self.score.clone(),
+            sources: Some(sources(&self.sources)),
+            pmids: Some(misc::str_vec_to_str(&self.pmids))
         };
-        enzyme_events_flat.push(event_flat);
+        return event_flat;
     }
-
-    return enzyme_events_flat;
 }
 
-pub fn proteoform(proteoforms: &Vec<Proteoform>) -> Vec<ProteoformFlat> {
-    let mut proteoforms_flat: Vec<ProteoformFlat> = Vec::new();
-    for proteoform in proteoforms {
+impl Flatten for Proteoform {
+    type Flat = ProteoformFlat;
 
+    fn flatten(&self) -> ProteoformFlat {
         // flatten ptm enzyme
         let ptm_enzyme_id;
         let ptm_enzyme_label;
-        match &proteoform.ptm_enzyme {
+        match &self.ptm_enzyme {
             &Some(ref ptm_enzyme) => {
                 ptm_enzyme_id = ptm_enzyme.pro_id.clone();
                 ptm_enzyme_label = ptm_enzyme.label.clone();
@@ -165,7 +215,7 @@ pub fn proteoform(proteoforms: &Vec<Proteoform>) -> Vec<ProteoformFlat> {
 
         // flatten source
         let source_name;
-        match &proteoform.source {
+        match &self.source {
             &Some(ref source) => {
                 source_name = source.name.clone(); 
             },
@@ -174,32 +224,30 @@ pub fn proteoform(proteoforms: &Vec<Proteoform>) -> Vec<ProteoformFlat> {
             }
         }
 
-        //build the flat proteoform
+        //build the flat self
         let proteoform_flat = ProteoformFlat {
-            pro_id : proteoform.pro_id.clone(),
-            label: proteoform.label.clone(),
-            sites: Some(misc::str_vec_to_str(&proteoform.sites)),
+            pro_id : self.pro_id.clone(),
+            label: self.label.clone(),
+            sites: Some(misc::str_vec_to_str(&self.sites)),
             ptm_enzyme_id: ptm_enzyme_id,
             ptm_enzyme_label: ptm_enzyme_label,
             source: source_name,
-            pmids: Some(misc::str_vec_to_str(&proteoform.sites))
+            pmids: Some(misc::str_vec_to_str(&self.sites))
         };
 
-        //add to vector
-        proteoforms_flat.push(proteoform_flat);
+        return proteoform_flat;
     }
-    return proteoforms_flat;
 }
 
 
-pub fn proteoform_ppis(proteoforms_ppi: &Vec<ProteoformPPI>) -> Vec<ProteoformPPIFlat> {
-    let mut proteoforms_ppi_flat: Vec<ProteoformPPIFlat> = Vec::new();
-    for proteoform_ppi in proteoforms_ppi {
+impl Flatten for ProteoformPPI {
+    type Flat = ProteoformPPIFlat;
 
+    fn flatten(&self) -> ProteoformPPIFlat {
         //build protein_1
         let protein_1_pro_id;
         let protein_1_label;
-        match &proteoform_ppi.protein_1 {
+        match &self.protein_1 {
             &Some(ref protein_1) => {
                 protein_1_pro_id = protein_1.pro_id.clone();
                 protein_1_label = protein_1.label.clone();
@@ -213,7 +261,7 @@ pub fn proteoform_ppis(proteoforms_ppi: &Vec<ProteoformPPI>) -> Vec<ProteoformPP
         //build protein_2
         let protein_2_pro_id;
         let protein_2_label;
-        match &proteoform_ppi.protein_2 {
+        match &self.protein_2 {
             &Some(ref protein_2) => {
                 protein_2_pro_id = protein_2.pro_id.clone();
                 protein_2_label = protein_2.label.clone();
@@ -226,12 +274,12 @@ pub fn proteoform_ppis(proteoforms_ppi: &Vec<ProteoformPPI>) -> Vec<ProteoformPP
 
 
         //pmids
-        let pmids = misc::str_vec_to_str(&proteoform_ppi.pmids);
+        let pmids = misc::str_vec_to_str(&self.pmids);
 
 
         //source
         let source_name;
-        match &proteoform_ppi.source {
+        match &self.source {
             &Some(ref source) => {
                 source_name = source.name.clone(); 
             },
@@ -240,30 +288,29 @@ pub fn proteoform_ppis(proteoforms_ppi: &Vec<ProteoformPPI>) -> Vec<ProteoformPP
             }
         }
 
-        //build flat proteoform_ppi
+        //build flat self
         let proteoform_ppi_flat = ProteoformPPIFlat {
             protein_1_pro_id: protein_1_pro_id,
             protein_1_label: protein_1_label,
             protein_2_pro_id: protein_2_pro_id,
             protein_2_label: protein_2_label,
-            relation: proteoform_ppi.relation.clone(),
+            relation: self.relation.clone(),
             source: source_name,
             pmids: Some(pmids)
         };
 
-        //add to vector
-        proteoforms_ppi_flat.push(proteoform_ppi_flat);
+        return proteoform_ppi_flat;
     }
-    return proteoforms_ppi_flat;
 }
 
-pub fn ptm_ppi(ptm_ppis: &Vec<PTMPPI>) -> Vec<PTMPPIFlat> {
-    let mut ptm_ppis_flat: Vec<PTMPPIFlat> = Vec::new();
-    for ptm_ppi in ptm_ppis {
+impl Flatten for PTMPPI {
+    type Flat = PTMPPIFlat;
+
+    fn flatten(&self) -> PTMPPIFlat {
         //flatten substrate
         let substrate_uniprot_id;
         let substrate_name;
-        match &ptm_ppi.substrate {
+        match &self.substrate {
             &Some(ref substrate) => {
                 substrate_uniprot_id = substrate.uniprot_id.clone();
                 substrate_name = substrate.name.clone();
@@ -277,7 +324,7 @@ pub fn ptm_ppi(ptm_ppis: &Vec<PTMPPI>) -> Vec<PTMPPIFlat> {
         //flatten interactant
         let interactant_uniprot_id;
         let interactant_name;
-        match &ptm_ppi.interactant {
+        match &self.interactant {
             &Some(ref interactant) => {
                 interactant_uniprot_id = interactant.uniprot_id.clone();
                 interactant_name = interactant.name.clone();
@@ -290,7 +337,7 @@ pub fn ptm_ppi(ptm_ppis: &Vec<PTMPPI>) -> Vec<PTMPPIFlat> {
 
         //source
         let source_name;
-        match &ptm_ppi.source {
+        match &self.source {
             &Some(ref source) => {
                 source_name = source.name.clone(); 
             },
@@ -299,35 +346,31 @@ pub fn ptm_ppi(ptm_ppis: &Vec<PTMPPI>) -> Vec<PTMPPIFlat> {
             }
         }
 
-        //build flat ptm_ppi
+        //build flat self
         let ptm_ppi_flat = PTMPPIFlat {
-            ptm_type: ptm_ppi.ptm_type.clone(),
+            ptm_type: self.ptm_type.clone(),
             substrate_uniprot_id: substrate_uniprot_id,
             substrate_name: substrate_name,
-            site: ptm_ppi.site.clone(),
+            site: self.site.clone(),
             interactant_uniprot_id: interactant_uniprot_id,
             interactant_name: interactant_name,
-            association_type: ptm_ppi.association_type.clone(),
+            association_type: self.association_type.clone(),
             source: source_name,
-            pmid: ptm_ppi.pmid.clone()
+            pmid: self.pmid.clone()
         };
 
-        //add to vector
-        ptm_ppis_flat.push(ptm_ppi_flat);
+        return ptm_ppi_flat;
     }
-
-    return ptm_ppis_flat;
 }
 
-pub fn batch_ptm_enzymes(batch_ptm_enzymes: &Vec<BatchPTMEnzyme>) -> Vec<BatchPTMEnzymeFlat>{
-    
-    let mut batch_ptm_enzymes_flat: Vec<BatchPTMEnzymeFlat> = Vec::new();
+impl Flatten for BatchPTMEnzyme {
+    type Flat = BatchPTMEnzymeFlat;
 
-    for batch_ptm_enzyme in batch_ptm_enzymes {
+    fn flatten(&self) -> BatchPTMEnzymeFlat {
         //flatten enzyme
         let enz_name;
         let enz_id;
-        match &batch_ptm_enzyme.enzyme {
+        match &self.enzyme {
             &Some(ref enzyme) => {
                 enz_name = enzyme.name.clone();
                 enz_id = enzyme.uniprot_id.clone();
@@ -341,7 +384,7 @@ pub fn batch_ptm_enzymes(batch_ptm_enzymes: &Vec<BatchPTMEnzyme>) -> Vec<BatchPT
         //flatten substrate
         let sub_name;
         let sub_id;
-        match &batch_ptm_enzyme.substrate {
+        match &self.substrate {
             &Some(ref substrate) => {
                 sub_name = substrate.name.clone();
                 sub_id = substrate.uniprot_id.clone();
@@ -353,38 +396,36 @@ pub fn batch_ptm_enzymes(batch_ptm_enzymes: &Vec<BatchPTMEnzyme>) -> Vec<BatchPT
         }
 
         //fatten sources
-        let sources = sources(&batch_ptm_enzyme.source);
+        let sources = sources(&self.source);
 
-        //build flat batch_ptm_enzyme
+        //build flat self
         let batch_ptm_enzyme_flat = BatchPTMEnzymeFlat {
             enz_id: enz_id,
             enz_name: enz_name,
             sub_id: sub_id,
             sub_name: sub_name,
-            ptm_type: batch_ptm_enzyme.ptm_type.clone(),
-            site: batch_ptm_enzyme.site.clone(),
-            site_position: batch_ptm_enzyme.site_position.clone(),
-            score: batch_ptm_enzyme.score.clone(),
+            ptm_type: self.ptm_type.clone(),
+            site: self.site.clone(),
+            site_position: self.site_position.clone(),
+            score: self.score.clone(),
             source: Some(sources),
-            pmids: Some(misc::str_vec_to_str(&batch_ptm_enzyme.pmids)),
+            pmids: Some(misc::str_vec_to_str(&self.pmids)),
+            query_site: self.query_site.clone(),
+            offset: self.offset,
         };
 
-        batch_ptm_enzymes_flat.push(batch_ptm_enzyme_flat);
-
+        return batch_ptm_enzyme_flat;
     }
-
-    return batch_ptm_enzymes_flat;
-
 }
 
-pub fn batch_ptm_ppi(batch_ptm_ppis: &Vec<BatchPTMPPI>) -> Vec<BatchPTMPPIFlat> {
-    let mut batch_ptm_ppis_flat: Vec<BatchPTMPPIFlat> = Vec::new();
+impl Flatten for BatchPTMPPI {
+    type Flat = BatchPTMPPIFlat;
 
-    for batch_ptm_ppi in batch_ptm_ppis {
+    fn flatten(&self) -> BatchPTMPPIFlat {
         //flatten interactant
         let interactant_uniprot_id;
         let interactant_name;
-        match &batch_ptm_ppi.interactant {
+        match &self.interactant {
             &Some(ref interactant) => {
                 interactant_uniprot_id = interactant.uniprot_id.clone();
                 interactant_name = interactant.name.clone();
@@ -398,7 +439,7 @@ pub fn batch_ptm_ppi(batch_ptm_ppis: &Vec<BatchPTMPPI>) -> Vec<BatchPTMPPIFlat>
         //flatten substrate
         let substrate_uniprot_id;
         let substrate_name;
-        match &batch_ptm_ppi.substrate {
+        match &self.substrate {
             &Some(ref substrate) => {
                 substrate_uniprot_id = substrate.uniprot_id.clone();
                 substrate_name = substrate.name.clone();
@@ -411,7 +452,7 @@ pub fn batch_ptm_ppi(batch_ptm_ppis: &Vec<BatchPTMPPI>) -> Vec<BatchPTMPPIFlat>
 
         //source
         let source_name;
-        match &batch_ptm_ppi.source {
+        match &self.source {
             &Some(ref source) => {
                 source_name = source.name.clone(); 
             },
@@ -420,26 +461,333 @@ pub fn batch_ptm_ppi(batch_ptm_ppis: &Vec<BatchPTMPPI>) -> Vec<BatchPTMPPIFlat>
             }
         }
 
-        // build flat batch_ptm_ppi
+        // build flat self
         let batch_ptm_ppi_flat = BatchPTMPPIFlat {
-            ptm_type: batch_ptm_ppi.ptm_type.clone(),
-            site: batch_ptm_ppi.site.clone(),
-            site_position: batch_ptm_ppi.site_position.clone(),
-            association_type: batch_ptm_ppi.association_type.clone(),
+            ptm_type: self.ptm_type.clone(),
+            site: self.site.clone(),
+            site_position: self.site_position.clone(),
+            association_type: self.association_type.clone(),
             interactant_id: interactant_uniprot_id,
             interactant_name: interactant_name,
             substrate_id: substrate_uniprot_id,
             substrate_name: substrate_name,
             source: source_name,
-            pmids: Some(misc::str_vec_to_str(&batch_ptm_ppi.pmids))
+            pmids: Some(misc::str_vec_to_str(&self.pmids)),
+            query_site: self.query_site.clone(),
+            offset: self.offset
+        };
+
+        return batch_ptm_ppi_flat;
+    }
+}
+
+// The contributing sites are written as accession:site pairs, e.g. Q15796:K19
+impl Flatten for EnzymeActivity {
+    type Flat = EnzymeActivityFlat;
+
+    fn flatten(&self) -> EnzymeActivityFlat {
+        //flatten enzyme
+        let enz_name;
+        let enz_id;
+        match &self.enzyme {
+            &Some(ref enzyme) => {
+                enz_name = enzyme.name.clone();
+                enz_id = enzyme.uniprot_id.clone();
+            },
+            &None => {
+                enz_name = None;
+                enz_id = None;
+            }
+        }
+
+        //flatten sites
+        let mut sites: Vec<String> = Vec::new();
+        for site in &self.sites {
+            let sub_id = site.substrate.as_ref().and_then(|substrate| substrate.uniprot_id.clone()).unwrap_or(String::new());
+            let site_name = site.site.clone().unwrap_or(String::new());
+            sites.push(format!("{sub_id}:{site}",sub_id=sub_id,site=site_name));
+        }
+
+        //build flat self
+        let enzyme_activity_flat = EnzymeActivityFlat {
+            enz_name: enz_name,
+            enz_id: enz_id,
+            num_sites: self.num_sites,
+            mean_fold_change: self.mean_fold_change,
+            z_score: self.z_score,
+            p_value: self.p_value,
+            fdr: self.fdr,
+            sites: misc::str_vec_to_str(&sites),
+        };
+
+        return enzyme_activity_flat;
+    }
+}
+
+impl Flatten for PtmEnrichment {
+    type Flat = PtmEnrichmentFlat;
+
+    fn flatten(&self) -> PtmEnrichmentFlat {
+        let ptm_enrichment_flat = PtmEnrichmentFlat {
+            ptm_type: self.ptm_type.clone(),
+            ptm_label: self.ptm_label.clone(),
+            count: self.count,
+            list_size: self.list_size,
+            background_count: self.background_count,
+            background_size: self.background_size,
+            fold_enrichment: self.fold_enrichment,
+            p_value: self.p_value,
+            fdr: self.fdr,
+            proteins: misc::str_vec_to_str(&self.proteins),
+        };
+
+        return ptm_enrichment_flat;
+    }
+}
+
+// The entries of an ambiguous mapping are written comma separated, in the same order in every column
+impl Flatten for IdMapping {
+    type Flat = IdMappingFlat;
+
+    fn flatten(&self) -> IdMappingFlat {
+        let mut entry_codes: Vec<String> = Vec::new();
+        let mut gene_names: Vec<String> = Vec::new();
+        let mut taxon_codes: Vec<String> = Vec::new();
+        for entry in &self.entries {
+            entry_codes.push(entry.iptm_entry_code.clone());
+            gene_names.push(entry.gene_name.clone().unwrap_or(String::new()));
+            taxon_codes.push(entry.organism.taxon_code.clone().unwrap_or(String::new()));
+        }
+
+        let id_mapping_flat = IdMappingFlat {
+            input: self.input.clone(),
+            status: self.status,
+            match_type: self.match_type,
+            iptm_entry_codes: misc::str_vec_to_str(&entry_codes),
+            gene_names: misc::str_vec_to_str(&gene_names),
+            organism_taxon_codes: misc::str_vec_to_str(&taxon_codes),
         };
 
-        //add to vector
-        batch_ptm_ppis_flat.push(batch_ptm_ppi_flat);
+        return id_mapping_flat;
+    }
+}
+
+// Variants are already flat
+impl Flatten for Variant {
+    type Flat = Variant;
 
+    fn flatten(&self) -> Variant {
+        return self.clone();
     }
+}
+
+// A site record is written as a single row, its lists joined with commas
+impl Flatten for SiteRecord {
+    type Flat = SiteRecordFlat;
+
+    fn flatten(&self) -> SiteRecordFlat {
+        //flatten enzymes as their names
+        let enzyme_names: Vec<String> = self.enzymes.iter().filter_map(|enzyme| enzyme.name.clone()).collect();
+
+        //flatten ptm ppis as their interactants
+        let mut interactants: Vec<String> = Vec::new();
+        for ptm_ppi in &self.ptm_ppis {
+            match ptm_ppi.interactant.as_ref().and_then(|interactant| interactant.uniprot_id.clone()) {
+                Some(uniprot_id) => {
+                    interactants.push(uniprot_id);
+                },
+                None => {}
+            }
+        }
+
+        //flatten variants as residue, position and mutated residue, e.g. S245R
+        let mut variants: Vec<String> = Vec::new();
+        for variant in &self.variants {
+            variants.push(format!("{residue_sequence}{position}{residue_mutated}",
+                                  residue_sequence=variant.residue_sequence.clone().unwrap_or_default(),
+                                  position=variant.position,
+                                  residue_mutated=variant.residue_mutated.clone().unwrap_or_default()));
+        }
+
+        //flatten proteoforms as their pro ids
+        let proteoforms: Vec<String> = self.proteoforms.iter().filter_map(|proteoform| proteoform.pro_id.clone()).collect();
+
+        //every pmid cited at the site
+        let mut pmids: Vec<String> = Vec::new();
+        for event in &self.events {
+            pmids.extend(event.pmids.iter().cloned());
+        }
+        for ptm_ppi in &self.ptm_ppis {
+            pmids.extend(misc::to_pmid_list(ptm_ppi.pmid.clone()));
+        }
+        for variant in &self.variants {
+            pmids.extend(misc::to_pmid_list(variant.pmid.clone()));
+        }
+        for proteoform in &self.proteoforms {
+            pmids.extend(proteoform.pmids.iter().cloned());
+        }
+
+        let site_record_flat = SiteRecordFlat {
+            uniprot_ac: self.uniprot_ac.clone(),
+            site: self.site.clone(),
+            site_status: self.site_check.status,
+            ptm_types: misc::str_vec_to_str(&self.ptm_types),
+            score: self.score,
+            enzymes: misc::str_vec_to_str(&misc::remove_duplicates(&enzyme_names)),
+            interactants: misc::str_vec_to_str(&misc::remove_duplicates(&interactants)),
+            variants: misc::str_vec_to_str(&variants),
+            proteoforms: misc::str_vec_to_str(&misc::remove_duplicates(&proteoforms)),
+            pmids: misc::str_vec_to_str(&misc::remove_duplicates(&pmids))
+        };
+
+        return site_record_flat;
+    }
+}
+
+impl Rows for Info {
+    type Row = InfoFlat;
+
+    fn rows(&self) -> Vec<InfoFlat> {
+        return vec![self.flatten()];
+    }
+}
 
-    return batch_ptm_ppis_flat;
+impl Rows for SiteRecord {
+    type Row = SiteRecordFlat;
+
+    fn rows(&self) -> Vec<SiteRecordFlat> {
+        return vec![self.flatten()];
+    }
+}
+
+// A publication is written as one row for every record citing it, the record_type column telling
+// which group the record comes from
+impl Rows for Publication {
+    type Row = PublicationRecordFlat;
+
+    fn rows(&self) -> Vec<PublicationRecordFlat> {
+        let mut rows: Vec<PublicationRecordFlat> = Vec::new();
+
+        for ptm_event in &self.ptm_events {
+            let mut row = publication_record(&self.pmid,PublicationRecordType::PtmEvent);
+            set_entity(&ptm_event.substrate,&mut row.substrate_id,&mut row.substrate_name);
+            row.site = ptm_event.site.clone();
+            row.ptm_type = ptm_event.ptm_type.clone();
+            row.sources = Some(sources(&ptm_event.sources));
+            row.pmids = Some(misc::str_vec_to_str(&ptm_event.pmids));
+            rows.push(row);
+        }
+
+        for enzyme_substrate in &self.enzyme_substrates {
+            let mut row = publication_record(&self.pmid,PublicationRecordType::EnzymeSubstrate);
+            set_entity(&enzyme_substrate.substrate,&mut row.substrate_id,&mut row.substrate_name);
+            set_entity(&enzyme_substrate.enzyme,&mut row.enzyme_id,&mut row.enzyme_name);
+            row.site = enzyme_substrate.site.clone();
+            row.ptm_type = enzyme_substrate.ptm_type.clone();
+            row.score = Some(enzyme_substrate.score);
+            row.sources = Some(sources(&enzyme_substrate.source));
+            row.pmids = Some(misc::str_vec_to_str(&enzyme_substrate.pmids));
+            rows.push(row);
+        }
+
+        for proteoform in &self.proteoforms {
+            let mut row = publication_record(&self.pmid,PublicationRecordType::Proteoform);
+            row.substrate_id = proteoform.pro_id.clone();
+            row.substrate_name = proteoform.label.clone();
+            set_protein(&proteoform.ptm_enzyme,&mut row.enzyme_id,&mut row.enzyme_name);
+            row.site = Some(misc::str_vec_to_str(&proteoform.sites));
+            row.sources = source_name(&proteoform.source);
+            row.pmids = Some(misc::str_vec_to_str(&proteoform.pmids));
+            rows.push(row);
+        }
+
+        for proteoform_ppi in &self.proteoform_ppis {
+            let mut row = publication_record(&self.pmid,PublicationRecordType::ProteoformPpi);
+            set_protein(&proteoform_ppi.protein_1,&mut row.substrate_id,&mut row.substrate_name);
+            set_protein(&proteoform_ppi.protein_2,&mut row.interactant_id,&mut row.interactant_name);
+            row.association_type = proteoform_ppi.relation.clone();
+            row.sources = source_name(&proteoform_ppi.source);
+            row.pmids = Some(misc::str_vec_to_str(&proteoform_ppi.pmids));
+            rows.push(row);
+        }
+
+        for ptm_ppi in &self.ptm_ppis {
+            let mut row = publication_record(&self.pmid,PublicationRecordType::PtmPpi);
+            set_entity(&ptm_ppi.substrate,&mut row.substrate_id,&mut row.substrate_name);
+            set_entity(&ptm_ppi.interactant,&mut row.interactant_id,&mut row.interactant_name);
+            row.site = ptm_ppi.site.clone();
+            row.ptm_type = ptm_ppi.ptm_type.clone();
+            row.association_type = ptm_ppi.association_type.clone();
+            row.sources = source_name(&ptm_ppi.source);
+            row.pmids = ptm_ppi.pmid.clone();
+            rows.push(row);
+        }
+
+        return rows;
+    }
+}
+
+//the publications of a batch, their rows one after the other
+impl Rows for Vec<Publication> {
+    type Row = PublicationRecordFlat;
+
+    fn rows(&self) -> Vec<PublicationRecordFlat> {
+        let mut rows: Vec<PublicationRecordFlat> = Vec::new();
+        for publication in self {
+            rows.append(&mut publication.rows());
+        }
+        return rows;
+    }
+}
+
+fn publication_record(pmid: &str, record_type: PublicationRecordType) -> PublicationRecordFlat {
+    return PublicationRecordFlat {
+        pmid: String::from(pmid),
+        record_type: record_type,
+        substrate_id: None,
+        substrate_name: None,
+        site: None,
+        ptm_type: None,
+        enzyme_id: None,
+        enzyme_name: None,
+        interactant_id: None,
+        interactant_name: None,
+        association_type: None,
+        score: None,
+        sources: None,
+        pmids: None
+    };
+}
+
+fn set_entity(entity: &Option<Entity>, id: &mut Option<String>, name: &mut Option<String>) {
+    match entity {
+        &Some(ref entity) => {
+            *id = entity.uniprot_id.clone();
+            *name = entity.name.clone();
+        },
+        &None => {}
+    }
+}
+
+fn set_protein(protein: &Option<Protein>, id: &mut Option<String>, name: &mut Option<String>) {
+    match protein {
+        &Some(ref protein) => {
+            *id = protein.pro_id.clone();
+            *name = protein.label.clone();
+        },
+        &None => {}
+    }
+}
+
+fn source_name(source: &Option<Source>) -> Option<String> {
+    match source {
+        &Some(ref source) => {
+            return source.name.clone();
+        },
+        &None => {
+            return None;
+        }
+    }
 }
 
 pub fn sources(sources: &Vec<Source>) -> String {
@@ -0,0 +1,133 @@
+use models::{IdMapping, MappedEntry, MappingStatus, MatchType};
+use std::collections::{HashMap, HashSet};
+
+//the entries by upper cased accession, gene name and gene synonym, as positions in the sorted entries
+struct EntryIndex {
+    entries: Vec<MappedEntry>,
+    by_code: HashMap<String,Vec<usize>>,
+    by_gene_name: HashMap<String,Vec<usize>>,
+    by_synonym: HashMap<String,Vec<usize>>,
+}
+
+impl EntryIndex {
+    fn new(mut entries: Vec<MappedEntry>) -> EntryIndex {
+        entries.sort_by(|a,b| a.iptm_entry_code.cmp(&b.iptm_entry_code));
+
+        let mut by_code: HashMap<String,Vec<usize>> = HashMap::new();
+        let mut by_gene_name: HashMap<String,Vec<usize>> = HashMap::new();
+        let mut by_synonym: HashMap<String,Vec<usize>> = HashMap::new();
+        for (position,entry) in entries.iter().enumerate() {
+            by_code.entry(entry.iptm_entry_code.trim().to_uppercase()).or_insert(Vec::new()).push(position);
+            match entry.gene_name {
+                Some(ref gene_name) => {
+                    by_gene_name.entry(gene_name.trim().to_uppercase()).or_insert(Vec::new()).push(position);
+                },
+                None => {}
+            }
+            for synonym in &entry.synonyms {
+                let positions = by_synonym.entry(synonym.trim().to_uppercase()).or_insert(Vec::new());
+                if !positions.contains(&position) {
+                    positions.push(position);
+                }
+            }
+        }
+
+        return EntryIndex {
+            entries: entries,
+            by_code: by_code,
+            by_gene_name: by_gene_name,
+            by_synonym: by_synonym
+        };
+    }
+
+    fn get(&self, index: &HashMap<String,Vec<usize>>, key: &str) -> Vec<MappedEntry> {
+        match index.get(key) {
+            Some(positions) => {
+                return positions.iter().map(|position| self.entries[*position].clone()).collect();
+            },
+            None => {
+                return Vec::new();
+            }
+        }
+    }
+}
+
+// Maps every token to the iPTMnet entries it names, given the candidates found for all the tokens.
+// A token is compared with the entries case insensitively, in this order, and the first kind of
+// match that finds entries wins:
+//
+// * the accession of an entry, or the id of a PRO entry, e.g. Q15796 or PR:000025963
+// * the gene name of an entry, e.g. SMAD2
+// * one of the gene synonyms of an entry, e.g. MADH2
+//
+// Only the entries of the given organisms are kept when any are given. A token matching several
+// entries is ambiguous and lists all of them, sorted by entry code, and a token matching none is
+// unmapped. The mappings are in the order of the tokens.
+pub fn map_ids(tokens: &Vec<String>, candidates: Vec<MappedEntry>, organism_taxons: &Vec<i32>) -> Vec<IdMapping> {
+    let taxon_codes: Vec<String> = organism_taxons.iter().map(|taxon| format!("{taxon}",taxon=taxon)).collect();
+
+    //the candidates of the organisms, each entry once
+    let mut entry_codes: HashSet<String> = HashSet::new();
+    let mut entries: Vec<MappedEntry> = Vec::new();
+    for candidate in candidates {
+        let in_organisms = taxon_codes.is_empty()
+                           || candidate.organism.taxon_code.as_ref().map(|taxon_code| taxon_codes.contains(taxon_code)).unwrap_or(false);
+        if in_organisms && entry_codes.insert(candidate.iptm_entry_code.clone()) {
+            entries.push(candidate);
+        }
+    }
+    let entry_index = EntryIndex::new(entries);
+
+    let mut id_mappings: Vec<IdMapping> = Vec::new();
+    for token in tokens {
+        id_mappings.push(map_id(token,&entry_index));
+    }
+    return id_mappings;
+}
+
+fn map_id(token: &str, entry_index: &EntryIndex) -> IdMapping {
+    let token_upper = token.trim().to_uppercase();
+
+    let mut matches: Vec<MappedEntry> = Vec::new();
+    let mut match_type: Option<MatchType> = None;
+    if !token_upper.is_empty() {
+        matches = entry_index.get(&entry_index.by_code,&token_upper);
+        if !matches.is_empty() {
+            if matches.iter().all(|entry| entry.entry_type == Some(String::from("pro_id"))) {
+                match_type = Some(MatchType::Pro);
+            }else{
+                match_type = Some(MatchType::Accession);
+            }
+        }
+    }
+
+    if matches.is_empty() && !token_upper.is_empty() {
+        matches = entry_index.get(&entry_index.by_gene_name,&token_upper);
+        if !matches.is_empty() {
+            match_type = Some(MatchType::GeneName);
+        }
+    }
+
+    if matches.is_empty() && !token_upper.is_empty() {
+        matches = entry_index.get(&entry_index.by_synonym,&token_upper);
+        if !matches.is_empty() {
+            match_type = Some(MatchType::Synonym);
+        }
+    }
+
+    let status;
+    if matches.is_empty() {
+        status = MappingStatus::Unmapped;
+    }else if matches.len() == 1 {
+        status = MappingStatus::Mapped;
+    }else{
+        status = MappingStatus::Ambiguous;
+    }
+
+    return IdMapping {
+        input: String::from(token),
+        status: status,
+        match_type: match_type,
+        entries: matches
+    };
+}
@@ -0,0 +1,641 @@
+use database;
+use errors::*;
+use misc;
+use models::*;
+use render::Flatten;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use stream::RowWriter;
+use upload::InputRow;
+use validation;
+
+//number of query substrates looked up between two progress updates
+const JOB_CHUNK_SIZE: usize = database::BATCH_QUERY_SIZE;
+
+//seconds between two removals of the expired jobs
+const CLEANUP_INTERVAL: u64 = 60;
+
+#[derive(Clone)]
+pub struct JobParams {
+    pub directory: String,
+    pub workers: usize,
+    pub max_queued: usize,
+    pub ttl: u64
+}
+
+struct Task {
+    id: String,
+    kind: JobKind,
+    rows: Vec<InputRow>,
+    strict: bool,
+    tolerance: i64
+}
+
+/// Runs the batch jobs on a fixed number of worker threads.
+///
+/// Every job is kept in memory and saved as `<id>.json` in the job directory each time it changes,
+/// and its result is written there as `<id>.ndjson`, one item per line. Finished jobs are removed,
+/// with their result, once their ttl is over. Jobs found on disk at startup are loaded again, and
+/// the ones that were still queued or running are marked as failed.
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: Arc<Mutex<HashMap<String,Job>>>,
+    sender: Arc<Mutex<mpsc::SyncSender<Task>>>,
+    directory: PathBuf,
+    max_queued: usize,
+    ttl: u64
+}
+
+impl JobQueue {
+    //loads the saved jobs and starts the workers and the cleanup of the expired jobs
+    pub fn start(job_params: &JobParams, db_pool: database::Pool) -> Result<JobQueue> {
+        match fs::create_dir_all(&job_params.directory) {
+            Ok(_) => {},
+            Err(error) => {
+                return Err(format!("Could not create the job directory {directory} - {error}",directory=job_params.directory,error=error).into());
+            }
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(job_params.max_queued);
+        let job_queue = JobQueue {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            sender: Arc::new(Mutex::new(sender)),
+            directory: PathBuf::from(&job_params.directory),
+            max_queued: job_params.max_queued,
+            ttl: job_params.ttl
+        };
+        job_queue.load()?;
+
+        //the workers take the tasks from a shared receiver
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..job_params.workers {
+            let worker_queue = job_queue.clone();
+            let worker_receiver = receiver.clone();
+            let worker_pool = db_pool.clone();
+            thread::spawn(move || {
+                worker_queue.work(worker_receiver,worker_pool);
+            });
+        }
+
+        let cleanup_queue = job_queue.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(Duration::from_secs(CLEANUP_INTERVAL));
+                cleanup_queue.remove_expired();
+            }
+        });
+
+        return Ok(job_queue);
+    }
+
+    // Queues a job looking up the rows of a batch within tolerance positions, failing with
+    // ErrorKind::QueueFull when max_queued jobs are waiting. The rows are validated once the job
+    // starts, and in strict mode the job fails when any of them is rejected.
+    pub fn submit(&self, kind: JobKind, rows: Vec<InputRow>, strict: bool, tolerance: i64) -> Result<Job> {
+        let job = Job {
+            id: new_job_id(),
+            kind: kind,
+            status: JobStatus::Queued,
+            progress: JobProgress {
+                processed: 0,
+                total: rows.len()
+            },
+            submitted: now(),
+            started: None,
+            finished: None,
+            expires: None,
+            error: None,
+            report: None
+        };
+
+        //the job is known before a worker can take it
+        {
+            let mut jobs = self.lock_jobs();
+            self.save(&job)?;
+            jobs.insert(job.id.clone(),job.clone());
+        }
+
+        let task = Task {
+            id: job.id.clone(),
+            kind: kind,
+            rows: rows,
+            strict: strict,
+            tolerance: tolerance
+        };
+
+        let send_result = lock(&self.sender).try_send(task);
+        match send_result {
+            Ok(_) => {
+                info!("Queued job {id}",id=job.id);
+                return Ok(job);
+            },
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.remove(&job.id);
+                return Err(ErrorKind::QueueFull(format!("{max_queued} jobs are waiting already, try again later",max_queued=self.max_queued)).into());
+            },
+            Err(mpsc::TrySendError::Disconnected(_)) => {
+                self.remove(&job.id);
+                return Err("The job workers have stopped".into());
+            }
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Result<Job> {
+        match self.lock_jobs().get(id) {
+            Some(job) => {
+                return Ok(job.clone());
+            },
+            None => {
+                return Err(unknown_job(id));
+            }
+        }
+    }
+
+    // Cancels a queued or running job, which keeps it until its ttl is over. A finished job is
+    // removed right away along with its result. Returns the job as it was left.
+    pub fn cancel(&self, id: &str) -> Result<Job> {
+        let ttl = self.ttl;
+        let cancelled = self.update(id,|job| {
+            if job.status != JobStatus::Queued && job.status != JobStatus::Running {
+                return false;
+            }
+            let finished = now();
+            job.status = JobStatus::Cancelled;
+            job.finished = Some(finished);
+            job.expires = Some(finished + ttl);
+            return true;
+        });
+
+        let job = self.get(id)?;
+        if cancelled {
+            info!("Cancelled job {id}",id=id);
+        }else{
+            self.remove(id);
+            info!("Removed job {id}",id=id);
+        }
+        return Ok(job);
+    }
+
+    //the completed job and the path of its result, failing with ErrorKind::Conflict when the job is not completed
+    pub fn result(&self, id: &str) -> Result<(Job,PathBuf)> {
+        let job = self.get(id)?;
+        match job.status {
+            JobStatus::Completed => {
+                let path = self.result_path(id);
+                return Ok((job,path));
+            },
+            JobStatus::Failed => {
+                let error = job.error.clone().unwrap_or(String::new());
+                return Err(ErrorKind::Conflict(format!("Job {id} failed - {error}",id=id,error=error)).into());
+            },
+            _ => {
+                return Err(ErrorKind::Conflict(format!("Job {id} is {status}, its result is only available once it is completed",id=id,status=status_name(job.status))).into());
+            }
+        }
+    }
+
+    fn work(&self, receiver: Arc<Mutex<mpsc::Receiver<Task>>>, db_pool: database::Pool) {
+        loop {
+            let task;
+            match lock(&receiver).recv() {
+                Ok(value) => {
+                    task = value;
+                },
+                Err(_error) => {
+                    //every sender is gone, the server is stopping
+                    return;
+                }
+            }
+            //a panicking job, like one reading an unexpected NULL, fails without taking its worker down
+            let id = task.id.clone();
+            match panic::catch_unwind(AssertUnwindSafe(|| self.run(task,&db_pool))) {
+                Ok(_) => {},
+                Err(payload) => {
+                    remove_file(&self.part_path(&id));
+                    if !self.is_cancelled(&id) {
+                        self.fail(&id,format!("The job stopped unexpectedly - {message}",message=misc::panic_message(&payload)));
+                    }
+                }
+            }
+        }
+    }
+
+    fn run(&self, task: Task, db_pool: &database::Pool) {
+        //jobs cancelled while queued are not started
+        let started = self.update(&task.id,|job| {
+            if job.status != JobStatus::Queued {
+                return false;
+            }
+            job.status = JobStatus::Running;
+            job.started = Some(now());
+            return true;
+        });
+        if !started {
+            return;
+        }
+
+        let id = task.id.clone();
+        let query_substrates;
+        match self.validate(&id,task.rows,task.strict,db_pool) {
+            Ok(Some(value)) => {
+                query_substrates = value;
+            },
+            Ok(None) => {
+                return;
+            },
+            Err(error) => {
+                self.fail(&id,format!("{}",error));
+                return;
+            }
+        }
+
+        let part_path = self.part_path(&id);
+        let tolerance = task.tolerance;
+        let write_result;
+        match task.kind {
+            JobKind::PtmEnzymes => {
+                write_result = self.build_result(&id,query_substrates,&part_path,db_pool,|conn,chunk| conn.get_ptm_enzymes(chunk,tolerance));
+            },
+            JobKind::PtmPpi => {
+                write_result = self.build_result(&id,query_substrates,&part_path,db_pool,|conn,chunk| conn.get_ptm_ppi(chunk,tolerance));
+            }
+        }
+
+        let ttl = self.ttl;
+        match write_result {
+            Ok(true) => {
+                //the job may have been cancelled since the last chunk, in which case the result is dropped
+                let renamed = fs::rename(&part_path,self.result_path(&id));
+                let completed = renamed.is_ok() && self.update(&id,|job| {
+                    if job.status != JobStatus::Running {
+                        return false;
+                    }
+                    let finished = now();
+                    job.status = JobStatus::Completed;
+                    job.progress.processed = job.progress.total;
+                    job.finished = Some(finished);
+                    job.expires = Some(finished + ttl);
+                    return true;
+                });
+
+                if completed {
+                    info!("Completed job {id}",id=id);
+                }else{
+                    remove_file(&part_path);
+                    remove_file(&self.result_path(&id));
+                    match renamed {
+                        Ok(_) => {},
+                        Err(error) => {
+                            self.fail(&id,format!("Could not save the result - {}",error));
+                        }
+                    }
+                }
+            },
+            Ok(false) => {
+                remove_file(&part_path);
+            },
+            Err(error) => {
+                remove_file(&part_path);
+                self.fail(&id,format!("{}",error));
+            }
+        }
+    }
+
+    // Validates the rows and keeps the report with the job, counting the rows left out as processed.
+    // Returns None when the job failed because of rejected rows in strict mode.
+    fn validate(&self, id: &str, rows: Vec<InputRow>, strict: bool, db_pool: &database::Pool) -> Result<Option<Vec<QuerySubstrate>>> {
+        let row_count = rows.len();
+        let (query_substrates, report) = {
+            let conn = database::get_connection(db_pool)?;
+            validation::validate(&**conn,rows,false)?
+        };
+
+        let rejected_count = report.rejected_rows.len();
+        let skipped_count = row_count - query_substrates.len();
+        self.update(id,|job| {
+            job.progress.processed = skipped_count;
+            job.report = Some(report);
+            return true;
+        });
+
+        if strict && rejected_count > 0 {
+            self.fail(id,format!("{count} rows of the batch were rejected",count=rejected_count));
+            return Ok(None);
+        }
+
+        return Ok(Some(query_substrates));
+    }
+
+    // Looks up the query substrates chunk by chunk with fetch and writes the items to path, one per
+    // line, updating the progress after every chunk. Returns false when the job was cancelled.
+    fn build_result<T,F>(&self, id: &str, query_substrates: Vec<QuerySubstrate>, path: &Path, db_pool: &database::Pool, fetch: F) -> Result<bool>
+        where T: Serialize,
+              F: Fn(&database::Connection,&Vec<QuerySubstrate>) -> Result<Vec<T>>
+    {
+        let mut wtr = BufWriter::new(File::create(path)?);
+
+        let mut remaining = query_substrates;
+        while !remaining.is_empty() {
+            if self.is_cancelled(id) {
+                return Ok(false);
+            }
+
+            let chunk_size = remaining.len().min(JOB_CHUNK_SIZE);
+            let rest = remaining.split_off(chunk_size);
+            let chunk = remaining;
+            remaining = rest;
+
+            //the connection goes back to the pool between chunks, so that long jobs do not hold it
+            let items;
+            {
+                let conn = database::get_connection(db_pool)?;
+                items = fetch(&conn,&chunk)?;
+            }
+
+            for item in items {
+                match serde_json::to_writer(&mut wtr,&item) {
+                    Ok(_) => {},
+                    Err(error) => {
+                        return Err(format!("{}",error).into());
+                    }
+                }
+                wtr.write_all(b"\n")?;
+            }
+
+            self.update(id,|job| {
+                job.progress.processed += chunk.len();
+                return true;
+            });
+        }
+
+        wtr.flush()?;
+        return Ok(true);
+    }
+
+    fn fail(&self, id: &str, error: String) {
+        error!("Job {id} failed - {error}",id=id,error=error);
+        let ttl = self.ttl;
+        self.update(id,|job| {
+            let finished = now();
+            job.status = JobStatus::Failed;
+            job.error = Some(error);
+            job.finished = Some(finished);
+            job.expires = Some(finished + ttl);
+            return true;
+        });
+    }
+
+    fn is_cancelled(&self, id: &str) -> bool {
+        match self.lock_jobs().get(id) {
+            Some(job) => {
+                return job.status == JobStatus::Cancelled;
+            },
+            None => {
+                return true;
+            }
+        }
+    }
+
+    //applies change to the job and saves it, returns false when the job is unknown or change left it as it was
+    fn update<F>(&self, id: &str, change: F) -> bool
+        where F: FnOnce(&mut Job) -> bool
+    {
+        let mut jobs = self.lock_jobs();
+        match jobs.get_mut(id) {
+            Some(job) => {
+                if !change(job) {
+                    return false;
+                }
+                match self.save(job) {
+                    Ok(_) => {},
+                    Err(error) => {
+                        error!("Could not save job {id} - {error}",id=id,error=error);
+                    }
+                }
+                return true;
+            },
+            None => {
+                return false;
+            }
+        }
+    }
+
+    //forgets the job and deletes its files
+    fn remove(&self, id: &str) {
+        self.lock_jobs().remove(id);
+        remove_file(&self.job_path(id));
+        remove_file(&self.part_path(id));
+        remove_file(&self.result_path(id));
+    }
+
+    fn remove_expired(&self) {
+        let now = now();
+        let expired_ids: Vec<String> = self.lock_jobs().values()
+            .filter(|job| job.expires.map(|expires| expires <= now).unwrap_or(false))
+            .map(|job| job.id.clone())
+            .collect();
+
+        for id in expired_ids {
+            self.remove(&id);
+            info!("Removed expired job {id}",id=id);
+        }
+    }
+
+    //saves the job through a temporary file, so that a crash never leaves half a job on disk
+    fn save(&self, job: &Job) -> Result<()> {
+        let job_serialized;
+        match serde_json::to_string_pretty(job) {
+            Ok(value) => {
+                job_serialized = value;
+            },
+            Err(error) => {
+                return Err(format!("{}",error).into());
+            }
+        }
+
+        let tmp_path = self.directory.join(format!("{id}.json.tmp",id=job.id));
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(job_serialized.as_bytes())?;
+        fs::rename(&tmp_path,self.job_path(&job.id))?;
+        return Ok(());
+    }
+
+    //loads the jobs saved by an earlier run
+    fn load(&self) -> Result<()> {
+        let ttl = self.ttl;
+        let mut jobs = self.lock_jobs();
+
+        for entry_result in fs::read_dir(&self.directory)? {
+            let path = entry_result?.path();
+            if path.extension().map(|extension| extension != "json").unwrap_or(true) {
+                continue;
+            }
+
+            let mut job: Job;
+            match File::open(&path).map(BufReader::new) {
+                Ok(reader) => {
+                    match serde_json::from_reader(reader) {
+                        Ok(value) => {
+                            job = value;
+                        },
+                        Err(error) => {
+                            warn!("Skipping job file {path} - {error}",path=path.display(),error=error);
+                            continue;
+                        }
+                    }
+                },
+                Err(error) => {
+                    warn!("Skipping job file {path} - {error}",path=path.display(),error=error);
+                    continue;
+                }
+            }
+
+            //the queued tasks did not survive the restart
+            if job.status == JobStatus::Queued || job.status == JobStatus::Running {
+                let finished = now();
+                job.status = JobStatus::Failed;
+                job.error = Some(String::from("The server restarted before the job finished"));
+                job.finished = Some(finished);
+                job.expires = Some(finished + ttl);
+                remove_file(&self.part_path(&job.id));
+                self.save(&job)?;
+            }
+
+            jobs.insert(job.id.clone(),job);
+        }
+
+        info!("Loaded {count} jobs from {directory}",count=jobs.len(),directory=self.directory.display());
+        return Ok(());
+    }
+
+    fn lock_jobs(&self) -> MutexGuard<HashMap<String,Job>> {
+        return lock(&self.jobs);
+    }
+
+    fn job_path(&self, id: &str) -> PathBuf {
+        return self.directory.join(format!("{id}.json",id=id));
+    }
+
+    fn part_path(&self, id: &str) -> PathBuf {
+        return self.directory.join(format!("{id}.ndjson.part",id=id));
+    }
+
+    fn result_path(&self, id: &str) -> PathBuf {
+        return self.directory.join(format!("{id}.ndjson",id=id));
+    }
+}
+
+//writes the saved result of a job with the row writer of a streamed response
+pub fn write_result(kind: JobKind, path: &Path, row_writer: &mut RowWriter) -> Result<()> {
+    match kind {
+        JobKind::PtmEnzymes => {
+            return write_items::<BatchPTMEnzyme>(path,row_writer);
+        },
+        JobKind::PtmPpi => {
+            return write_items::<BatchPTMPPI>(path,row_writer);
+        }
+    }
+}
+
+fn write_items<T: DeserializeOwned + Serialize + Flatten>(path: &Path, row_writer: &mut RowWriter) -> Result<()> where T::Flat: Default {
+    row_writer.columns::<T>()?;
+    let reader = BufReader::new(File::open(path)?);
+    for line_result in reader.lines() {
+        let line = line_result?;
+        let item: T;
+        match serde_json::from_str(&line) {
+            Ok(value) => {
+                item = value;
+            },
+            Err(error) => {
+                return Err(format!("Corrupt job result {path} - {error}",path=path.display(),error=error).into());
+            }
+        }
+        row_writer.write(&item)?;
+    }
+    return Ok(());
+}
+
+fn unknown_job(id: &str) -> Error {
+    return ErrorKind::NotFound(format!("No job matches {id}, it may have expired",id=id)).into();
+}
+
+fn status_name(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => {
+            return "queued";
+        },
+        JobStatus::Running => {
+            return "running";
+        },
+        JobStatus::Completed => {
+            return "completed";
+        },
+        JobStatus::Failed => {
+            return "failed";
+        },
+        JobStatus::Cancelled => {
+            return "cancelled";
+        }
+    }
+}
+
+//a job is only as private as its id, so ids are random rather than sequential
+fn new_job_id() -> String {
+    let mut id = String::new();
+    for _ in 0..2 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(now());
+        id.push_str(&format!("{:016x}",hasher.finish()));
+    }
+    return id;
+}
+
+//seconds since the unix epoch
+fn now() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => {
+            return duration.as_secs();
+        },
+        Err(_error) => {
+            return 0;
+        }
+    }
+}
+
+//work catches the panics of the jobs, which may leave a lock poisoned, so a poisoned lock is used anyway
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    match mutex.lock() {
+        Ok(guard) => {
+            return guard;
+        },
+        Err(poisoned) => {
+            return poisoned.into_inner();
+        }
+    }
+}
+
+fn remove_file(path: &Path) {
+    if path.exists() {
+        match fs::remove_file(path) {
+            Ok(_) => {},
+            Err(error) => {
+                warn!("Could not remove {path} - {error}",path=path.display(),error=error);
+            }
+        }
+    }
+}
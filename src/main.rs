@@ -4,6 +4,7 @@
 extern crate env_logger;
 extern crate actix_web;
 extern crate postgres;
+extern crate fallible_iterator;
 extern crate oracle;
 
 extern crate serde;
@@ -11,10 +12,13 @@ extern crate serde_json;
 
 extern crate inner;
 extern crate futures;
+extern crate bytes;
 extern crate csv;
 extern crate ini;
 extern crate bio;
 extern crate rayon;
+extern crate r2d2;
+extern crate rusqlite;
 
 #[macro_use]
 extern crate log;
@@ -29,55 +33,176 @@ extern crate error_chain;
 mod controller;
 mod router;
 mod database;
+mod postgres_backend;
+mod oracle_backend;
+mod sqlite_backend;
+mod sql_backend;
 mod models;
 mod errors;
 mod misc;
 mod flatten;
 mod query_builder;
 mod msa;
+mod config;
+mod problem;
+mod negotiation;
+mod render;
+mod stream;
+mod jobs;
+mod upload;
+mod validation;
+mod analysis;
+mod id_mapping;
+mod site;
+mod publication;
+mod network;
 
 use actix_web::middleware::Logger;
 use actix_web::*;
 
 
 pub use errors::*;
-use ini::Ini;
 
 pub struct State {
-      pub db_params: database::DBParams,
+      pub db_pool: database::Pool,
+      pub body_limit: usize,
+      pub jobs: jobs::JobQueue,
+      pub streams: stream::StreamPool,
 }
 
 fn main() {
-    //Logging is hard coded for now, but soon can be configured through config file    
-    std::env::set_var("RUST_LOG", "actix_web=trace,iptmnet_api=trace");
-    env_logger::Builder::from_default_env()
-        .default_format_timestamp(false)
-        .default_format_module_path(false)
-        .init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli_args;
+    match config::parse_args(&args) {
+            Ok(value) => {
+                cli_args = value;
+            },
+            Err(error) => {
+                eprintln!("{}\n\n{}",error,config::usage());
+                std::process::exit(2);
+            }
+    }
 
     let conf;
-    let conf_result = Ini::load_from_file("config.ini");
-    match conf_result {
+    match config::read(&cli_args.config_path) {
             Ok(value) => {
                 conf = value;
             },
+            Err(error) => {
+                init_logging("info");
+                error!("{}",error);
+                std::process::exit(1);
+            }
+    }
+
+    init_logging(&config::log_level(&conf));
+
+    //report every problem in the config file at once
+    let app_config;
+    match config::parse(&conf) {
+            Ok(value) => {
+                app_config = value;
+            },
+            Err(problems) => {
+                error!("Invalid configuration in {path}",path=cli_args.config_path);
+                for problem in &problems {
+                    error!("{}",problem);
+                }
+                std::process::exit(1);
+            }
+    }
+
+    if cli_args.check_config {
+        check_config(&app_config,&cli_args.config_path);
+    }
+
+    let db_pool;
+    match database::create_pool(&app_config.db,&app_config.pool) {
+            Ok(value) => {
+                db_pool = value;
+            },
             Err(error) => {
                 error!("{}",error);
                 std::process::exit(1);
             }
     }
 
-    let db_params = misc::parse_configs(&conf);    
+    let job_queue;
+    match jobs::JobQueue::start(&app_config.jobs,db_pool.clone()) {
+            Ok(value) => {
+                job_queue = value;
+            },
+            Err(error) => {
+                error!("{}",error);
+                std::process::exit(1);
+            }
+    }
 
+    let server_params = app_config.server;
+    let stream_pool = stream::StreamPool::start(server_params.max_streams);
+    let body_limit = server_params.body_limit;
+    let log_format = server_params.log_format.clone();
     let app = move || {
-            let app = App::with_state(State{db_params: db_params.clone()})
-                                  .middleware(Logger::new("STATUS : %s | %t | %D ms | PID: %P | %r "));
+            let app = App::with_state(State{db_pool: db_pool.clone(), body_limit: body_limit, jobs: job_queue.clone(), streams: stream_pool.clone()})
+                                  .middleware(problem::RequestId)
+                                  .middleware(Logger::new(&log_format));
             return router::init_routes(app);    
     };
 
-    server::HttpServer::new(app)
-            .bind("0.0.0.0:8088")
-            .expect("Can not bind to 0.0.0.0:8080")
-            .keep_alive(None)
-            .run();    
+    let address = format!("{host}:{port}",host=server_params.host,port=server_params.port);
+    let mut server = server::HttpServer::new(app)
+            .keep_alive(server_params.keep_alive);
+
+    match server_params.workers {
+        Some(workers) => {
+            server = server.threads(workers);
+        },
+        None => {
+
+        }
+    }
+
+    match server.bind(&address) {
+        Ok(server) => {
+            info!("Listening on {}",address);
+            server.run();
+        },
+        Err(error) => {
+            error!("Can not bind to {address} - {error}",address=address,error=error);
+            std::process::exit(1);
+        }
+    }
+}
+
+//validates the database connection for --check-config and exits without starting the server
+fn check_config(app_config: &config::Config, config_path: &str) {
+    println!("Configuration {path} is valid",path=config_path);
+
+    match database::connect(&app_config.db) {
+        Ok(conn) => {
+            match conn.is_valid() {
+                Ok(_) => {
+                    println!("Connected to the {engine} database",engine=app_config.db.engine);
+                    std::process::exit(0);
+                },
+                Err(error) => {
+                    eprintln!("Database connection is not usable - {error}",error=error);
+                    std::process::exit(1);
+                }
+            }
+        },
+        Err(error) => {
+            eprintln!("Could not connect to the {engine} database - {error}",engine=app_config.db.engine,error=error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn init_logging(log_level: &str) {
+    let filters = format!("actix_web={level},iptmnet_api={level}",level=log_level);
+    env_logger::Builder::new()
+        .parse(&filters)
+        .default_format_timestamp(false)
+        .default_format_module_path(false)
+        .init();
 }
@@ -1,167 +1,8 @@
 use models::*;
 use std::collections::HashMap;
 use actix_web::dev::Params;
-use actix_web::HttpRequest;
-use actix_web::HttpMessage;
-use database::DBParams;
-use ini;
-use std;
 use errors::*;
-
-pub fn parse_configs(conf: &ini::Ini) -> DBParams {
-    let engine;
-    let mut host = &String::new();;
-    let mut port = &String::new();;
-    let mut user = &String::new();;
-    let mut password = &String::new();;
-    let mut db_name = &String::new();;
-
-    match conf.section(Some("DEFAULT".to_owned())) {
-         Some(section) => {
-                match section.get("driver") {
-                     Some(value) => {
-                        engine = value;
-                     },
-                     None => {
-                        error!("{}","Could not find value 'driver' in section 'DEFAULT'");
-                        std::process::exit(1);               
-                     }
-                } 
-         },
-         None => {
-              error!("{}","Could not find section - DEFAULT");
-              std::process::exit(1);   
-         }   
-    }
-
-    if engine == "postgres" {
-        match conf.section(Some("POSTGRES".to_owned())) {
-             Some(section) => {
-                //host
-                match section.get("host") {
-                        Some(value) => {
-                                host=value;
-                        } None => {
-                                error!("{}","Could not find value 'host' in section 'POSTGRES'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //port
-                match section.get("port") {
-                        Some(value) => {
-                                port=value;
-                        } None => {
-                                error!("{}","Could not find value 'port' in section 'POSTGRES'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //user
-                match section.get("user") {
-                        Some(value) => {
-                                user=value;
-                        } None => {
-                                error!("{}","Could not find value 'user' in section 'POSTGRES'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //pass
-                match section.get("password") {
-                        Some(value) => {
-                                password=value;
-                        } None => {
-                                error!("{}","Could not find value 'password' in section 'POSTGRES'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //db name
-                match section.get("database-name") {
-                        Some(value) => {
-                                db_name =value;
-                        } None => {
-                                error!("{}","Could not find value 'database-name' in section 'POSTGRES'");
-                                std::process::exit(1); 
-                        }
-                }                                                                
-
-             },
-             None => {
-                
-             }   
-        }
-    }else{
-        match conf.section(Some("ORACLE".to_owned())) {
-             Some(section) => {
-                //host
-                match section.get("host") {
-                        Some(value) => {
-                                host=value;
-                        } None => {
-                                error!("{}","Could not find value 'host' in section 'ORACLE'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //port
-                match section.get("port") {
-                        Some(value) => {
-                                port=value;
-                        } None => {
-                                error!("{}","Could not find value 'port' in section 'ORACLE'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //user
-                match section.get("user") {
-                        Some(value) => {
-                                user=value;
-                        } None => {
-                                error!("{}","Could not find value 'user' in section 'ORACLE'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //pass
-                match section.get("password") {
-                        Some(value) => {
-                                password=value;
-                        } None => {
-                                error!("{}","Could not find value 'password' in section 'ORACLE'");
-                                std::process::exit(1); 
-                        }
-                }
-
-                //service-name
-                match section.get("service-name") {
-                        Some(value) => {
-                                db_name =value;
-                        } None => {
-                                error!("{}","Could not find value 'service-name' in section 'ORACLE'");
-                                std::process::exit(1); 
-                        }
-                }
-             },
-             None => {
-                     
-             }
-        }    
-    }
-
-    let db_params = DBParams {
-            engine: engine.clone(),
-            host: host.clone(),
-            port: port.clone(),
-            user: user.clone(),
-            pass: password.clone(),
-            db_name: db_name.clone()
-    };
-
-    return db_params;        
-}
+use std::any::Any;
 
 pub fn to_vec_string(data_str: &Option<String>, seperator: &str) -> Vec<String> {
     match data_str {
@@ -203,7 +44,7 @@ pub fn get_vec_i32_from_param(params: &Params, key: &str)-> Result<Vec<i32>> {
                     values.push(value);
                 },
                 Err(_error) => {
-                    return Err(format!("{value} is not int",value=value_str).into());
+                    return Err(ErrorKind::BadRequest(format!("{key} must be an integer, got {value}",key=key,value=value_str)).into());
                 }
             }            
         }
@@ -211,6 +52,47 @@ pub fn get_vec_i32_from_param(params: &Params, key: &str)-> Result<Vec<i32>> {
     return Ok(values);
 }
 
+//an integer parameter, or the default when the parameter is missing
+pub fn get_i64_from_param(params: &Params, key: &str, default: i64) -> Result<i64> {
+    match params.get(key) {
+        Some(value_str) => {
+            match value_str.parse::<i64>() {
+                Ok(value) => {
+                    return Ok(value);
+                },
+                Err(_error) => {
+                    return Err(ErrorKind::BadRequest(format!("{key} must be an integer, got {value}",key=key,value=value_str)).into());
+                }
+            }
+        },
+        None => {
+            return Ok(default);
+        }
+    }
+}
+
+//a flag given as true/false, 1/0 or yes/no, false when the parameter is missing
+pub fn get_bool_from_param(params: &Params, key: &str) -> Result<bool> {
+    match params.get(key) {
+        Some(value_str) => {
+            match value_str.to_lowercase().as_str() {
+                "true" | "1" | "yes" => {
+                    return Ok(true);
+                },
+                "false" | "0" | "no" => {
+                    return Ok(false);
+                },
+                _ => {
+                    return Err(ErrorKind::BadRequest(format!("{key} must be true or false, got {value}",key=key,value=value_str)).into());
+                }
+            }
+        },
+        None => {
+            return Ok(false);
+        }
+    }
+}
+
 pub fn to_vec_i64(data_str: &Option<String>, seperator: &str) -> Vec<i64> {
     match data_str {
         &Some(ref data) => {
@@ -305,19 +187,6 @@ pub fn str_vec_to_str_with_sep(items: &Vec<String>,seperator: String) -> String{
 
 }
 
-pub fn taxons_to_tuple_str(taxons: &Vec<i32>) -> String {
-    let mut taxons_str = String::new();
-    for (index,taxon) in taxons.iter().enumerate() {
-        let taxon_str = format!("{taxon}",taxon=taxon);
-        if index == 0{
-            taxons_str = taxon_str;
-        }else{
-            taxons_str = format!("{existing_string},{current_str}",existing_string=taxons_str,current_str=taxon_str);
-        }
-    }
-    return taxons_str;
-}
-
 pub fn to_bool(bool_str: Option<String>) -> bool{
     match bool_str {
         Some(value) => {
@@ -329,21 +198,6 @@ pub fn to_bool(bool_str: Option<String>) -> bool{
     }
 }
 
-pub fn to_postgres_array_str(items: &Vec<String>) -> String{
-    let mut items_str = String::new();
-    for (index,item) in items.iter().enumerate() {
-        if index == 0 {
-            items_str = format!(r#"'{curr_str}'"#,curr_str=item);
-        }else{
-            let curr_str = format!(r#"'{curr_str}'"#,curr_str=item);
-            items_str = format!("{prev_str},{curr_str}",prev_str=items_str,curr_str=curr_str);
-        }
-    }
-       
-    return format!("array[{items}]",items=items_str);
-
-}
-
 pub fn get_source(source_type: Option<String>) -> Option<Source> {
     match source_type {
         Some(value) => {
@@ -391,6 +245,24 @@ pub fn get_ptm_event_label(ptm_name: &str) -> Option<String>{
     }
 }
 
+//the ptm event names known to get_ptm_event_label, in the order of default_ptm_labels
+pub fn ptm_event_names() -> Vec<String>{
+    let mut ptm_names:Vec<String> = Vec::new();
+    ptm_names.push(String::from("acetylation"));
+    ptm_names.push(String::from("n-glycosylation"));
+    ptm_names.push(String::from("o-glycosylation"));
+    ptm_names.push(String::from("c-glycosylation"));
+    ptm_names.push(String::from("s-glycosylation"));
+    ptm_names.push(String::from("methylation"));
+    ptm_names.push(String::from("myristoylation"));
+    ptm_names.push(String::from("phosphorylation"));
+    ptm_names.push(String::from("sumoylation"));
+    ptm_names.push(String::from("ubiquitination"));
+    ptm_names.push(String::from("interaction"));
+    ptm_names.push(String::from("s-nitrosylation"));
+    return ptm_names;
+}
+
 pub fn default_ptm_labels() -> Vec<String>{
     let mut ptm_labels:Vec<String> = Vec::new();
     ptm_labels.push(String::from("ac"));
@@ -408,19 +280,79 @@ pub fn default_ptm_labels() -> Vec<String>{
     return ptm_labels;
 }
 
-pub fn query_substrates_to_tuple_str(query_substrates: &Vec<QuerySubstrate>) -> String {
-    let mut query_substrates_str = String::new();
-    for (index,substrate) in query_substrates.iter().enumerate() {
-        let substrate_str = format!("('{substrate_ac}','{residue}','{position}')",substrate_ac=substrate.substrate_ac,
-                                                                            residue=substrate.site_residue,
-                                                                            position=substrate.site_position);    
-        if index == 0{
-            query_substrates_str = substrate_str;
-        }else{
-            query_substrates_str = format!("{existing_string},{current_str}",existing_string=query_substrates_str,current_str=substrate_str);
+//converts the query substrates to (substrate_ac, residue, position) keys that can be bound as query parameters.
+//substrates whose position is not a number can not match any site and are dropped.
+pub fn query_substrate_keys(query_substrates: &Vec<QuerySubstrate>) -> Vec<(String,String,i64)> {
+    let mut substrate_keys: Vec<(String,String,i64)> = Vec::new();
+    for substrate in query_substrates {
+        match substrate.site_position.trim().parse::<i64>() {
+            Ok(position) => {
+                substrate_keys.push((substrate.substrate_ac.clone(),substrate.site_residue.clone(),position));
+            },
+            Err(_error) => {
+                warn!("Ignoring substrate {substrate_ac} with invalid position '{position}'",substrate_ac=substrate.substrate_ac,position=substrate.site_position);
+            }
+        }
+    }
+    return substrate_keys;
+}
+
+/// Matches the sites found by a batch query with the query substrates they answer.
+///
+/// The sites within `tolerance` positions of a query substrate, with the same residue, are looked up
+/// along with the query site itself, since search engines often place a site a few residues apart.
+pub struct SiteMatcher {
+    tolerance: i64,
+    query_positions: HashMap<(String,String),Vec<i64>>
+}
+
+impl SiteMatcher {
+    pub fn new(query_substrates: &Vec<QuerySubstrate>, tolerance: i64) -> SiteMatcher {
+        let mut query_positions: HashMap<(String,String),Vec<i64>> = HashMap::new();
+        for (substrate_ac,residue,position) in query_substrate_keys(query_substrates) {
+            let positions = query_positions.entry((substrate_ac,residue)).or_insert(Vec::new());
+            if !positions.contains(&position) {
+                positions.push(position);
+            }
+        }
+
+        return SiteMatcher {
+            tolerance: tolerance,
+            query_positions: query_positions
+        };
+    }
+
+    //the keys of every site within the tolerance of a query substrate, sorted so that the batches
+    //sent to the database do not depend on the order of the map
+    pub fn substrate_keys(&self) -> Vec<(String,String,i64)> {
+        let mut substrate_keys: Vec<(String,String,i64)> = Vec::new();
+        for (&(ref substrate_ac,ref residue),positions) in &self.query_positions {
+            for position in positions {
+                for window_position in (position - self.tolerance).max(1)..(position + self.tolerance + 1) {
+                    substrate_keys.push((substrate_ac.clone(),residue.clone(),window_position));
+                }
+            }
+        }
+        substrate_keys.sort();
+        substrate_keys.dedup();
+        return substrate_keys;
+    }
+
+    //the query sites a found site answers, with the offset of the found site from each of them
+    pub fn query_sites(&self, site_key: &(String,String,i64)) -> Vec<(String,i64)> {
+        let &(ref substrate_ac,ref residue,position) = site_key;
+        match self.query_positions.get(&(substrate_ac.clone(),residue.clone())) {
+            Some(positions) => {
+                return positions.iter()
+                        .filter(|query_position| (position - **query_position).abs() <= self.tolerance)
+                        .map(|query_position| (format!("{residue}{position}",residue=residue,position=query_position),position - query_position))
+                        .collect();
+            },
+            None => {
+                return Vec::new();
+            }
         }
     }
-    return query_substrates_str;
 }
 
 pub fn calculate_score_batch_ptm_enzymes(num_substrates: Vec<i64>,sources: &Vec<String>,pmids: &Vec<String>) -> i64 {
@@ -514,32 +446,6 @@ fn has_non_large_scale(pmids: &Vec<String>, pmid_stats: &HashMap<String,i64>) ->
 
 }
 
-pub fn get_accept_header_value(req: &HttpRequest<super::State>) -> String{
-    let content_header;
-    match req.headers().get("ACCEPT") {
-        Some(value) => {
-            match value.to_str() {
-                Ok(header_value) => {
-                    if header_value.contains("*/*") {
-                        content_header = "application/json";
-                    }else{
-                        content_header = header_value;
-                    }
-                }
-                Err(_) => {
-                    content_header = "application/json";
-                }
-            }
-        },
-        None => {
-            content_header = "application/json";
-        }
-    }
-
-    return String::from(content_header);
-
-}
-
 pub fn filter_empty_enzymes(batch_ptm_enzyme: &BatchPTMEnzyme) -> bool {
     match batch_ptm_enzyme.enzyme {
         Some(ref enzyme) => {
@@ -560,4 +466,97 @@ pub fn filter_empty_enzymes(batch_ptm_enzyme: &BatchPTMEnzyme) -> bool {
             return false;
         }
     }
-}
\ No newline at end of file
+}
+
+const SUGGESTION_PREFIX_LENGTH: usize = 3;
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+const MAX_SUGGESTIONS: usize = 5;
+
+//strips the isoform suffix from a uniprot accession, e.g. Q15796-2 becomes Q15796
+pub fn base_accession(id: &str) -> String {
+    match id.rfind('-') {
+        Some(index) => {
+            let suffix = &id[index+1..];
+            if index > 0 && !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) {
+                return String::from(&id[..index]);
+            }else{
+                return String::from(id);
+            }
+        },
+        None => {
+            return String::from(id);
+        }
+    }
+}
+
+//LIKE pattern used to look up suggestions for an unknown id, built from its first alphanumeric characters
+pub fn suggestion_pattern(id: &str) -> Option<String> {
+    let prefix: String = id.chars().filter(|c| c.is_alphanumeric()).take(SUGGESTION_PREFIX_LENGTH).collect::<String>().to_uppercase();
+    if prefix.is_empty() {
+        return None;
+    }else{
+        return Some(format!("{prefix}%",prefix=prefix));
+    }
+}
+
+//keeps the candidates closest to the id, comparing it with the accession, the uniprot id and the gene name
+pub fn rank_suggestions(id: &str, candidates: Vec<Suggestion>) -> Vec<Suggestion> {
+    let id_upper = id.to_uppercase();
+
+    let mut ranked: Vec<(usize,Suggestion)> = Vec::new();
+    for candidate in candidates {
+        let mut distance = edit_distance(&id_upper,&candidate.uniprot_ac.to_uppercase());
+        for name in [&candidate.uniprot_id,&candidate.gene_name].iter() {
+            match **name {
+                Some(ref value) => {
+                    distance = distance.min(edit_distance(&id_upper,&value.to_uppercase()));
+                },
+                None => {}
+            }
+        }
+
+        if distance <= MAX_SUGGESTION_DISTANCE {
+            ranked.push((distance,candidate));
+        }
+    }
+
+    ranked.sort_by(|a,b| a.0.cmp(&b.0).then_with(|| a.1.uniprot_ac.cmp(&b.1.uniprot_ac)));
+    ranked.dedup_by(|a,b| a.1.uniprot_ac == b.1.uniprot_ac);
+    return ranked.into_iter().take(MAX_SUGGESTIONS).map(|(_,suggestion)| suggestion).collect();
+}
+
+//levenshtein distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b_chars.len()+1).collect();
+    let mut current: Vec<usize> = vec![0; b_chars.len()+1];
+
+    for (i,a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j,b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char { 0 } else { 1 };
+            current[j+1] = (previous[j] + substitution_cost).min(previous[j+1] + 1).min(current[j] + 1);
+        }
+        ::std::mem::swap(&mut previous,&mut current);
+    }
+
+    return previous[b_chars.len()];
+}
+
+//the message a job or a streamed response panicked with, when it is a string
+pub fn panic_message(payload: &Box<Any + Send>) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => {
+            return String::from(*message);
+        },
+        None => {}
+    }
+    match payload.downcast_ref::<String>() {
+        Some(message) => {
+            return message.clone();
+        },
+        None => {
+            return String::from("unknown panic");
+        }
+    }
+}
@@ -10,6 +10,23 @@ pub struct Info {
     pub pro: Option<Pro>
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InfoFlat {
+    pub uniprot_ac: String,
+    pub uniprot_id: String,
+    pub protein_name: Option<String>,
+    pub gene_name: Option<String>,
+    pub synonyms: String,
+    pub organism_taxon_code: Option<String>,
+    pub organism_species: Option<String>,
+    pub organism_common_name: Option<String>,
+    pub pro_id: Option<String>,
+    pub pro_name: Option<String>,
+    pub pro_definition: Option<String>,
+    pub pro_short_label: Option<String>,
+    pub pro_category: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug,Clone)]
 pub struct Organism {
     pub taxon_code: Option<String>,
@@ -44,7 +61,7 @@ pub struct SearchResult {
     pub isoforms: Option<i64>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct SearchResultFlat {
     pub iptm_id: Option<String>,
     pub protein_name: Option<String>,
@@ -113,7 +130,7 @@ pub struct EnzymeEventFlat {
     pub pmids: Option<String>, 
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Proteoform {
     pub pro_id: Option<String>,
     pub label: Option<String>,
@@ -134,7 +151,7 @@ pub struct ProteoformFlat {
     pub pmids: Option<String>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Protein {
     pub pro_id: Option<String>,
     pub label: Option<String>
@@ -147,7 +164,7 @@ pub struct Source {
     pub url: Option<String>
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProteoformPPI {
     pub protein_1: Option<Protein>,
     pub relation: Option<String>,
@@ -167,7 +184,7 @@ pub struct ProteoformPPIFlat {
     pub pmids: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PTMPPI {
     pub ptm_type : Option<String>,
     pub substrate : Option<Entity>,
@@ -191,7 +208,7 @@ pub struct PTMPPIFlat {
     pub pmid : Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Entity {
     pub uniprot_id: Option<String>,
     pub name: Option<String>
@@ -204,7 +221,7 @@ pub struct QuerySubstrate {
     pub site_position: String
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BatchPTMEnzyme {
     pub enzyme: Option<Entity>,
     pub substrate: Option<Entity>,
@@ -214,9 +231,13 @@ pub struct BatchPTMEnzyme {
     pub score: i64,
     pub source: Vec<Source>,
     pub pmids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_site: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct BatchPTMEnzymeFlat {
     pub enz_name: Option<String>,
     pub enz_id: Option<String>,
@@ -228,9 +249,11 @@ pub struct BatchPTMEnzymeFlat {
     pub score: i64,
     pub source: Option<String>,
     pub pmids: Option<String>,
+    pub query_site: Option<String>,
+    pub offset: Option<i64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BatchPTMPPI {
     pub ptm_type: Option<String>,
     pub site: Option<String>,
@@ -240,9 +263,13 @@ pub struct BatchPTMPPI {
     pub substrate: Option<Entity>,
     pub source: Option<Source>,
     pub pmids: Vec<String>,
+    #[serde(default)]
+    pub query_site: Option<String>,
+    #[serde(default)]
+    pub offset: Option<i64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct BatchPTMPPIFlat {
     pub ptm_type: Option<String>,
     pub site: Option<String>,
@@ -254,6 +281,8 @@ pub struct BatchPTMPPIFlat {
     pub substrate_name: Option<String>,
     pub source: Option<String>,
     pub pmids: Option<String>,
+    pub query_site: Option<String>,
+    pub offset: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Clone,Debug)]
@@ -283,7 +312,7 @@ pub struct Sequence {
     pub sequence: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Variant {
     pub ac: Option<String>,
     pub position: i32,
@@ -294,5 +323,315 @@ pub struct Variant {
     pub pmid: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Suggestion {
+    pub uniprot_ac: String,
+    pub uniprot_id: Option<String>,
+    pub gene_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum JobKind {
+    #[serde(rename = "batch-ptm-enzymes")]
+    PtmEnzymes,
+    #[serde(rename = "batch-ptm-ppi")]
+    PtmPpi,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JobProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub progress: JobProgress,
+    pub submitted: u64,
+    pub started: Option<u64>,
+    pub finished: Option<u64>,
+    pub expires: Option<u64>,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub report: Option<ValidationReport>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RejectedRow {
+    pub index: usize,
+    pub substrate_ac: Option<String>,
+    pub site_residue: Option<String>,
+    pub site_position: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RowWarning {
+    pub index: usize,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteStatus {
+    Match,
+    Mismatch,
+    OutOfRange,
+    NoSequence,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SiteSuggestion {
+    pub substrate_ac: String,
+    pub site_position: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SiteCheck {
+    pub index: usize,
+    pub substrate_ac: String,
+    pub site_residue: String,
+    pub site_position: String,
+    pub status: SiteStatus,
+    pub sequence_residue: Option<String>,
+    pub suggestions: Vec<SiteSuggestion>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ValidationReport {
+    pub rejected_rows: Vec<RejectedRow>,
+    pub warnings: Vec<RowWarning>,
+    #[serde(default)]
+    pub site_checks: Vec<SiteCheck>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActivitySite {
+    pub substrate: Option<Entity>,
+    pub site: Option<String>,
+    pub query_site: Option<String>,
+    pub fold_change: f64,
+    pub score: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EnzymeActivity {
+    pub enzyme: Option<Entity>,
+    pub num_sites: usize,
+    pub mean_fold_change: f64,
+    pub z_score: f64,
+    pub p_value: f64,
+    pub fdr: f64,
+    pub sites: Vec<ActivitySite>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EnzymeActivityFlat {
+    pub enz_name: Option<String>,
+    pub enz_id: Option<String>,
+    pub num_sites: usize,
+    pub mean_fold_change: f64,
+    pub z_score: f64,
+    pub p_value: f64,
+    pub fdr: f64,
+    pub sites: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PtmEnrichment {
+    pub ptm_type: String,
+    pub ptm_label: String,
+    pub count: usize,
+    pub list_size: usize,
+    pub background_count: i64,
+    pub background_size: i64,
+    pub fold_enrichment: f64,
+    pub p_value: f64,
+    pub fdr: f64,
+    pub proteins: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PtmEnrichmentFlat {
+    pub ptm_type: String,
+    pub ptm_label: String,
+    pub count: usize,
+    pub list_size: usize,
+    pub background_count: i64,
+    pub background_size: i64,
+    pub fold_enrichment: f64,
+    pub p_value: f64,
+    pub fdr: f64,
+    pub proteins: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchType {
+    Accession,
+    Pro,
+    GeneName,
+    Synonym,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingStatus {
+    Mapped,
+    Ambiguous,
+    Unmapped,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MappedEntry {
+    pub iptm_entry_code: String,
+    pub entry_type: Option<String>,
+    pub gene_name: Option<String>,
+    pub synonyms: Vec<String>,
+    pub organism: Organism,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IdMapping {
+    pub input: String,
+    pub status: MappingStatus,
+    pub match_type: Option<MatchType>,
+    pub entries: Vec<MappedEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IdMappingFlat {
+    pub input: String,
+    pub status: MappingStatus,
+    pub match_type: Option<MatchType>,
+    pub iptm_entry_codes: String,
+    pub gene_names: String,
+    pub organism_taxon_codes: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SiteRecord {
+    pub uniprot_ac: String,
+    pub site: String,
+    pub residue: String,
+    pub position: i64,
+    pub site_check: SiteCheck,
+    pub ptm_types: Vec<String>,
+    pub score: Option<i64>,
+    pub enzymes: Vec<Enzyme>,
+    pub events: Vec<SubstrateEvent>,
+    pub ptm_ppis: Vec<PTMPPI>,
+    pub variants: Vec<Variant>,
+    pub proteoforms: Vec<Proteoform>,
+}
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SiteRecordFlat {
+    pub uniprot_ac: String,
+    pub site: String,
+    pub site_status: SiteStatus,
+    pub ptm_types: String,
+    pub score: Option<i64>,
+    pub enzymes: String,
+    pub interactants: String,
+    pub variants: String,
+    pub proteoforms: String,
+    pub pmids: String,
+}
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PublicationEvent {
+    pub substrate: Option<Entity>,
+    pub ptm_type: Option<String>,
+    pub site: Option<String>,
+    pub site_position: Option<i64>,
+    pub sources: Vec<Source>,
+    pub pmids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Publication {
+    pub pmid: String,
+    pub ptm_events: Vec<PublicationEvent>,
+    pub enzyme_substrates: Vec<BatchPTMEnzyme>,
+    pub proteoforms: Vec<Proteoform>,
+    pub proteoform_ppis: Vec<ProteoformPPI>,
+    pub ptm_ppis: Vec<PTMPPI>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicationRecordType {
+    PtmEvent,
+    EnzymeSubstrate,
+    Proteoform,
+    ProteoformPpi,
+    PtmPpi,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PublicationRecordFlat {
+    pub pmid: String,
+    pub record_type: PublicationRecordType,
+    pub substrate_id: Option<String>,
+    pub substrate_name: Option<String>,
+    pub site: Option<String>,
+    pub ptm_type: Option<String>,
+    pub enzyme_id: Option<String>,
+    pub enzyme_name: Option<String>,
+    pub interactant_id: Option<String>,
+    pub interactant_name: Option<String>,
+    pub association_type: Option<String>,
+    pub score: Option<i64>,
+    pub sources: Option<String>,
+    pub pmids: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkNode {
+    pub id: String,
+    pub name: Option<String>,
+    pub seed: bool,
+    pub depth: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkEdgeType {
+    EnzymeSubstrate,
+    PtmPpi,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub edge_type: NetworkEdgeType,
+    pub interaction: String,
+    pub ptm_type: Option<String>,
+    pub site: Option<String>,
+    pub score: Option<i64>,
+    pub association_type: Option<String>,
+    pub sources: Vec<String>,
+    pub pmids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Network {
+    pub nodes: Vec<NetworkNode>,
+    pub edges: Vec<NetworkEdge>,
+}
@@ -2,6 +2,8 @@ use models::Sequence;
 use database;
 use models::Alignment;
 use models::AlignmentItem;
+use models::Decoration;
+use std::collections::HashMap;
 use std::io::{Write};
 use std::process::{Command, Stdio};
 use errors::*;
@@ -49,8 +51,12 @@ pub fn align(sequences: &Vec<Sequence>) -> Result<String> {
     }
 }
 
-pub fn decorate(id: &str,alignmened_sequences: &str,db_params: database::DBParams) -> Result<Vec<Alignment>> {
+// Decorates every residue of the aligned sequences with the events of its form. A single pooled
+// connection is used for the whole alignment, and the decorations of each form are read at once
+// before its residues are decorated in parallel.
+pub fn decorate(id: &str,alignmened_sequences: &str,db_pool: &database::Pool) -> Result<Vec<Alignment>> {
     let mut alignments : Vec<Alignment> = Vec::new();
+    let conn = database::get_connection(db_pool)?;
 
     //decode the fasta string
     let fasta_reader = io::fasta::Reader::new(alignmened_sequences.as_bytes());
@@ -60,9 +66,10 @@ pub fn decorate(id: &str,alignmened_sequences: &str,db_params: database::DBParam
         let form_id = record.id();
         let seq = record.seq();
 
-        let closure = |seq_item| decorate_item(seq_item,String::from(id),String::from(form_id),db_params.clone());
+        let form_decorations = conn.get_decorations(id,form_id)?;
+        let closure = |seq_item| decorate_item(seq_item,&form_decorations);
        
-        let alignment_items = seq.par_iter().enumerate().map(closure).collect::<Result<Vec<AlignmentItem>>>()?;
+        let alignment_items = seq.par_iter().enumerate().map(closure).collect::<Vec<AlignmentItem>>();
 
         let alignment = Alignment {
             id: String::from(form_id),
@@ -75,7 +82,7 @@ pub fn decorate(id: &str,alignmened_sequences: &str,db_params: database::DBParam
 }
 
 
-fn decorate_item(seq_item: (usize, &u8),id: String,form_id: String,db_params: database::DBParams) -> Result<AlignmentItem> {    
+fn decorate_item(seq_item: (usize, &u8),form_decorations: &HashMap<(i64,String),Vec<Decoration>>) -> AlignmentItem {    
     let str_vec = [*(seq_item.1)].to_vec();
     let site;
     match String::from_utf8(str_vec) {
@@ -90,14 +97,12 @@ fn decorate_item(seq_item: (usize, &u8),id: String,form_id: String,db_params: da
     let mut position = seq_item.0 as i16;
     position = position + 1;
 
-    let conn = database::connect(&db_params)?;
-    let decorations = database::get_decorations(&id,&form_id,position as i64,&site,&conn)?;
+    let decorations = form_decorations.get(&(position as i64,site.clone())).cloned().unwrap_or_default();
 
     let alignment_item = AlignmentItem {
         site: site,
         position: position,
         decorations: decorations
     };
-    return Ok(alignment_item);       
+    return alignment_item;       
 }
-
@@ -0,0 +1,342 @@
+use actix_web::{HttpRequest, HttpMessage};
+use actix_web::http;
+use csv;
+use errors::*;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+    Tsv,
+    Ndjson
+}
+
+//the formats in the order the server prefers them when the client accepts several of them equally
+const FORMATS: [Format; 4] = [Format::Json, Format::Csv, Format::Tsv, Format::Ndjson];
+
+impl Format {
+    pub fn content_type(&self) -> &'static str {
+        match *self {
+            Format::Json => {
+                return "application/json";
+            },
+            Format::Csv => {
+                return "text/csv";
+            },
+            Format::Tsv => {
+                return "text/tab-separated-values";
+            },
+            Format::Ndjson => {
+                return "application/x-ndjson";
+            }
+        }
+    }
+
+    //value of the format query parameter selecting this format
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Format::Json => {
+                return "json";
+            },
+            Format::Csv => {
+                return "csv";
+            },
+            Format::Tsv => {
+                return "tsv";
+            },
+            Format::Ndjson => {
+                return "ndjson";
+            }
+        }
+    }
+
+    //media types answered with this format. text/plain has always meant CSV in this API.
+    fn media_types(&self) -> Vec<&'static str> {
+        match *self {
+            Format::Json => {
+                return vec!["application/json"];
+            },
+            Format::Csv => {
+                return vec!["text/csv","text/plain"];
+            },
+            Format::Tsv => {
+                return vec!["text/tab-separated-values"];
+            },
+            Format::Ndjson => {
+                return vec!["application/x-ndjson"];
+            }
+        }
+    }
+
+    pub fn is_delimited(&self) -> bool {
+        return *self == Format::Csv || *self == Format::Tsv;
+    }
+
+    //writer for the delimited formats
+    pub fn csv_writer(&self) -> csv::Writer<Vec<u8>> {
+        return self.csv_writer_to(vec![]);
+    }
+
+    //writer for the delimited formats writing to wtr
+    pub fn csv_writer_to<W: io::Write>(&self, wtr: W) -> csv::Writer<W> {
+        let delimiter;
+        if *self == Format::Tsv {
+            delimiter = b'\t';
+        }else{
+            delimiter = b',';
+        }
+        return csv::WriterBuilder::new().delimiter(delimiter).from_writer(wtr);
+    }
+}
+
+/// A response format of the network export, which has formats of its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetworkFormat {
+    Cytoscape,
+    Graphml,
+    Sif
+}
+
+//the network formats in the order the server prefers them
+const NETWORK_FORMATS: [NetworkFormat; 3] = [NetworkFormat::Cytoscape, NetworkFormat::Graphml, NetworkFormat::Sif];
+
+impl NetworkFormat {
+    pub fn content_type(&self) -> &'static str {
+        match *self {
+            NetworkFormat::Cytoscape => {
+                return "application/json";
+            },
+            NetworkFormat::Graphml => {
+                return "application/graphml+xml";
+            },
+            NetworkFormat::Sif => {
+                return "text/plain";
+            }
+        }
+    }
+
+    //value of the format query parameter selecting this format
+    pub fn name(&self) -> &'static str {
+        match *self {
+            NetworkFormat::Cytoscape => {
+                return "cytoscape";
+            },
+            NetworkFormat::Graphml => {
+                return "graphml";
+            },
+            NetworkFormat::Sif => {
+                return "sif";
+            }
+        }
+    }
+
+    //media types answered with this format
+    fn media_types(&self) -> Vec<&'static str> {
+        match *self {
+            NetworkFormat::Cytoscape => {
+                return vec!["application/json"];
+            },
+            NetworkFormat::Graphml => {
+                return vec!["application/graphml+xml","application/xml"];
+            },
+            NetworkFormat::Sif => {
+                return vec!["text/plain","text/x-sif"];
+            }
+        }
+    }
+}
+
+// Picks the response format. The format query parameter wins over the Accept header, which is
+// negotiated as described in RFC 7231 section 5.3.2. A request without an Accept header gets JSON.
+pub fn negotiate<S>(req: &HttpRequest<S>) -> Result<Format> {
+    return pick(req,&FORMATS,&|format| format.name(),&|format| format.media_types());
+}
+
+// Picks the response format of an endpoint answering in JSON only, like the multiple sequence
+// alignment whose nested sites have no rows. Asking for another format, through the Accept header
+// or the format query parameter, is not acceptable.
+pub fn negotiate_json<S>(req: &HttpRequest<S>) -> Result<Format> {
+    match req.query().get("format") {
+        Some(format_name) => {
+            let format_name_lower = format_name.trim().to_lowercase();
+            if format_name_lower != Format::Json.name() && FORMATS.iter().any(|format| format.name() == format_name_lower) {
+                return Err(ErrorKind::NotAcceptable(format!("The {name} format is not available here, expected json",name=format_name_lower)).into());
+            }
+        },
+        None => {}
+    }
+    return pick(req,&[Format::Json],&|format| format.name(),&|format| format.media_types());
+}
+
+//picks the format of a network export like negotiate does, a request without an Accept header gets Cytoscape.js JSON
+pub fn negotiate_network<S>(req: &HttpRequest<S>) -> Result<NetworkFormat> {
+    return pick(req,&NETWORK_FORMATS,&|format| format.name(),&|format| format.media_types());
+}
+
+// Picks one of the formats, by its name when the format query parameter is given and through the
+// Accept header otherwise. The first format is the default, and ties go to the earlier formats.
+fn pick<S,F: Copy>(req: &HttpRequest<S>, formats: &[F], name: &Fn(&F) -> &'static str, media_types: &Fn(&F) -> Vec<&'static str>) -> Result<F> {
+    match req.query().get("format") {
+        Some(format_name) => {
+            let format_name_lower = format_name.trim().to_lowercase();
+            for format in formats.iter() {
+                if name(format) == format_name_lower {
+                    return Ok(*format);
+                }
+            }
+            let names: Vec<&str> = formats.iter().map(|format| name(format)).collect();
+            return Err(ErrorKind::BadRequest(format!("Unknown format '{name}', expected one of {names}",name=format_name,names=names.join(", "))).into());
+        },
+        None => {}
+    }
+
+    let accept;
+    match req.headers().get(http::header::ACCEPT) {
+        Some(value) => {
+            match value.to_str() {
+                Ok(value_str) => {
+                    accept = String::from(value_str);
+                },
+                Err(_error) => {
+                    return Err(ErrorKind::BadRequest(String::from("The Accept header is not valid ASCII")).into());
+                }
+            }
+        },
+        None => {
+            return Ok(formats[0]);
+        }
+    }
+
+    let media_ranges = parse_accept(&accept);
+    if media_ranges.is_empty() {
+        return Ok(formats[0]);
+    }
+
+    //keep the first format with the highest quality, so that ties go to the server preference
+    let mut best: Option<(F,f32)> = None;
+    for format in formats.iter() {
+        let mut format_quality: f32 = 0.0;
+        for media_type in media_types(format) {
+            format_quality = format_quality.max(quality(&media_ranges,media_type));
+        }
+
+        if format_quality > 0.0 {
+            match best {
+                Some((_,best_quality)) if best_quality >= format_quality => {},
+                _ => {
+                    best = Some((*format,format_quality));
+                }
+            }
+        }
+    }
+
+    match best {
+        Some((format,_)) => {
+            return Ok(format);
+        },
+        None => {
+            //the first media type of every format is its content type
+            let content_types: Vec<&str> = formats.iter().map(|format| media_types(format)[0]).collect();
+            return Err(ErrorKind::NotAcceptable(format!("None of the accepted types '{accept}' is supported, expected one of {types}",accept=accept,types=content_types.join(", "))).into());
+        }
+    }
+}
+
+struct MediaRange {
+    main_type: String,
+    sub_type: String,
+    quality: f32
+}
+
+//parses an Accept header, skipping the media ranges that are malformed
+fn parse_accept(accept: &str) -> Vec<MediaRange> {
+    let mut media_ranges: Vec<MediaRange> = Vec::new();
+
+    for range_str in accept.split(',') {
+        let mut parts = range_str.split(';');
+
+        //type and subtype
+        let media_type = parts.next().unwrap_or("").trim().to_lowercase();
+        let mut type_parts = media_type.splitn(2,'/');
+        let main_type = String::from(type_parts.next().unwrap_or("").trim());
+        let sub_type = String::from(type_parts.next().unwrap_or("").trim());
+        if main_type.is_empty() || sub_type.is_empty() || (main_type == "*" && sub_type != "*") {
+            continue;
+        }
+
+        //quality, 1 when not given
+        let mut quality: Option<f32> = Some(1.0);
+        for param in parts {
+            let mut param_parts = param.splitn(2,'=');
+            let name = param_parts.next().unwrap_or("").trim().to_lowercase();
+            let value = param_parts.next().unwrap_or("").trim();
+            if name == "q" {
+                match value.parse::<f32>() {
+                    Ok(parsed_value) => {
+                        if parsed_value >= 0.0 && parsed_value <= 1.0 {
+                            quality = Some(parsed_value);
+                        }else{
+                            quality = None;
+                        }
+                    },
+                    Err(_error) => {
+                        quality = None;
+                    }
+                }
+            }
+        }
+
+        match quality {
+            Some(value) => {
+                media_ranges.push(MediaRange {
+                    main_type: main_type,
+                    sub_type: sub_type,
+                    quality: value
+                });
+            },
+            None => {
+                warn!("Ignoring media range with an invalid quality - {}",range_str.trim());
+            }
+        }
+    }
+
+    return media_ranges;
+}
+
+//quality of the most specific media range matching the media type, 0 when none matches
+fn quality(media_ranges: &Vec<MediaRange>, media_type: &str) -> f32 {
+    let mut type_parts = media_type.splitn(2,'/');
+    let main_type = type_parts.next().unwrap_or("");
+    let sub_type = type_parts.next().unwrap_or("");
+
+    let mut best: Option<(u8,f32)> = None;
+    for media_range in media_ranges {
+        let specificity;
+        if media_range.main_type == main_type && media_range.sub_type == sub_type {
+            specificity = 2;
+        }else if media_range.main_type == main_type && media_range.sub_type == "*" {
+            specificity = 1;
+        }else if media_range.main_type == "*" {
+            specificity = 0;
+        }else{
+            continue;
+        }
+
+        match best {
+            Some((best_specificity,_)) if best_specificity >= specificity => {},
+            _ => {
+                best = Some((specificity,media_range.quality));
+            }
+        }
+    }
+
+    match best {
+        Some((_,value)) => {
+            return value;
+        },
+        None => {
+            return 0.0;
+        }
+    }
+}
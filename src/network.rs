@@ -0,0 +1,373 @@
+use database::Backend;
+use errors::*;
+use misc;
+use models::{BatchPTMEnzyme, BatchPTMPPI, Entity, Network, NetworkEdge, NetworkEdgeType, NetworkNode, Source};
+use serde_json;
+use std::collections::{HashMap, HashSet};
+
+//number of times the network is expanded from the seeds, unless asked otherwise
+pub const DEFAULT_DEPTH: i64 = 1;
+
+//the deepest network that can be asked for, the networks of hub kinases grow fast
+pub const MAX_DEPTH: i64 = 3;
+
+//the largest network returned, a larger one has to be narrowed by its depth, ptm types or score
+pub const MAX_NODES: usize = 2000;
+
+/// Options of a network export.
+#[derive(Debug, Clone)]
+pub struct NetworkOptions {
+    /// Number of times the network is expanded from the seeds.
+    pub depth: usize,
+    /// Lower case PTM types of the edges kept. An empty list keeps every type.
+    pub ptm_types: Vec<String>,
+    /// Enzyme-substrate edges with a lower score are left out. PTM-dependent PPIs have no score.
+    pub min_score: i64,
+}
+
+//an edge as told apart from the others, the same edge is found again from each of its ends
+type EdgeKey = (NetworkEdgeType,String,String,Option<String>,Option<String>,Option<String>);
+
+// Builds the network around the seeds. The enzyme-substrate relations of MV_EVENT and the
+// PTM-dependent PPIs of MV_EFIP of the seeds are read first, and the proteins they reach are
+// added at depth 1. The network is then expanded in the same way from the proteins added last,
+// depth times in all, so the proteins of the last depth are reached but not expanded.
+//
+// Edges are numbered in the order they are found, and the edges found at each depth are sorted
+// first, so the same request always gives the same network.
+pub fn build_network(conn: &Backend, seeds: &Vec<String>, options: &NetworkOptions) -> Result<Network> {
+    let mut nodes: Vec<NetworkNode> = Vec::new();
+    let mut node_positions: HashMap<String,usize> = HashMap::new();
+    for seed in seeds {
+        add_node(&mut nodes,&mut node_positions,seed,None,true,0);
+    }
+
+    let mut edges: Vec<NetworkEdge> = Vec::new();
+    let mut edge_keys: HashSet<EdgeKey> = HashSet::new();
+    let mut frontier: Vec<String> = seeds.clone();
+    for depth in 1..(options.depth + 1) {
+        if frontier.is_empty() {
+            break;
+        }
+
+        //the edges of the proteins added last, with the names of their ends
+        let mut found: Vec<(NetworkEdge,Option<String>,Option<String>)> = Vec::new();
+        for relation in conn.get_enzyme_relations(&frontier,&options.ptm_types)? {
+            if relation.score >= options.min_score {
+                match enzyme_edge(relation) {
+                    Some(value) => {
+                        found.push(value);
+                    },
+                    None => {}
+                }
+            }
+        }
+        for relation in conn.get_ptm_ppi_relations(&frontier)? {
+            if is_kept_ptm_type(&relation.ptm_type,&options.ptm_types) {
+                match ptm_ppi_edge(relation) {
+                    Some(value) => {
+                        found.push(value);
+                    },
+                    None => {}
+                }
+            }
+        }
+        found.sort_by(|a,b| edge_key(&a.0).cmp(&edge_key(&b.0)));
+
+        let mut next_frontier: Vec<String> = Vec::new();
+        for (mut edge,source_name,target_name) in found {
+            if !edge_keys.insert(edge_key(&edge)) {
+                continue;
+            }
+
+            if add_node(&mut nodes,&mut node_positions,&edge.source,source_name,false,depth) {
+                next_frontier.push(edge.source.clone());
+            }
+            if add_node(&mut nodes,&mut node_positions,&edge.target,target_name,false,depth) {
+                next_frontier.push(edge.target.clone());
+            }
+
+            //stop as soon as the network is too large rather than after the whole depth
+            if nodes.len() > MAX_NODES {
+                return Err(ErrorKind::Unprocessable(format!("The network has more than {max_nodes} proteins at depth {depth}, lower the depth or narrow it by ptm_type or min_score",max_nodes=MAX_NODES,depth=depth)).into());
+            }
+
+            edge.id = format!("e{index}",index=edges.len());
+            edges.push(edge);
+        }
+
+        frontier = next_frontier;
+    }
+
+    return Ok(Network {
+        nodes: nodes,
+        edges: edges
+    });
+}
+
+//adds the protein unless it is a node already, whose name is then filled in if missing. True when the protein was added.
+fn add_node(nodes: &mut Vec<NetworkNode>, node_positions: &mut HashMap<String,usize>, id: &str, name: Option<String>, seed: bool, depth: usize) -> bool {
+    match node_positions.get(id) {
+        Some(position) => {
+            if nodes[*position].name.is_none() {
+                nodes[*position].name = name;
+            }
+            return false;
+        },
+        None => {}
+    }
+
+    node_positions.insert(String::from(id),nodes.len());
+    nodes.push(NetworkNode {
+        id: String::from(id),
+        name: name,
+        seed: seed,
+        depth: depth
+    });
+    return true;
+}
+
+fn is_kept_ptm_type(ptm_type: &Option<String>, ptm_types: &Vec<String>) -> bool {
+    if ptm_types.is_empty() {
+        return true;
+    }
+    return ptm_type.as_ref().map(|ptm_type| ptm_types.contains(&ptm_type.to_lowercase())).unwrap_or(false);
+}
+
+fn edge_key(edge: &NetworkEdge) -> EdgeKey {
+    return (edge.edge_type,edge.source.clone(),edge.target.clone(),edge.ptm_type.clone(),edge.site.clone(),edge.association_type.clone());
+}
+
+//the id and name of an end of a relation, None when the relation has no id for it
+fn entity_end(entity: &Option<Entity>) -> Option<(String,Option<String>)> {
+    match entity {
+        &Some(ref entity) => {
+            match entity.uniprot_id {
+                Some(ref uniprot_id) if !uniprot_id.is_empty() => {
+                    return Some((uniprot_id.clone(),entity.name.clone()));
+                },
+                _ => {
+                    return None;
+                }
+            }
+        },
+        &None => {
+            return None;
+        }
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    match value {
+        &Some(ref value) if !value.trim().is_empty() => {
+            return Some(String::from(value.trim()));
+        },
+        _ => {
+            return None;
+        }
+    }
+}
+
+//the interaction of an edge in SIF, which is a single word
+fn sif_interaction(value: &str) -> String {
+    return value.trim().to_lowercase().split_whitespace().collect::<Vec<&str>>().join("_");
+}
+
+fn source_names(sources: &Vec<Source>) -> Vec<String> {
+    return misc::remove_duplicates(&sources.iter().filter_map(|source| source.name.clone()).collect());
+}
+
+fn pmid_list(pmids: &Vec<String>) -> Vec<String> {
+    let trimmed: Vec<String> = pmids.iter().map(|pmid| String::from(pmid.trim())).filter(|pmid| !pmid.is_empty()).collect();
+    return misc::remove_duplicates(&trimmed);
+}
+
+//an edge from the enzyme to the substrate, with the names of both
+fn enzyme_edge(relation: BatchPTMEnzyme) -> Option<(NetworkEdge,Option<String>,Option<String>)> {
+    let (source,source_name) = entity_end(&relation.enzyme)?;
+    let (target,target_name) = entity_end(&relation.substrate)?;
+
+    let interaction = sif_interaction(relation.ptm_type.as_ref().map(|ptm_type| ptm_type.as_str()).unwrap_or("modification"));
+    let edge = NetworkEdge {
+        id: String::new(),
+        source: source,
+        target: target,
+        edge_type: NetworkEdgeType::EnzymeSubstrate,
+        interaction: interaction,
+        ptm_type: relation.ptm_type.clone(),
+        site: non_empty(&relation.site),
+        score: Some(relation.score),
+        association_type: None,
+        sources: source_names(&relation.source),
+        pmids: pmid_list(&relation.pmids)
+    };
+    return Some((edge,source_name,target_name));
+}
+
+//an edge from the modified substrate to its interactant, with the names of both
+fn ptm_ppi_edge(relation: BatchPTMPPI) -> Option<(NetworkEdge,Option<String>,Option<String>)> {
+    let (source,source_name) = entity_end(&relation.substrate)?;
+    let (target,target_name) = entity_end(&relation.interactant)?;
+
+    let interaction = sif_interaction(relation.association_type.as_ref().map(|association_type| association_type.as_str()).unwrap_or("ptm_ppi"));
+    let mut sources: Vec<String> = Vec::new();
+    match relation.source {
+        Some(ref source) => {
+            match source.name {
+                Some(ref name) => {
+                    sources.push(name.clone());
+                },
+                None => {}
+            }
+        },
+        None => {}
+    }
+
+    let edge = NetworkEdge {
+        id: String::new(),
+        source: source,
+        target: target,
+        edge_type: NetworkEdgeType::PtmPpi,
+        interaction: interaction,
+        ptm_type: relation.ptm_type.clone(),
+        site: non_empty(&relation.site),
+        score: None,
+        association_type: relation.association_type.clone(),
+        sources: sources,
+        pmids: pmid_list(&relation.pmids)
+    };
+    return Some((edge,source_name,target_name));
+}
+
+#[derive(Serialize)]
+struct CytoscapeNetwork<'a> {
+    elements: CytoscapeElements<'a>,
+}
+
+#[derive(Serialize)]
+struct CytoscapeElements<'a> {
+    nodes: Vec<CytoscapeElement<&'a NetworkNode>>,
+    edges: Vec<CytoscapeElement<&'a NetworkEdge>>,
+}
+
+#[derive(Serialize)]
+struct CytoscapeElement<T> {
+    data: T,
+}
+
+// The network as Cytoscape.js elements JSON, every node and edge holding its members under data,
+// which cy.add and the JSON import of Cytoscape both read.
+pub fn to_cytoscape(network: &Network) -> Result<String> {
+    let cytoscape_network = CytoscapeNetwork {
+        elements: CytoscapeElements {
+            nodes: network.nodes.iter().map(|node| CytoscapeElement { data: node }).collect(),
+            edges: network.edges.iter().map(|edge| CytoscapeElement { data: edge }).collect()
+        }
+    };
+
+    match serde_json::to_string_pretty(&cytoscape_network) {
+        Ok(value) => {
+            return Ok(value);
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+}
+
+// The network as GraphML. Enzyme-substrate edges are directed from the enzyme to the substrate,
+// PTM-dependent PPIs are undirected, and lists are written comma separated.
+pub fn to_graphml(network: &Network) -> String {
+    let mut graphml = String::new();
+    graphml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    graphml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+
+    let node_keys = [("name","string"),("seed","boolean"),("depth","int")];
+    for &(key,key_type) in node_keys.iter() {
+        graphml.push_str(&format!("  <key id=\"{key}\" for=\"node\" attr.name=\"{key}\" attr.type=\"{key_type}\"/>\n",key=key,key_type=key_type));
+    }
+    let edge_keys = [("edge_type","string"),("interaction","string"),("ptm_type","string"),("site","string"),("score","long"),
+                     ("association_type","string"),("sources","string"),("pmids","string")];
+    for &(key,key_type) in edge_keys.iter() {
+        graphml.push_str(&format!("  <key id=\"{key}\" for=\"edge\" attr.name=\"{key}\" attr.type=\"{key_type}\"/>\n",key=key,key_type=key_type));
+    }
+
+    graphml.push_str("  <graph id=\"iptmnet\" edgedefault=\"directed\">\n");
+    for node in &network.nodes {
+        graphml.push_str(&format!("    <node id=\"{id}\">\n",id=xml_escape(&node.id)));
+        push_data(&mut graphml,"name",&node.name);
+        push_data(&mut graphml,"seed",&Some(format!("{}",node.seed)));
+        push_data(&mut graphml,"depth",&Some(format!("{}",node.depth)));
+        graphml.push_str("    </node>\n");
+    }
+
+    for edge in &network.edges {
+        let directed;
+        if edge.edge_type == NetworkEdgeType::PtmPpi {
+            directed = " directed=\"false\"";
+        }else{
+            directed = "";
+        }
+        graphml.push_str(&format!("    <edge id=\"{id}\" source=\"{source}\" target=\"{target}\"{directed}>\n",
+                                  id=xml_escape(&edge.id),
+                                  source=xml_escape(&edge.source),
+                                  target=xml_escape(&edge.target),
+                                  directed=directed));
+        let edge_type = match edge.edge_type {
+            NetworkEdgeType::EnzymeSubstrate => "enzyme_substrate",
+            NetworkEdgeType::PtmPpi => "ptm_ppi"
+        };
+        push_data(&mut graphml,"edge_type",&Some(String::from(edge_type)));
+        push_data(&mut graphml,"interaction",&Some(edge.interaction.clone()));
+        push_data(&mut graphml,"ptm_type",&edge.ptm_type);
+        push_data(&mut graphml,"site",&edge.site);
+        push_data(&mut graphml,"score",&edge.score.map(|score| format!("{}",score)));
+        push_data(&mut graphml,"association_type",&edge.association_type);
+        push_data(&mut graphml,"sources",&Some(misc::str_vec_to_str(&edge.sources)));
+        push_data(&mut graphml,"pmids",&Some(misc::str_vec_to_str(&edge.pmids)));
+        graphml.push_str("    </edge>\n");
+    }
+
+    graphml.push_str("  </graph>\n");
+    graphml.push_str("</graphml>\n");
+    return graphml;
+}
+
+//a data element of a node or edge, left out when there is no value
+fn push_data(graphml: &mut String, key: &str, value: &Option<String>) {
+    match value {
+        &Some(ref value) => {
+            graphml.push_str(&format!("      <data key=\"{key}\">{value}</data>\n",key=key,value=xml_escape(value)));
+        },
+        &None => {}
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    return value.replace("&","&amp;")
+                .replace("<","&lt;")
+                .replace(">","&gt;")
+                .replace("\"","&quot;")
+                .replace("'","&apos;");
+}
+
+// The network as SIF, one tab separated line for every edge with its source, interaction and
+// target. SIF has no attributes, and a protein without edges is written alone on its line.
+pub fn to_sif(network: &Network) -> String {
+    let mut connected: HashSet<&str> = HashSet::new();
+    for edge in &network.edges {
+        connected.insert(&edge.source);
+        connected.insert(&edge.target);
+    }
+
+    let mut sif = String::new();
+    for edge in &network.edges {
+        sif.push_str(&format!("{source}\t{interaction}\t{target}\n",source=edge.source,interaction=edge.interaction,target=edge.target));
+    }
+    for node in &network.nodes {
+        if !connected.contains(node.id.as_str()) {
+            sif.push_str(&format!("{id}\n",id=node.id));
+        }
+    }
+    return sif;
+}
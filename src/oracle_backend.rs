@@ -0,0 +1,187 @@
+use errors::*;
+use oracle;
+use oracle::ToSql;
+use database::*;
+use query_builder::Dialect;
+use sql_backend::{Connector,Param};
+
+impl<'a> MyRow<'a> for oracle::Row {
+    fn get_string(&self,column_name: &str) -> Option<String>{
+        let result = self.get(column_name);
+        match result {
+            Ok(val) => {
+                return Some(val);
+            },
+            Err(_error) => {
+                return None;
+            }
+        };
+    }
+
+    fn get_string_unwrapped(&self,column_name: &str) -> String {
+        let result = self.get(column_name);
+        match result {
+            Ok(val) => {
+                return val;
+            },
+            Err(error) => {
+                panic!("{}",error);
+            }
+        };
+    }
+
+    fn get_i64(&self,column_name: &str) -> Option<i64>{
+        let result = self.get(column_name);
+        match result {
+            Ok(val) => {
+                return Some(val);
+            },
+            Err(_error) => {
+                return None;
+            }
+        };
+    }
+
+    fn get_bool(&self,column_name: &str) -> Option<bool>{
+        let result = self.get(column_name);
+        match result {
+            Ok(val) => {
+                return Some(val);
+            },
+            Err(_error) => {
+                return None;
+            }
+        };
+    }
+}
+
+pub struct OracleBackend {
+    conn: oracle::Connection
+}
+
+// The oracle connection holds a raw ODPI-C handle and is therefore not Send, but ODPI-C
+// connections are safe to move between threads as long as they are used by one thread at a time,
+// which is exactly what the pool guarantees.
+unsafe impl Send for OracleBackend {}
+
+impl OracleBackend {
+    pub fn connect(db_params: &DBParams) -> Result<OracleBackend> {
+        let connect_string = format!("{host}:{port}/{db_name}",host=db_params.host,port=db_params.port,db_name=db_params.db_name);
+        let connect_result = oracle::Connection::connect(&db_params.user, &db_params.pass, &connect_string, &[]);
+        match connect_result {
+            Ok(conn) => {
+                return Ok(OracleBackend { conn: conn });
+            },
+            Err(error) => {
+                error!("{}",error);
+                error!("Connect string - {}",connect_string);
+                return Err(format!("{}",error).into());
+            }
+        }
+    }
+}
+
+//query errors are reported as database errors, except for calls cancelled by a timeout (ORA-01013)
+fn query_error(error: oracle::Error) -> Error {
+    let is_timeout;
+    match error {
+        oracle::Error::OciError(ref db_error) => {
+            is_timeout = db_error.code() == 1013;
+        },
+        _ => {
+            is_timeout = false;
+        }
+    }
+
+    if is_timeout {
+        return ErrorKind::Timeout(format!("{}",error)).into();
+    }else{
+        return ErrorKind::Database(format!("{}",error)).into();
+    }
+}
+
+fn to_sql_params(params: &[Param]) -> Vec<&ToSql> {
+    let mut sql_params: Vec<&ToSql> = Vec::new();
+    for param in params {
+        match param {
+            &Param::Text(ref value) => {
+                sql_params.push(value);
+            },
+            &Param::Int(ref value) => {
+                sql_params.push(value);
+            }
+        }
+    }
+    return sql_params;
+}
+
+pub struct OracleDialect;
+
+impl Dialect for OracleDialect {
+    fn placeholder(&self, index: usize) -> String {
+        return format!(":{index}",index=index);
+    }
+
+    fn like_ignore_case(&self, column: &str, pattern: &str) -> String {
+        return format!("UPPER({column}) LIKE UPPER({pattern}) ESCAPE '\\'",column=column,pattern=pattern);
+    }
+
+    fn page(&self, offset: i64, limit: i64) -> String {
+        return format!("OFFSET {offset} rows FETCH NEXT {limit} rows only",offset=offset,limit=limit);
+    }
+
+    fn row_values(&self, tuples: &str) -> String {
+        return format!("({tuples})",tuples=tuples);
+    }
+
+    fn string_agg(&self, column: &str, separator: &str, order_column: &str) -> String {
+        return format!("LISTAGG({column},'{separator}') WITHIN GROUP (ORDER BY {order_column})",column=column,separator=separator,order_column=order_column);
+    }
+
+    fn aggregated_rows(&self, table: &str, where_clause: &str, _order_column: &str) -> String {
+        return format!("{table} where {where_clause}",table=table,where_clause=where_clause);
+    }
+}
+
+impl Connector for OracleBackend {
+    fn dialect(&self) -> &Dialect {
+        return &OracleDialect;
+    }
+
+    fn ping(&self) -> Result<()> {
+        match self.conn.ping() {
+            Ok(_) => {
+                return Ok(());
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+    }
+
+    fn query_rows(&self, query_str: &str, params: &[Param], handle_row: &mut FnMut(&MyRow) -> Result<bool>) -> Result<()> {
+        let sql_params = to_sql_params(params);
+        let query_result = self.conn.query(query_str,&sql_params);
+        match query_result {
+            Ok(rows_result) => {
+                for row_result in rows_result {
+                    match row_result {
+                        Ok(row) => {
+                            if !handle_row(&row)? {
+                                return Ok(());
+                            }
+                        },
+                        Err(error) => {
+                            error!("{}",error);
+                            return Err(query_error(error));
+                        }
+                    }
+                };
+                return Ok(());
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use errors::*;
+use postgres;
+use postgres::types::ToSql;
+use database::*;
+use query_builder::Dialect;
+use sql_backend::{Connector,Param};
+use fallible_iterator::FallibleIterator;
+
+//number of rows fetched from the server at a time by query_each
+const CURSOR_FETCH_SIZE: i32 = 500;
+
+impl<'a> MyRow<'a> for postgres::rows::Row<'a> {
+    fn get_string(&self,column_name: &str) -> Option<String>{
+        return self.get(column_name);
+    }
+
+    fn get_string_unwrapped(&self,column_name: &str) -> String {
+        return self.get(column_name);
+    }
+
+    //integer columns of any width are read, like the int4 positions of biomuta
+    fn get_i64(&self,column_name: &str) -> Option<i64>{
+        match self.get_opt::<_,Option<i64>>(column_name) {
+            Some(Ok(value)) => {
+                return value;
+            },
+            _ => {}
+        }
+        match self.get_opt::<_,Option<i32>>(column_name) {
+            Some(Ok(value)) => {
+                return value.map(|value| value as i64);
+            },
+            _ => {}
+        }
+        let value: Option<i16> = self.get(column_name);
+        return value.map(|value| value as i64);
+    }
+
+    fn get_bool(&self,column_name: &str) -> Option<bool>{
+        return self.get(column_name);
+    }
+}
+
+pub struct PostgresBackend {
+    conn: postgres::Connection
+}
+
+impl PostgresBackend {
+    pub fn connect(db_params: &DBParams) -> Result<PostgresBackend> {
+        let connect_string = format!("postgres://{user}:{pass}@{host}:{port}/{database}",user=db_params.user,
+                                     pass=db_params.pass,
+                                     host=db_params.host,
+                                     port=db_params.port,
+                                     database=db_params.db_name
+        );
+        let connect_result = postgres::Connection::connect(connect_string.clone(),postgres::TlsMode::None);
+        match connect_result {
+            Ok(conn) => {
+                return Ok(PostgresBackend { conn: conn });
+            },
+            Err(error) => {
+                error!("{}",error);
+                error!("Connect string - {}",connect_string);
+                return Err(format!("{}",error).into());
+            }
+        }
+    }
+}
+
+//query errors are reported as database errors, except for statements cancelled by the server's statement timeout
+fn query_error(error: postgres::Error) -> Error {
+    let is_timeout = error.code() == Some(&postgres::error::QUERY_CANCELED);
+    if is_timeout {
+        return ErrorKind::Timeout(format!("{}",error)).into();
+    }else{
+        return ErrorKind::Database(format!("{}",error)).into();
+    }
+}
+
+fn to_sql_params(params: &[Param]) -> Vec<&ToSql> {
+    let mut sql_params: Vec<&ToSql> = Vec::new();
+    for param in params {
+        match param {
+            &Param::Text(ref value) => {
+                sql_params.push(value);
+            },
+            &Param::Int(ref value) => {
+                sql_params.push(value);
+            }
+        }
+    }
+    return sql_params;
+}
+
+pub struct PostgresDialect;
+
+impl Dialect for PostgresDialect {
+    fn placeholder(&self, index: usize) -> String {
+        return format!("${index}",index=index);
+    }
+
+    fn like_ignore_case(&self, column: &str, pattern: &str) -> String {
+        return format!("{column} ILIKE {pattern} ESCAPE '\\'",column=column,pattern=pattern);
+    }
+
+    fn page(&self, offset: i64, limit: i64) -> String {
+        return format!("OFFSET {offset} LIMIT {limit}",offset=offset,limit=limit);
+    }
+
+    fn row_values(&self, tuples: &str) -> String {
+        return format!("({tuples})",tuples=tuples);
+    }
+
+    fn string_agg(&self, column: &str, separator: &str, order_column: &str) -> String {
+        return format!("string_agg({column},'{separator}' ORDER BY {order_column})",column=column,separator=separator,order_column=order_column);
+    }
+
+    fn aggregated_rows(&self, table: &str, where_clause: &str, _order_column: &str) -> String {
+        return format!("{table} where {where_clause}",table=table,where_clause=where_clause);
+    }
+}
+
+impl Connector for PostgresBackend {
+    fn dialect(&self) -> &Dialect {
+        return &PostgresDialect;
+    }
+
+    fn ping(&self) -> Result<()> {
+        match self.conn.batch_execute("SELECT 1") {
+            Ok(_) => {
+                return Ok(());
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+    }
+
+    fn is_broken(&self) -> bool {
+        return self.conn.is_desynchronized();
+    }
+
+    //the rows are read from a portal in batches of CURSOR_FETCH_SIZE rows, so that a large result is
+    //never held in memory at once. Portals only live inside a transaction, which is rolled back when
+    //dropped since nothing is written.
+    fn query_rows(&self, query_str: &str, params: &[Param], handle_row: &mut FnMut(&MyRow) -> Result<bool>) -> Result<()> {
+        let trans;
+        match self.conn.transaction() {
+            Ok(val) => {
+                trans = val;
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+
+        let stmt;
+        match trans.prepare(query_str) {
+            Ok(val) => {
+                stmt = val;
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+
+        let sql_params = to_sql_params(params);
+        let mut rows;
+        match stmt.lazy_query(&trans,&sql_params,CURSOR_FETCH_SIZE) {
+            Ok(val) => {
+                rows = val;
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+
+        loop {
+            match rows.next() {
+                Ok(Some(row)) => {
+                    if !handle_row(&row)? {
+                        return Ok(());
+                    }
+                },
+                Ok(None) => {
+                    return Ok(());
+                },
+                Err(error) => {
+                    return Err(query_error(error));
+                }
+            }
+        }
+    }
+}
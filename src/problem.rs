@@ -0,0 +1,236 @@
+use actix_web;
+use actix_web::{HttpRequest, HttpResponse, HttpMessage};
+use actix_web::http;
+use actix_web::http::StatusCode;
+use actix_web::http::header::HeaderValue;
+use actix_web::middleware::{Middleware, Started, Response};
+use errors::*;
+use models::{RejectedRow, Suggestion};
+use serde_json;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+static REQUEST_COUNTER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// RFC 7807 problem details, extended with a machine readable code and the request id
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub code: String,
+    pub detail: String,
+    pub instance: String,
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<Suggestion>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected_rows: Option<Vec<RejectedRow>>
+}
+
+//what a problem response needs to know about the request it answers
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub instance: String
+}
+
+pub fn context<S>(req: &HttpRequest<S>) -> RequestContext {
+    let request_id;
+    match req.headers().get(REQUEST_ID_HEADER) {
+        Some(value) => {
+            match value.to_str() {
+                Ok(value_str) => {
+                    request_id = String::from(value_str);
+                },
+                Err(_error) => {
+                    request_id = new_request_id();
+                }
+            }
+        },
+        None => {
+            request_id = new_request_id();
+        }
+    }
+
+    return RequestContext {
+        request_id: request_id,
+        instance: String::from(req.path())
+    };
+}
+
+//builds a request id from the start time and a per process counter, e.g. 5bc8a1f2-00002a
+fn new_request_id() -> String {
+    let seconds;
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => {
+            seconds = duration.as_secs();
+        },
+        Err(_error) => {
+            seconds = 0;
+        }
+    }
+    let count = REQUEST_COUNTER.fetch_add(1, Ordering::SeqCst);
+    return format!("{seconds:x}-{count:06x}",seconds=seconds,count=count);
+}
+
+//maps an error to its status, code and title
+fn classify(error: &Error) -> (StatusCode,&'static str,&'static str) {
+    match *error.kind() {
+        ErrorKind::BadRequest(_) => {
+            return (StatusCode::BAD_REQUEST,"bad_request","Bad Request");
+        },
+        ErrorKind::NotFound(_) | ErrorKind::UnknownEntry(_,_) => {
+            return (StatusCode::NOT_FOUND,"not_found","Not Found");
+        },
+        ErrorKind::NotAcceptable(_) => {
+            return (StatusCode::NOT_ACCEPTABLE,"not_acceptable","Not Acceptable");
+        },
+        ErrorKind::UnsupportedMediaType(_) => {
+            return (StatusCode::UNSUPPORTED_MEDIA_TYPE,"unsupported_media_type","Unsupported Media Type");
+        },
+        ErrorKind::Unprocessable(_) | ErrorKind::RejectedRows(_) => {
+            return (StatusCode::UNPROCESSABLE_ENTITY,"unprocessable_entity","Unprocessable Entity");
+        },
+        ErrorKind::Conflict(_) => {
+            return (StatusCode::CONFLICT,"conflict","Conflict");
+        },
+        ErrorKind::PayloadTooLarge(_) => {
+            return (StatusCode::PAYLOAD_TOO_LARGE,"payload_too_large","Payload Too Large");
+        },
+        ErrorKind::Database(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR,"database_error","Internal Server Error");
+        },
+        ErrorKind::Timeout(_) => {
+            return (StatusCode::GATEWAY_TIMEOUT,"timeout","Gateway Timeout");
+        },
+        ErrorKind::PoolUnavailable(_) => {
+            return (StatusCode::SERVICE_UNAVAILABLE,"service_unavailable","Service Unavailable");
+        },
+        ErrorKind::QueueFull(_) => {
+            return (StatusCode::SERVICE_UNAVAILABLE,"queue_full","Service Unavailable");
+        },
+        ErrorKind::StreamsBusy(_) => {
+            return (StatusCode::SERVICE_UNAVAILABLE,"streams_busy","Service Unavailable");
+        },
+        _ => {
+            return (StatusCode::INTERNAL_SERVER_ERROR,"internal_error","Internal Server Error");
+        }
+    }
+}
+
+//renders the error as an application/problem+json response
+pub fn response(context: &RequestContext, error: &Error) -> HttpResponse {
+    let (status,code,title) = classify(error);
+
+    //server side failures are logged with the request id, and their details are not sent to the client
+    let detail;
+    if status == StatusCode::INTERNAL_SERVER_ERROR {
+        error!("{request_id} - {error}",request_id=context.request_id,error=error);
+        detail = String::from("The server could not complete the request");
+    }else if status.is_server_error() {
+        error!("{request_id} - {error}",request_id=context.request_id,error=error);
+        detail = format!("{}",error);
+    }else{
+        detail = format!("{}",error);
+    }
+
+    //unknown ids carry the closest known entries
+    let suggestions;
+    match *error.kind() {
+        ErrorKind::UnknownEntry(_,ref entry_suggestions) => {
+            suggestions = Some(entry_suggestions.clone());
+        },
+        _ => {
+            suggestions = None;
+        }
+    }
+
+    //strict batch requests carry every row that was rejected
+    let rejected_rows;
+    match *error.kind() {
+        ErrorKind::RejectedRows(ref rows) => {
+            rejected_rows = Some(rows.clone());
+        },
+        _ => {
+            rejected_rows = None;
+        }
+    }
+
+    let problem = Problem {
+        problem_type: String::from("about:blank"),
+        title: String::from(title),
+        status: status.as_u16(),
+        code: String::from(code),
+        detail: detail,
+        instance: context.instance.clone(),
+        request_id: context.request_id.clone(),
+        suggestions: suggestions,
+        rejected_rows: rejected_rows
+    };
+
+    let problem_serialized = serde_json::to_string_pretty(&problem).unwrap();
+    return HttpResponse::build(status)
+            .force_close()
+            .header(http::header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)
+            .header(REQUEST_ID_HEADER, context.request_id.as_str())
+            .body(problem_serialized);
+}
+
+pub fn bad_request<T: fmt::Display>(context: &RequestContext, detail: T) -> HttpResponse {
+    return response(context,&ErrorKind::BadRequest(format!("{}",detail)).into());
+}
+
+pub fn not_found<T: fmt::Display>(context: &RequestContext, detail: T) -> HttpResponse {
+    return response(context,&ErrorKind::NotFound(format!("{}",detail)).into());
+}
+
+pub fn internal<T: fmt::Display>(context: &RequestContext, error: T) -> HttpResponse {
+    return response(context,&format!("{}",error).into());
+}
+
+pub fn payload_error(context: &RequestContext, error: &actix_web::error::PayloadError) -> HttpResponse {
+    match *error {
+        actix_web::error::PayloadError::Overflow => {
+            return response(context,&ErrorKind::PayloadTooLarge(format!("{}",error)).into());
+        },
+        _ => {
+            return bad_request(context,error);
+        }
+    }
+}
+
+// Makes sure every request carries an X-Request-Id, generating one when the client did not send
+// it, and echoes it back on the response.
+pub struct RequestId;
+
+impl<S> Middleware<S> for RequestId {
+    fn start(&self, req: &mut HttpRequest<S>) -> actix_web::Result<Started> {
+        if !req.headers().contains_key(REQUEST_ID_HEADER) {
+            match HeaderValue::from_str(&new_request_id()) {
+                Ok(value) => {
+                    req.headers_mut().insert(REQUEST_ID_HEADER, value);
+                },
+                Err(_error) => {}
+            }
+        }
+        return Ok(Started::Done);
+    }
+
+    fn response(&self, req: &mut HttpRequest<S>, mut resp: HttpResponse) -> actix_web::Result<Response> {
+        if !resp.headers().contains_key(REQUEST_ID_HEADER) {
+            match req.headers().get(REQUEST_ID_HEADER) {
+                Some(value) => {
+                    resp.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+                },
+                None => {}
+            }
+        }
+        return Ok(Response::Done(resp));
+    }
+}
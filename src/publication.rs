@@ -0,0 +1,81 @@
+use database::CitedRecords;
+use misc;
+use models::Publication;
+use std::collections::HashMap;
+
+// Groups the records citing any of the pmids by the pmid they cite, in the order of the pmids. A
+// record citing several of the pmids is listed under each of them, and a pmid cited by no record
+// gets empty lists.
+pub fn publications(pmids: &Vec<String>, cited_records: CitedRecords) -> Vec<Publication> {
+    let mut publications: Vec<Publication> = Vec::new();
+    let mut pmid_positions: HashMap<String,usize> = HashMap::new();
+    for pmid in pmids {
+        pmid_positions.insert(String::from(pmid.trim()),publications.len());
+        publications.push(Publication {
+            pmid: String::from(pmid.trim()),
+            ptm_events: Vec::new(),
+            enzyme_substrates: Vec::new(),
+            proteoforms: Vec::new(),
+            proteoform_ppis: Vec::new(),
+            ptm_ppis: Vec::new()
+        });
+    }
+
+    for ptm_event in cited_records.ptm_events {
+        for position in cited_positions(&ptm_event.pmids,&pmid_positions) {
+            publications[position].ptm_events.push(ptm_event.clone());
+        }
+    }
+
+    for enzyme_substrate in cited_records.enzyme_substrates {
+        for position in cited_positions(&enzyme_substrate.pmids,&pmid_positions) {
+            publications[position].enzyme_substrates.push(enzyme_substrate.clone());
+        }
+    }
+
+    for proteoform in cited_records.proteoforms {
+        for position in cited_positions(&proteoform.pmids,&pmid_positions) {
+            publications[position].proteoforms.push(proteoform.clone());
+        }
+    }
+
+    for proteoform_ppi in cited_records.proteoform_ppis {
+        for position in cited_positions(&proteoform_ppi.pmids,&pmid_positions) {
+            publications[position].proteoform_ppis.push(proteoform_ppi.clone());
+        }
+    }
+
+    for ptm_ppi in cited_records.ptm_ppis {
+        let pmids = misc::to_pmid_list(ptm_ppi.pmid.clone());
+        for position in cited_positions(&pmids,&pmid_positions) {
+            publications[position].ptm_ppis.push(ptm_ppi.clone());
+        }
+    }
+
+    return publications;
+}
+
+//true when no record cites the pmid of the publication
+pub fn is_empty(publication: &Publication) -> bool {
+    return publication.ptm_events.is_empty()
+           && publication.enzyme_substrates.is_empty()
+           && publication.proteoforms.is_empty()
+           && publication.proteoform_ppis.is_empty()
+           && publication.ptm_ppis.is_empty();
+}
+
+//the positions of the publications a record is listed under, each one once
+fn cited_positions(record_pmids: &Vec<String>, pmid_positions: &HashMap<String,usize>) -> Vec<usize> {
+    let mut positions: Vec<usize> = Vec::new();
+    for pmid in record_pmids {
+        match pmid_positions.get(pmid.trim()) {
+            Some(position) => {
+                if !positions.contains(position) {
+                    positions.push(*position);
+                }
+            },
+            None => {}
+        }
+    }
+    return positions;
+}
@@ -1,65 +1,145 @@
-use database::Engine;
 use misc;
 
-pub fn info(engine: &Engine) -> String {
-    match engine {
-        Engine::Postgres => {
-            return String::from("SELECT * FROM MV_ENTRY where iptm_entry_code = $1");
-        },
-        Engine::Oracle => {
-            return String::from("SELECT * FROM MV_ENTRY where iptm_entry_code = :1");
-        }
+/// The parts of a query every engine writes in its own way.
+///
+/// The queries below are written once in terms of the dialect, and each backend module has the
+/// dialect of its engine, so a new engine is added without touching them.
+pub trait Dialect {
+    //positional placeholder, numbered from 1
+    fn placeholder(&self, index: usize) -> String;
+
+    //case insensitive match of the column against a LIKE pattern whose wildcards are escaped with \
+    fn like_ignore_case(&self, column: &str, pattern: &str) -> String;
+
+    //at most limit rows from offset on, following an ORDER BY
+    fn page(&self, offset: i64, limit: i64) -> String;
+
+    //the list of row values an IN matches a row against, from tuples like ($1,$2,$3),($4,$5,$6)
+    fn row_values(&self, tuples: &str) -> String;
+
+    //the values of the column in a group, joined by the separator in the order of order_column
+    fn string_agg(&self, column: &str, separator: &str, order_column: &str) -> String;
+
+    //the rows of the table matching the where clause, in the FROM of a query grouping them with string_agg
+    fn aggregated_rows(&self, table: &str, where_clause: &str, order_column: &str) -> String;
+}
+
+pub fn info(dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_ENTRY where iptm_entry_code = {id}",id=dialect.placeholder(1));
+}
+
+//counts the entries matching either the id or its base accession
+pub fn entry_exists(dialect: &Dialect) -> String {
+    return format!(r#"SELECT COUNT(*) AS "entry_count" FROM MV_ENTRY where iptm_entry_code = {id} OR iptm_entry_code = {base_accession}"#,
+                   id=dialect.placeholder(1),
+                   base_accession=dialect.placeholder(2));
+}
+
+//entries whose accession, uniprot id or gene name starts with the same characters as an unknown id
+pub fn entry_candidates(dialect: &Dialect) -> String {
+    return format!("SELECT iptm_entry_code, uniprot_id, gene_name FROM MV_ENTRY \
+                    where iptm_entry_type != 'pro_id' \
+                    AND (UPPER(iptm_entry_code) LIKE {code} OR UPPER(uniprot_id) LIKE {uniprot_id} OR UPPER(gene_name) LIKE {gene_name}) \
+                    ORDER BY iptm_entry_code {page}",
+                   code=dialect.placeholder(1),
+                   uniprot_id=dialect.placeholder(2),
+                   gene_name=dialect.placeholder(3),
+                   page=dialect.page(0,ENTRY_CANDIDATES_LIMIT));
+}
+
+const ENTRY_CANDIDATES_LIMIT: i64 = 500;
+
+//the entries among the given number of ids
+pub fn known_entries(id_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT iptm_entry_code FROM MV_ENTRY where iptm_entry_code IN ({placeholders})",
+                   placeholders=id_placeholders(1,id_count,dialect));
+}
+
+//the entries among the given number of entry codes, with every column read by build_info and build_pro_info
+pub fn entries(code_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_ENTRY where iptm_entry_code IN ({placeholders})",
+                   placeholders=id_placeholders(1,code_count,dialect));
+}
+
+// The entries whose code, gene name or one of the gene synonyms could be one of the given number of
+// tokens. The upper cased tokens are bound for the codes, then for the gene names, and then the
+// synonym patterns, like %|SMAD2|%, that are matched against the | separated gene_syn column.
+pub fn mapping_candidates(token_count: usize, dialect: &Dialect) -> String {
+    let mut synonym_matches: Vec<String> = Vec::new();
+    for index in 0..token_count {
+        synonym_matches.push(format!(r"('|' || UPPER(gene_syn) || '|') LIKE {pattern} ESCAPE '\'",pattern=dialect.placeholder(2 * token_count + index + 1)));
     }
+    return format!("SELECT iptm_entry_code, iptm_entry_type, gene_name, gene_syn, taxon_code, taxon_species, taxon_common FROM MV_ENTRY \
+                    where UPPER(iptm_entry_code) IN ({codes}) OR UPPER(gene_name) IN ({genes}) OR {synonyms}",
+                   codes=id_placeholders(1,token_count,dialect),
+                   genes=id_placeholders(token_count + 1,token_count,dialect),
+                   synonyms=misc::str_vec_to_str_with_sep(&synonym_matches,String::from(" OR ")));
 }
 
-pub fn search(term_type: &str, role: &str,ptm_types: &Vec<String>,organism_taxons: &Vec<i32>,paginate: bool,offset: i32, limit: i32,engine: &Engine) -> String {
-    let search_clause = search_clause(term_type, role,ptm_types,organism_taxons, paginate, offset, limit, engine);
+//the substrates of the background of an enrichment analysis, restricted to the taxons bound from first_index on
+fn substrate_background_clause(first_index: usize, taxon_count: usize, dialect: &Dialect) -> String {
+    let mut taxon_clause = String::new();
+    if taxon_count > 0 {
+        taxon_clause = format!("AND taxon_code IN ({taxon_codes})",taxon_codes=id_placeholders(first_index,taxon_count,dialect));
+    }
+    return format!("role_as_substrate = 'T' AND iptm_entry_type != 'pro_id' {taxon_clause}",taxon_clause=taxon_clause);
+}
+
+//the number of background substrates for every list of ptm labels, the labels are split by the caller
+pub fn substrate_label_counts(taxon_count: usize, dialect: &Dialect) -> String {
+    return format!(r#"SELECT list_as_substrate, COUNT(*) AS "entry_count" FROM MV_ENTRY where {background_clause} GROUP BY list_as_substrate"#,
+                   background_clause=substrate_background_clause(1,taxon_count,dialect));
+}
+
+//the ptm labels of the background substrates among the given number of ids
+pub fn substrate_labels(id_count: usize, taxon_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT iptm_entry_code, list_as_substrate FROM MV_ENTRY where iptm_entry_code IN ({placeholders}) AND {background_clause}",
+                   placeholders=id_placeholders(1,id_count,dialect),
+                   background_clause=substrate_background_clause(id_count + 1,taxon_count,dialect));
+}
+
+//number of times the search term is bound, once for every column it is matched against
+pub fn search_term_param_count(term_type: &str) -> usize {
+    if term_type == "All" {
+        return 3;
+    }else if term_type == "UniprotID" {
+        return 1;
+    }else if term_type == "Protein/Gene Name" {
+        return 2;
+    }else{
+        return 0;
+    }
+}
+
+// The search queries bind their parameters in this order:
+//  1. the search term, search_term_param_count times
+//  2. one pattern for every ptm type, like %,phosphorylation,%
+//  3. the taxon codes, if any were given
+pub fn search(term_type: &str, role: &str,ptm_types: &Vec<String>,organism_taxons: &Vec<i32>,paginate: bool,offset: i32, limit: i32,dialect: &Dialect) -> String {
+    let search_clause = search_clause(term_type, role,ptm_types,organism_taxons, paginate, offset, limit, dialect);
     return format!("SELECT * FROM {search_clause}",search_clause=search_clause);
 }
 
-pub fn search_count(term_type: &str, role: &str,ptm_types: &Vec<String>, organism_taxons: &Vec<i32>,engine: &Engine) -> String {
-    let search_clause = search_clause(term_type, role,ptm_types,organism_taxons, false,0,0,engine);
+pub fn search_count(term_type: &str, role: &str,ptm_types: &Vec<String>, organism_taxons: &Vec<i32>,dialect: &Dialect) -> String {
+    let search_clause = search_clause(term_type, role,ptm_types,organism_taxons, false,0,0,dialect);
     return format!("SELECT COUNT(iptm_entry_id) AS search_count FROM {search_clause}",search_clause=search_clause);
 }
 
-fn search_clause(term_type: &str, role: &str,ptm_types: &Vec<String>,organism_taxons: &Vec<i32>,paginate: bool,offset: i32, limit: i32,engine: &Engine) -> String {
+fn search_clause(term_type: &str, role: &str,ptm_types: &Vec<String>,organism_taxons: &Vec<i32>,paginate: bool,offset: i32, limit: i32,dialect: &Dialect) -> String {
     // build the search term matching clause
-    let mut search_term_clause = String::new();
-
+    let mut search_columns: Vec<&str> = Vec::new();
     if term_type == "All" {
-        match engine {
-            &Engine::Postgres => {
-                search_term_clause = String::from("uniprot_id ILIKE $1 OR protein_name ILIKE $2 OR gene_name ILIKE $3");
-            },
-            &Engine::Oracle => {
-                search_term_clause = String::from("regexp_like(uniprot_id,:1,'i') OR regexp_like(protein_name,:2,'i') OR regexp_like(gene_name,:3,'i')")    
-            }
-        }
-        
+        search_columns = vec!["uniprot_id","protein_name","gene_name"];
     }else if term_type == "UniprotID" {
-        match engine {
-            &Engine::Postgres => {
-                search_term_clause = String::from("uniprot_id ILIKE $1")
-            },
-            &Engine::Oracle => {
-                search_term_clause = String::from("regexp_like(uniprot_id,:1,'i')")
-            }
-        }
-        
+        search_columns = vec!["uniprot_id"];
     }else if term_type == "Protein/Gene Name" {
-        match engine {
-            &Engine::Postgres => {
-                search_term_clause = String::from("uniprot_id ILIKE $1 OR gene_name ILIKE $2")
-            },
-            &Engine::Oracle => {
-                if !paginate{
-                    search_term_clause = String::from("regexp_like(uniprot_id,:1,'i')' OR regexp_like(gene_name,:2,'i')")
-                }else{
-                    search_term_clause = String::from("")
-                }
-            }
-        }
+        search_columns = vec!["uniprot_id","gene_name"];
     }
+    let mut search_term_matches: Vec<String> = Vec::new();
+    for (index,column) in search_columns.iter().enumerate() {
+        search_term_matches.push(dialect.like_ignore_case(column,&dialect.placeholder(index + 1)));
+    }
+    let search_term_clause = misc::str_vec_to_str_with_sep(&search_term_matches,String::from(" OR "));
 
     // build the enzyme matching clause
     let mut enzyme_clause = String::from("");
@@ -73,56 +153,35 @@ fn search_clause(term_type: &str, role: &str,ptm_types: &Vec<String>,organism_ta
         enzyme_clause = String::from("AND (role_as_enzyme = 'T' AND role_as_substrate = 'T')")
     }
 
-    //ptm clause
-    let ptm_clause;
-    match engine {
-            Engine::Postgres => {
-                let ptm_array = misc::to_postgres_array_str(ptm_types);
-                ptm_clause = format!("AND (string_to_array(list_as_substrate,',') && {array})",array=ptm_array);
-            },
-            Engine::Oracle => {
-                //ptm_clause = format!("AND (taxon_code = ANY ({taxon_codes}))",taxon_codes=taxon_codes);
-                let ptm_csv = misc::str_vec_to_str_with_sep(ptm_types,String::from("|"));
-                ptm_clause = format!("AND (regexp_like(LIST_AS_SUBSTRATE,'{ptm_csv}','i'))",ptm_csv=ptm_csv);
-            }
+    //the ptm and taxon parameters follow the search term
+    let mut param_index = search_term_param_count(term_type) + 1;
+
+    //ptm clause, each label is matched against the comma separated list
+    let mut ptm_matches: Vec<String> = Vec::new();
+    for _ in ptm_types {
+        ptm_matches.push(dialect.like_ignore_case("(',' || list_as_substrate || ',')",&dialect.placeholder(param_index)));
+        param_index = param_index + 1;
     }
+    if ptm_matches.is_empty() {
+        ptm_matches.push(String::from("1 = 0"));
+    }
+    let ptm_clause = format!("AND ({ptm_matches})",ptm_matches=misc::str_vec_to_str_with_sep(&ptm_matches,String::from(" OR ")));
 
     //taxon clause
     let mut taxon_clause = String::new();
     if !organism_taxons.is_empty() {
-        let taxon_codes=misc::taxons_to_tuple_str(organism_taxons);
-        match engine {
-            Engine::Postgres => {
-                taxon_clause = format!("AND (taxon_code = ANY ('{{{taxon_codes}}}'))",taxon_codes=taxon_codes);
-            },
-            Engine::Oracle => {
-                taxon_clause = format!("AND (taxon_code = ANY ({taxon_codes}))",taxon_codes=taxon_codes);
-            }
-        }
-    }    
+        taxon_clause = format!("AND (taxon_code IN ({taxon_codes}))",taxon_codes=id_placeholders(param_index,organism_taxons.len(),dialect));
+    }
 
     // pagination
     if paginate {
-
-        //limit offset clause
-        let limit_offset_clause;
-        match engine {
-            Engine::Postgres => {
-                limit_offset_clause = format!("OFFSET {offset} LIMIT {limit}",limit=limit,offset=offset);
-            },
-            Engine::Oracle => {
-                limit_offset_clause = format!("OFFSET {offset} rows FETCH NEXT {limit} rows only",limit=limit,offset=offset);
-                //limit_offset_clause = String::from("");
-            }
-        }
-
         return format!("MV_ENTRY where ({search_term_clause}) {enzyme_clause} AND iptm_entry_type != 'pro_id' {ptm_clause} {taxon_clause} \
-                    ORDER BY iptm_entry_id {limit_offset_clause}",
+                    ORDER BY iptm_entry_id {page}",
                     search_term_clause=search_term_clause,
                     enzyme_clause=enzyme_clause,
                     ptm_clause=ptm_clause,
                     taxon_clause=taxon_clause,
-                    limit_offset_clause=limit_offset_clause
+                    page=dialect.page(offset as i64,limit as i64)
                 );
     }else{
         return format!("MV_ENTRY where ({search_term_clause}) {enzyme_clause} AND iptm_entry_type != 'pro_id' {ptm_clause} {taxon_clause}",
@@ -135,93 +194,191 @@ fn search_clause(term_type: &str, role: &str,ptm_types: &Vec<String>,organism_ta
 }
 
 
-pub fn pro_info(engine: &Engine) -> String {
-    let query_str = String::from("SELECT * FROM MV_ENTRY where iptm_entry_code = $1");
-    match engine {
-        &Engine::Postgres => {
-            return query_str;
-        },
-        &Engine::Oracle => {
-            return query_str.replace("$",":");
-        }
-    }
+pub fn pro_info(dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_ENTRY where iptm_entry_code = {pro_id}",pro_id=dialect.placeholder(1));
 }
 
-pub fn sub_forms(engine: &Engine) -> String {
-    let query_str = String::from("SELECT DISTINCT SUB_FORM_CODE from MV_EVENT where SUB_CODE = $1");
-    match engine {
-        &Engine::Postgres => {
-            return query_str;
-        },
-        &Engine::Oracle => {
-            return query_str.replace("$",":");
-        }
-    }
+pub fn sub_forms(dialect: &Dialect) -> String {
+    return format!("SELECT DISTINCT SUB_FORM_CODE from MV_EVENT where SUB_CODE = {id}",id=dialect.placeholder(1));
+}
+
+pub fn proteoforms(dialect: &Dialect) -> String  {
+    return format!("SELECT * FROM MV_PROTEO where {sub_xref_match} AND EVENT_NAME != 'Interaction'",
+                   sub_xref_match=dialect.like_ignore_case("SUB_XREF",&dialect.placeholder(1)));
+}
+
+pub fn proteoformppi(dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_PROTEO where {sub_xref_match} AND EVENT_NAME = 'Interaction'",
+                   sub_xref_match=dialect.like_ignore_case("SUB_XREF",&dialect.placeholder(1)));
+}
+
+pub fn ptmppi(dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_EFIP where PPI_SUB_CODE = {id} OR PPI_PR_CODE = {id}",id=dialect.placeholder(1));
+}
+
+pub fn events_for_sub_form(dialect: &Dialect) -> String {
+    return format!("SELECT RESIDUE,POSITION,EVENT_NAME,ENZ_CODE,ENZ_TYPE,ENZ_SYMBOL,SOURCE_LABEL,PMIDS,NUM_SUBSTRATES \
+                    FROM MV_EVENT \
+                    where SUB_FORM_CODE = {sub_form} \
+                    ORDER BY RESIDUE,POSITION,EVENT_NAME",sub_form=dialect.placeholder(1));
+}
+
+pub fn enzyme_events(dialect: &Dialect) -> String {
+    return format!("SELECT SUB_FORM_CODE,SUB_SYMBOL,RESIDUE,POSITION,SOURCE_LABEL,PMIDS \
+                    FROM MV_EVENT \
+                    where ENZ_CODE = {id} AND EVENT_LABEL = 'p' \
+                    ORDER BY RESIDUE,POSITION,EVENT_NAME",id=dialect.placeholder(1));
 }
 
-pub fn proteoforms(engine: &Engine) -> String  {
-    match engine {
-        &Engine::Postgres => {
-            return String::from("SELECT * FROM MV_PROTEO where SUB_XREF ILIKE $1 AND EVENT_NAME != 'Interaction'");
-        },
-        &Engine::Oracle => {
-            return String::from("SELECT * FROM MV_PROTEO where regexp_like(SUB_XREF,:1,'i') AND EVENT_NAME != 'Interaction'");
-        }
+//one bindable (substrate, residue, position) tuple for every query substrate
+fn substrate_tuples(substrate_count: usize, dialect: &Dialect) -> String {
+    let mut tuples: Vec<String> = Vec::new();
+    for index in 0..substrate_count {
+        tuples.push(format!("({substrate},{residue},{position})",substrate=dialect.placeholder(index * 3 + 1),
+                                                                  residue=dialect.placeholder(index * 3 + 2),
+                                                                  position=dialect.placeholder(index * 3 + 3)));
     }
+    return misc::str_vec_to_str_with_sep(&tuples,String::from(","));
+}
+
+pub fn ptm_enzymes(substrate_count: usize, dialect: &Dialect) -> String {
+    let tuples = substrate_tuples(substrate_count,dialect);
+    return aggregated_ptm_enzymes(&format!("(sub_code,residue,position) in {row_values}",row_values=dialect.row_values(&tuples)),dialect);
 }
 
-pub fn proteoformppi(engine: &Engine) -> String {
-    match engine {
-        &Engine::Postgres => {
-            return String::from("SELECT * FROM MV_PROTEO where SUB_XREF ILIKE $1 AND EVENT_NAME = 'Interaction'");
-        },
-        &Engine::Oracle => {
-            return String::from("SELECT * FROM MV_PROTEO where regexp_like(SUB_XREF,:1,'i') AND EVENT_NAME = 'Interaction'");
-        }
+//the substrate sites of the given number of enzymes, of the given number of ptm types if any, bound after the enzymes
+pub fn enzyme_substrates(enzyme_count: usize, ptm_type_count: usize, dialect: &Dialect) -> String {
+    let mut where_clause = format!("enz_code IN ({enzyme_codes})",enzyme_codes=id_placeholders(1,enzyme_count,dialect));
+
+    if ptm_type_count > 0 {
+        where_clause = format!("{where_clause} and LOWER(event_name) IN ({ptm_types})",where_clause=where_clause,ptm_types=id_placeholders(enzyme_count + 1,ptm_type_count,dialect));
     }
+
+    return aggregated_ptm_enzymes(&where_clause,dialect);
+}
+
+//one row for every enzyme, substrate site and ptm type of the mv_event rows matching the where clause,
+//with the sources, pmids and substrate counts of the rows aggregated as build_ptm_enzyme expects them
+fn aggregated_ptm_enzymes(where_clause: &str, dialect: &Dialect) -> String {
+    return format!("SELECT event_name,sub_code,sub_symbol,residue,position,enz_code,enz_symbol, \
+                    {source_label} as source_label, \
+                    {num_substrates} as num_substrates, \
+                    {pmids} as pmids \
+                    FROM {rows} \
+                    GROUP BY enz_code,enz_symbol,sub_code,sub_symbol,residue,position,event_name",
+                   source_label=dialect.string_agg("source_label",",","source_label"),
+                   num_substrates=dialect.string_agg("num_substrates","|","source_label"),
+                   pmids=dialect.string_agg("pmids",",","source_label"),
+                   rows=dialect.aggregated_rows("MV_EVENT",&format!("{where_clause} and enz_code is not NULL",where_clause=where_clause),"source_label"));
+}
+
+pub fn ptm_ppi(substrate_count: usize, dialect: &Dialect) -> String {
+    let tuples = substrate_tuples(substrate_count,dialect);
+    return format!("SELECT * FROM MV_EFIP where (ptm_sub_code,ptm_residue,ptm_position) in {row_values}",row_values=dialect.row_values(&tuples));
+}
+
+pub fn variants(dialect: &Dialect) -> String {
+    return format!(r"SELECT * FROM BIOMUTA where ac LIKE {id} ESCAPE '\'",id=dialect.placeholder(1));
+}
+
+pub fn get_sequences(dialect: &Dialect) -> String {
+    return format!("SELECT * FROM SEQUENCE where {id_match}",id_match=dialect.like_ignore_case("ID",&dialect.placeholder(1)));
 }
 
-pub fn ptmppi(engine: &Engine) -> String {
-    let query_str = String::from("SELECT * FROM MV_EFIP where PPI_SUB_CODE = $1 OR PPI_PR_CODE = $1"); 
-    match engine {
-        &Engine::Postgres => {
-            return query_str;
-        },
-        &Engine::Oracle => {
-            return query_str.replace("$",":");
-        }
+// The sequences of the given number of entries and of their isoforms. The accessions are bound
+// first, followed by the isoform patterns, like Q15796-%.
+pub fn entry_sequences(accession_count: usize, dialect: &Dialect) -> String {
+    let mut isoform_matches: Vec<String> = Vec::new();
+    for index in 0..accession_count {
+        isoform_matches.push(format!(r"ID LIKE {pattern} ESCAPE '\'",pattern=dialect.placeholder(accession_count + index + 1)));
     }
+    return format!("SELECT * FROM SEQUENCE where ID IN ({accessions}) OR {isoforms}",
+                   accessions=id_placeholders(1,accession_count,dialect),
+                   isoforms=misc::str_vec_to_str_with_sep(&isoform_matches,String::from(" OR ")));
+}
+
+//the decorations of every site of a form, grouped by position, residue and event name
+pub fn get_decorations(dialect: &Dialect) -> String {
+    return format!("select position,residue,event_name,{source_labels} as source_labels,{pmids} as pmids \
+                    from {rows} \
+                    group by position,residue,event_name",
+                   source_labels=dialect.string_agg("source_label",", ","source_label"),
+                   pmids=dialect.string_agg("pmids",", ","source_label"),
+                   rows=dialect.aggregated_rows("mv_event",&format!("sub_form_code = {form_id} AND position is not null",form_id=dialect.placeholder(1)),"source_label"));
 }
 
-pub fn get_sequences(engine: &Engine) -> String {
-    match engine {
-        Engine::Postgres => {
-            return String::from("SELECT * FROM SEQUENCE where ID ILIKE $1");
-        },
-        Engine::Oracle => {
-            return String::from("SELECT * FROM SEQUENCE where regexp_like(ID,:1,'i')");
-        }
+//the positions and residues of an entry having at least one event
+pub fn get_decorated_sites(dialect: &Dialect) -> String {
+    return format!("select position,residue from mv_event where sub_code = {id} AND position is not null group by position,residue",id=dialect.placeholder(1));
+}
+
+// Matches the comma separated pmids column against the given number of patterns, like %,15241418,%,
+// bound from index 1 on. The spaces some lists have after their commas are removed first.
+fn cited_clause(pmids_column: &str, pmid_count: usize, dialect: &Dialect) -> String {
+    let mut pmid_matches: Vec<String> = Vec::new();
+    for index in 0..pmid_count {
+        pmid_matches.push(format!("(',' || REPLACE({pmids_column},' ','') || ',') LIKE {pattern}",pmids_column=pmids_column,pattern=dialect.placeholder(index + 1)));
     }
+    return format!("({pmid_matches})",pmid_matches=misc::str_vec_to_str_with_sep(&pmid_matches,String::from(" OR ")));
+}
+
+//one row for every substrate site and ptm type of the mv_event rows citing one of the pmids, with or without enzyme
+pub fn cited_events(pmid_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT event_name,sub_code,sub_symbol,residue,position, \
+                    {source_label} as source_label, \
+                    {pmids} as pmids \
+                    FROM {rows} \
+                    GROUP BY sub_code,sub_symbol,residue,position,event_name",
+                   source_label=dialect.string_agg("source_label",",","source_label"),
+                   pmids=dialect.string_agg("pmids",",","source_label"),
+                   rows=dialect.aggregated_rows("MV_EVENT",&cited_clause("pmids",pmid_count,dialect),"source_label"));
+}
+
+//the enzyme-substrate relations citing one of the pmids, as build_ptm_enzyme expects them
+pub fn cited_enzyme_substrates(pmid_count: usize, dialect: &Dialect) -> String {
+    return aggregated_ptm_enzymes(&cited_clause("pmids",pmid_count,dialect),dialect);
+}
+
+//the proteoforms citing one of the pmids
+pub fn cited_proteoforms(pmid_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_PROTEO where {where_clause} AND EVENT_NAME != 'Interaction'",where_clause=cited_clause("pmids",pmid_count,dialect));
+}
+
+//the proteoform ppis citing one of the pmids
+pub fn cited_proteoform_ppis(pmid_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_PROTEO where {where_clause} AND EVENT_NAME = 'Interaction'",where_clause=cited_clause("pmids",pmid_count,dialect));
 }
 
-pub fn get_decorations(engine: &Engine) -> String {
-    match engine {
-        Engine::Postgres => {
-            return String::from("select event_name,string_agg(source_label, ', ') as source_labels ,string_agg(pmids, ', ') as pmids from mv_event where sub_form_code = $1 and position = $2 and residue = $3 AND position is not null group by event_name");
-        },
-        Engine::Oracle => {
-            return String::from("SELECT * FROM SEQUENCE where regexp_like(ID,:1,'i')");
-        }
+//the ptm dependent ppis citing one of the pmids
+pub fn cited_ptm_ppis(pmid_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_EFIP where {where_clause}",where_clause=cited_clause("ppi_pmids",pmid_count,dialect));
+}
+
+//the given number of ids bound from first_index on, as a list of placeholders
+fn id_placeholders(first_index: usize, id_count: usize, dialect: &Dialect) -> String {
+    let mut placeholders: Vec<String> = Vec::new();
+    for index in 0..id_count {
+        placeholders.push(dialect.placeholder(first_index + index));
     }
+    return misc::str_vec_to_str_with_sep(&placeholders,String::from(","));
 }
 
-pub fn get_decorations_count(engine: &Engine) -> String {
-    match engine {
-        Engine::Postgres => {
-            return String::from(r#"select count(*) as "count" from mv_event where sub_code = $1 and position = $2 and residue = $3 AND position is not null"#);
-        },
-        Engine::Oracle => {
-            return String::from("SELECT * FROM SEQUENCE where regexp_like(ID,:1,'i')");
-        }
+// The enzyme-substrate relations of a network, where either the enzyme or the substrate is one of
+// the given number of ids. The ids are bound twice, for the enzymes and then for the substrates,
+// followed by the lower case ptm types, if any.
+pub fn enzyme_relations(id_count: usize, ptm_type_count: usize, dialect: &Dialect) -> String {
+    let mut where_clause = format!("(enz_code IN ({enzyme_codes}) OR sub_code IN ({substrate_codes}))",
+                                   enzyme_codes=id_placeholders(1,id_count,dialect),
+                                   substrate_codes=id_placeholders(id_count + 1,id_count,dialect));
+    if ptm_type_count > 0 {
+        where_clause = format!("{where_clause} and LOWER(event_name) IN ({ptm_types})",where_clause=where_clause,ptm_types=id_placeholders(2 * id_count + 1,ptm_type_count,dialect));
     }
-}
\ No newline at end of file
+    return aggregated_ptm_enzymes(&where_clause,dialect);
+}
+
+//the ptm dependent ppis of a network, where either partner is one of the given number of ids, bound twice
+pub fn ptm_ppi_relations(id_count: usize, dialect: &Dialect) -> String {
+    return format!("SELECT * FROM MV_EFIP where ppi_sub_code IN ({substrate_codes}) OR ppi_pr_code IN ({interactant_codes})",
+                   substrate_codes=id_placeholders(1,id_count,dialect),
+                   interactant_codes=id_placeholders(id_count + 1,id_count,dialect));
+}
@@ -0,0 +1,197 @@
+use actix_web::{HttpResponse, http};
+use actix_web::dev::HttpResponseBuilder;
+use errors::*;
+use models::{SiteStatus, ValidationReport};
+use negotiation::Format;
+use problem;
+use serde::Serialize;
+use serde_json;
+
+pub const REJECTED_ROWS_HEADER: &str = "x-rejected-rows";
+pub const WARNINGS_HEADER: &str = "x-warnings";
+pub const UNMATCHED_SITES_HEADER: &str = "x-unmatched-sites";
+
+/// A model written as one row of the delimited formats, through its flat counterpart.
+pub trait Flatten {
+    type Flat: Serialize;
+
+    fn flatten(&self) -> Self::Flat;
+}
+
+/// A response body that can be written as rows of the delimited formats.
+pub trait Rows {
+    type Row: Serialize;
+
+    fn rows(&self) -> Vec<Self::Row>;
+}
+
+impl<T: Flatten> Rows for Vec<T> {
+    type Row = T::Flat;
+
+    fn rows(&self) -> Vec<T::Flat> {
+        return self.iter().map(|item| item.flatten()).collect();
+    }
+}
+
+//serializes the value in the given format
+pub fn render<T: Serialize + Rows>(value: &T, format: Format) -> Result<String> {
+    if format == Format::Json {
+        match serde_json::to_string_pretty(value) {
+            Ok(value_serialized) => {
+                return Ok(value_serialized);
+            },
+            Err(error) => {
+                return Err(format!("{}",error).into());
+            }
+        }
+    }
+
+    if format == Format::Ndjson {
+        return render_ndjson(value);
+    }
+
+    let mut wtr = format.csv_writer();
+    for row in value.rows() {
+        match wtr.serialize(&row) {
+            Ok(_) => {},
+            Err(error) => {
+                return Err(format!("{}",error).into());
+            }
+        }
+    }
+
+    let inner;
+    match wtr.into_inner() {
+        Ok(value) => {
+            inner = value;
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+
+    let data = String::from_utf8(inner)?;
+    return Ok(data);
+}
+
+// One JSON document per line. Arrays are written one item per line and objects one entry per line,
+// so that the lines are the items a JSON response would hold.
+fn render_ndjson<T: Serialize>(value: &T) -> Result<String> {
+    let json_value;
+    match serde_json::to_value(value) {
+        Ok(val) => {
+            json_value = val;
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+
+    let mut lines: Vec<serde_json::Value> = Vec::new();
+    match json_value {
+        serde_json::Value::Array(items) => {
+            lines = items;
+        },
+        serde_json::Value::Object(entries) => {
+            for (key,entry) in entries {
+                let mut line = serde_json::Map::new();
+                line.insert(key,entry);
+                lines.push(serde_json::Value::Object(line));
+            }
+        },
+        other => {
+            lines.push(other);
+        }
+    }
+
+    let mut data = String::new();
+    for line in lines {
+        data.push_str(&line.to_string());
+        data.push('\n');
+    }
+    return Ok(data);
+}
+
+//200 response holding the value in the given format, or a problem if it could not be serialized
+pub fn response<T: Serialize + Rows>(context: &problem::RequestContext, value: &T, format: Format) -> HttpResponse {
+    match render(value,format) {
+        Ok(data) => {
+            return HttpResponse::Ok()
+                    .force_close()
+                    .header(http::header::CONTENT_TYPE, format.content_type())
+                    .body(data);
+        },
+        Err(error) => {
+            return problem::response(context,&error);
+        }
+    }
+}
+
+//the members of the validation report, written next to the results of a batch in json
+pub fn report_members(report: &ValidationReport) -> Result<serde_json::Map<String,serde_json::Value>> {
+    match serde_json::to_value(report) {
+        Ok(serde_json::Value::Object(members)) => {
+            return Ok(members);
+        },
+        Ok(_) => {
+            return Err("the validation report is not an object".into());
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+}
+
+//sets the headers holding the number of rejected rows, warnings and unmatched sites of a batch
+pub fn report_headers(builder: &mut HttpResponseBuilder, report: &ValidationReport) {
+    let unmatched_sites = report.site_checks.iter().filter(|site_check| site_check.status != SiteStatus::Match).count();
+    builder.header(REJECTED_ROWS_HEADER, format!("{}",report.rejected_rows.len()));
+    builder.header(WARNINGS_HEADER, format!("{}",report.warnings.len()));
+    builder.header(UNMATCHED_SITES_HEADER, format!("{}",unmatched_sites));
+}
+
+fn render_batch_json<T: Serialize>(results: &T, report: &ValidationReport) -> Result<String> {
+    let mut members = report_members(report)?;
+    match serde_json::to_value(results) {
+        Ok(value) => {
+            members.insert(String::from("results"),value);
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+
+    match serde_json::to_string_pretty(&members) {
+        Ok(value) => {
+            return Ok(value);
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+}
+
+// 200 response holding the results of a batch. JSON holds the validation report next to the
+// results, the other formats only hold the results, and every format gets the report headers.
+pub fn batch_response<T: Serialize + Rows>(context: &problem::RequestContext, results: &T, report: &ValidationReport, format: Format) -> HttpResponse {
+    let data_result: Result<String>;
+    if format == Format::Json {
+        data_result = render_batch_json(results,report);
+    }else{
+        data_result = render(results,format);
+    }
+
+    match data_result {
+        Ok(data) => {
+            let mut builder = HttpResponse::Ok();
+            report_headers(&mut builder,report);
+            return builder
+                    .force_close()
+                    .header(http::header::CONTENT_TYPE, format.content_type())
+                    .body(data);
+        },
+        Err(error) => {
+            return problem::response(context,&error);
+        }
+    }
+}
@@ -33,7 +33,24 @@ pub fn init_routes(application: App<super::State>) -> App<super::State> {
             .resource("/v1/batch_ptm_ppi",|r|r.method(http::Method::POST).f(controller::batch_ptm_ppi_controller))
             .resource("/v1/batch-ptm-enzymes",|r|r.method(http::Method::POST).f(controller::batch_ptm_enzymes_controller))
             .resource("/v1/batch-ptm-ppi",|r|r.method(http::Method::POST).f(controller::batch_ptm_ppi_controller))
+            .resource("/v1/batch-info",|r|r.method(http::Method::POST).f(controller::batch_info_controller))
+            .resource("/v1/id-mapping",|r|r.method(http::Method::POST).f(controller::id_mapping_controller))
+            .resource("/v1/pmid/{pmid}",|r|r.method(http::Method::GET).f(controller::pmid_controller))
+            .resource("/v1/batch-pmid",|r|r.method(http::Method::POST).f(controller::batch_pmid_controller))
+            .resource("/v1/network",|r|r.method(http::Method::GET).f(controller::network_controller))
+            .resource("/v1/batch-enzyme-substrates",|r|r.method(http::Method::POST).f(controller::batch_enzyme_substrates_controller))
+            .resource("/v1/analysis/ksea",|r|r.method(http::Method::POST).f(controller::ksea_controller))
+            .resource("/v1/analysis/ptm-enrichment",|r|r.method(http::Method::POST).f(controller::ptm_enrichment_controller))
+            .resource("/v1/jobs/batch-ptm-enzymes",|r|r.method(http::Method::POST).f(controller::submit_ptm_enzymes_job_controller))
+            .resource("/v1/jobs/batch-ptm-ppi",|r|r.method(http::Method::POST).f(controller::submit_ptm_ppi_job_controller))
+            .resource("/v1/jobs/{id}",|r| {
+                r.method(http::Method::GET).f(controller::get_job_controller);
+                r.method(http::Method::DELETE).f(controller::cancel_job_controller);
+            })
+            .resource("/v1/jobs/{id}/result",|r|r.method(http::Method::GET).f(controller::get_job_result_controller))
             .resource("/v1/{id}/msa",|r|r.method(http::Method::GET).f(controller::get_msa_controller))
             .resource("/v1/{id}/variants",|r|r.method(http::Method::GET).f(controller::get_variants))
-            .register();
+            .resource("/v1/{id}/sites/{site}",|r|r.method(http::Method::GET).f(controller::site_controller))
+            .register()
+            .default_resource(|r| r.f(controller::not_found_controller));
 }
\ No newline at end of file
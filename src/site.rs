@@ -0,0 +1,102 @@
+use database::Backend;
+use errors::*;
+use models::{Enzyme, QuerySubstrate, SiteRecord};
+use validation;
+
+//residues taken on each side of the site when looking for variants, unless asked otherwise
+pub const DEFAULT_VARIANT_WINDOW: i64 = 5;
+
+//the widest window around the site variants are looked for in
+pub const MAX_VARIANT_WINDOW: i64 = 50;
+
+// Gathers everything known about a single site of an entry into one record:
+//
+// * the PTM events of MV_EVENT at the site, scored like /substrate, with their PTM types, enzymes
+//   and best score gathered
+// * the PTM-dependent PPIs of MV_EFIP where the site is the modified site of the substrate
+// * the BIOMUTA variants of the entry at most variant_window residues away from the site
+// * the MV_PROTEO proteoforms listing the site among their sites
+//
+// The events are read for the given form only, so an isoform id gives the sites of the isoform.
+// The site is also checked against the stored sequence, which tells a site numbered after another
+// isoform apart from a site nothing is known about.
+pub fn site_record(conn: &Backend, id: &str, residue: &str, position: i64, variant_window: i64) -> Result<SiteRecord> {
+    let site = format!("{residue}{position}",residue=residue,position=position);
+    let is_site = |value: &Option<String>| value.as_ref().map(|value| value.trim() == site).unwrap_or(false);
+
+    //events at the site
+    let events: Vec<_> = conn.get_sub_form_events(id)?
+                             .into_iter()
+                             .filter(|event| is_site(&event.site))
+                             .collect();
+
+    let mut ptm_types: Vec<String> = Vec::new();
+    let mut enzymes: Vec<Enzyme> = Vec::new();
+    let mut score: Option<i64> = None;
+    for event in &events {
+        match event.ptm_type {
+            Some(ref ptm_type) => {
+                if !ptm_types.contains(ptm_type) {
+                    ptm_types.push(ptm_type.clone());
+                }
+            },
+            None => {}
+        }
+        for enzyme in &event.enzymes {
+            if !enzymes.contains(enzyme) {
+                enzymes.push(enzyme.clone());
+            }
+        }
+        if event.score > score {
+            score = event.score;
+        }
+    }
+
+    //ppis depending on the site, the entry may be the interactant of other ppis
+    let ptm_ppis: Vec<_> = conn.get_ptmppis(id)?
+                               .into_iter()
+                               .filter(|ptm_ppi| {
+                                   let is_substrate = ptm_ppi.substrate.as_ref()
+                                                             .and_then(|substrate| substrate.uniprot_id.as_ref())
+                                                             .map(|uniprot_id| uniprot_id.trim().eq_ignore_ascii_case(id.trim()))
+                                                             .unwrap_or(false);
+                                   return is_substrate && is_site(&ptm_ppi.site);
+                               })
+                               .collect();
+
+    //variants around the site, the variants are looked up with a pattern that also finds the isoforms
+    let variants: Vec<_> = conn.get_variants(id)?
+                               .into_iter()
+                               .filter(|variant| {
+                                   let is_entry = variant.ac.as_ref().map(|ac| ac.trim().eq_ignore_ascii_case(id.trim())).unwrap_or(false);
+                                   return is_entry && (variant.position as i64 - position).abs() <= variant_window;
+                               })
+                               .collect();
+
+    //proteoforms carrying the site
+    let proteoforms: Vec<_> = conn.get_proteoforms(id)?
+                                  .into_iter()
+                                  .filter(|proteoform| proteoform.sites.iter().any(|proteoform_site| proteoform_site.trim() == site))
+                                  .collect();
+
+    let site_check = validation::check_site(conn,QuerySubstrate {
+        substrate_ac: String::from(id),
+        site_residue: String::from(residue),
+        site_position: format!("{}",position)
+    })?;
+
+    return Ok(SiteRecord {
+        uniprot_ac: String::from(id),
+        site: site.clone(),
+        residue: String::from(residue),
+        position: position,
+        site_check: site_check,
+        ptm_types: ptm_types,
+        score: score,
+        enzymes: enzymes,
+        events: events,
+        ptm_ppis: ptm_ppis,
+        variants: variants,
+        proteoforms: proteoforms
+    });
+}
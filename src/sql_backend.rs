@@ -0,0 +1,454 @@
+use models::*;
+use errors::*;
+use misc;
+use database::*;
+use query_builder;
+use query_builder::Dialect;
+use std::collections::HashMap;
+
+/// A parameter bound to a query, converted by every engine to its own parameter type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Text(String),
+    Int(i64)
+}
+
+impl<'a> From<&'a str> for Param {
+    fn from(value: &'a str) -> Param {
+        return Param::Text(String::from(value));
+    }
+}
+
+impl<'a> From<&'a String> for Param {
+    fn from(value: &'a String) -> Param {
+        return Param::Text(value.clone());
+    }
+}
+
+impl From<String> for Param {
+    fn from(value: String) -> Param {
+        return Param::Text(value);
+    }
+}
+
+impl From<i64> for Param {
+    fn from(value: i64) -> Param {
+        return Param::Int(value);
+    }
+}
+
+//one text parameter for every value
+pub fn text_params(values: &[String]) -> Vec<Param> {
+    return values.iter().map(|value| Param::from(value)).collect();
+}
+
+/// The connection of an engine, which runs the queries written in its dialect.
+///
+/// This is all an engine has to provide: every Backend query is written once below, on top of
+/// query_rows, with the SQL of query_builder in the dialect of the engine.
+pub trait Connector: Send {
+    fn dialect(&self) -> &Dialect;
+
+    //fails when the connection can no longer run queries
+    fn ping(&self) -> Result<()>;
+
+    fn is_broken(&self) -> bool {
+        return false;
+    }
+
+    // Runs the query with the params, handing the rows to handle_row one at a time until it returns
+    // false or the rows run out. No other query is run on the connection while the rows are read.
+    fn query_rows(&self, query_str: &str, params: &[Param], handle_row: &mut FnMut(&MyRow) -> Result<bool>) -> Result<()>;
+
+    //the first row of the result, the others are not read
+    fn query_one<T,F>(&self, query_str: &str, params: &[Param], build_item: F) -> Result<Option<T>>
+        where Self: Sized, F: Fn(&MyRow) -> Result<T>
+    {
+        let mut item: Option<T> = None;
+        self.query_rows(query_str,params,&mut |row| {
+            item = Some(build_item(row)?);
+            return Ok(false);
+        })?;
+        return Ok(item);
+    }
+
+    fn query_each<F>(&self, query_str: &str, params: &[Param], mut handle_row: F) -> Result<()>
+        where Self: Sized, F: FnMut(&MyRow) -> Result<()>
+    {
+        return self.query_rows(query_str,params,&mut |row| {
+            handle_row(row)?;
+            return Ok(true);
+        });
+    }
+
+    fn query_bulk<T,F>(&self, query_str: &str, params: &[Param], build_item: F) -> Result<Vec<T>>
+        where Self: Sized, F: Fn(&MyRow) -> Result<T>
+    {
+        let mut items: Vec<T> = Vec::new();
+        self.query_each(query_str,params,|row| {
+            let item = build_item(row)?;
+            items.push(item);
+            return Ok(());
+        })?;
+        return Ok(items);
+    }
+}
+
+// Binds the search parameters in the order described in query_builder::search and runs the query
+// with them. Nothing is run when the term type is not matched against any column.
+fn with_search_params<T,F>(search_term: &str, term_type: &str, ptm_types: &Vec<String>, organism_taxons: &Vec<i32>, run_query: F) -> Result<Option<T>>
+    where F: FnOnce(&[Param]) -> Result<T>
+{
+    //the search term is bound once for every column it is matched against
+    let param_count = query_builder::search_term_param_count(term_type);
+    if param_count == 0 {
+        return Ok(None);
+    }
+
+    let mut search_params: Vec<Param> = Vec::new();
+    for _ in 0..param_count {
+        search_params.push(Param::from(like_pattern(search_term)));
+    }
+    for ptm_type in ptm_types {
+        search_params.push(Param::from(list_item_pattern(ptm_type)));
+    }
+    for taxon in organism_taxons {
+        search_params.push(Param::from(format!("{taxon}",taxon=taxon)));
+    }
+
+    let value = run_query(&search_params)?;
+    return Ok(Some(value));
+}
+
+//flattens the substrate keys into the parameter list expected by the batch queries
+fn substrate_key_params(substrate_keys: &[SiteKey]) -> Vec<Param> {
+    let mut params: Vec<Param> = Vec::new();
+    for &(ref substrate_ac,ref residue,position) in substrate_keys {
+        params.push(Param::from(substrate_ac));
+        params.push(Param::from(residue));
+        params.push(Param::from(position));
+    }
+    return params;
+}
+
+//the ids bound twice, as the network relation queries expect them, followed by the extra params
+fn network_params(ids: &[String], extra_params: &[String]) -> Vec<Param> {
+    let mut params = text_params(ids);
+    params.append(&mut text_params(ids));
+    params.append(&mut text_params(extra_params));
+    return params;
+}
+
+impl<C: Connector> Backend for C {
+    fn is_valid(&self) -> Result<()> {
+        return self.ping();
+    }
+
+    fn has_broken(&self) -> bool {
+        return self.is_broken();
+    }
+
+    fn get_info(&self, id: &str) -> Result<Option<Info>> {
+        let query_str = query_builder::info(self.dialect());
+        let info = self.query_one(&query_str,&[Param::from(id)],|row| build_info(row,None))?;
+
+        //the pro info is read once the row is, since no query can run while the rows of another are read
+        match info {
+            Some(mut info) => {
+                info.pro = self.get_pro_info(id)?;
+                return Ok(Some(info));
+            },
+            None => {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn get_pro_info(&self, id: &str) -> Result<Option<Pro>> {
+        //construct the pro id
+        let pro_id = pro_entry_code(id);
+
+        //build query str
+        let query_str = query_builder::pro_info(self.dialect());
+
+        return self.query_one(&query_str,&[Param::from(pro_id)],build_pro_info);
+    }
+
+    fn get_infos(&self, ids: &Vec<String>) -> Result<Vec<Info>> {
+        let mut infos: Vec<Info> = Vec::new();
+        let mut pros: HashMap<String,Pro> = HashMap::new();
+        for ids_chunk in ids.chunks(BATCH_QUERY_SIZE) {
+            let mut entry_codes: Vec<String> = ids_chunk.to_vec();
+            for id in ids_chunk {
+                entry_codes.push(pro_entry_code(id));
+            }
+            let query_str = query_builder::entries(entry_codes.len(),self.dialect());
+            self.query_each(&query_str,&text_params(&entry_codes),|row| {
+                return build_entry_or_pro(row,ids_chunk,&mut infos,&mut pros);
+            })?;
+        }
+        add_pro_infos(&mut infos,pros);
+        return Ok(infos);
+    }
+
+    fn get_mapping_candidates(&self, tokens: &Vec<String>) -> Result<Vec<MappedEntry>> {
+        let mut candidates: Vec<MappedEntry> = Vec::new();
+        for tokens_chunk in tokens.chunks(MAPPING_QUERY_SIZE) {
+            let query_str = query_builder::mapping_candidates(tokens_chunk.len(),self.dialect());
+            let mut chunk_candidates = self.query_bulk(&query_str,&text_params(&mapping_params(tokens_chunk)),build_mapped_entry)?;
+            candidates.append(&mut chunk_candidates);
+        }
+        return Ok(candidates);
+    }
+
+    fn entry_exists(&self, id: &str) -> Result<bool> {
+        let query_str = query_builder::entry_exists(self.dialect());
+        let base_accession = misc::base_accession(id);
+        let count = self.query_one(&query_str,&[Param::from(id),Param::from(base_accession)],build_entry_count)?.unwrap_or(0);
+        return Ok(count > 0);
+    }
+
+    fn get_entry_candidates(&self, pattern: &str) -> Result<Vec<Suggestion>> {
+        let query_str = query_builder::entry_candidates(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(pattern),Param::from(pattern),Param::from(pattern)],build_suggestion);
+    }
+
+    fn get_known_entries(&self, ids: &Vec<String>) -> Result<Vec<String>> {
+        let mut known_entries: Vec<String> = Vec::new();
+        for ids_chunk in ids.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::known_entries(ids_chunk.len(),self.dialect());
+            let mut chunk_entries = self.query_bulk(&query_str,&text_params(ids_chunk),build_entry_code)?;
+            known_entries.append(&mut chunk_entries);
+        }
+        return Ok(known_entries);
+    }
+
+    fn get_substrate_label_counts(&self, organism_taxons: &Vec<i32>) -> Result<Vec<(Option<String>,i64)>> {
+        let query_str = query_builder::substrate_label_counts(organism_taxons.len(),self.dialect());
+        let taxon_codes: Vec<String> = organism_taxons.iter().map(|taxon| format!("{taxon}",taxon=taxon)).collect();
+        return self.query_bulk(&query_str,&text_params(&taxon_codes),build_label_count);
+    }
+
+    fn get_substrate_labels(&self, ids: &Vec<String>, organism_taxons: &Vec<i32>) -> Result<Vec<(String,Option<String>)>> {
+        let taxon_codes: Vec<String> = organism_taxons.iter().map(|taxon| format!("{taxon}",taxon=taxon)).collect();
+        let mut entry_labels: Vec<(String,Option<String>)> = Vec::new();
+        for ids_chunk in ids.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::substrate_labels(ids_chunk.len(),taxon_codes.len(),self.dialect());
+            let mut params = text_params(ids_chunk);
+            params.append(&mut text_params(&taxon_codes));
+            let mut chunk_labels = self.query_bulk(&query_str,&params,build_entry_labels)?;
+            entry_labels.append(&mut chunk_labels);
+        }
+        return Ok(entry_labels);
+    }
+
+    fn search_count(&self,
+                    search_term: &str,
+                    term_type: &str,
+                    role: &str,
+                    ptm_types: &Vec<String>,
+                    organism_taxons: &Vec<i32>) -> Result<i64> {
+
+        //build the query
+        let count_query_str = query_builder::search_count(term_type,role,ptm_types,organism_taxons,self.dialect());
+
+        let count = with_search_params(search_term,term_type,ptm_types,organism_taxons,|search_params| {
+            return self.query_one(&count_query_str,search_params,build_search_count);
+        })?;
+
+        return Ok(count.unwrap_or(None).unwrap_or(0));
+    }
+
+    fn search_each(&self,
+                   search_term: &str,
+                   term_type: &str,
+                   role: &str,
+                   ptm_types: &Vec<String>,
+                   organism_taxons: &Vec<i32>,
+                   paginate: bool,
+                   offset: i32,
+                   limit: i32,
+                   handle_item: &mut FnMut(SearchResult) -> Result<()>) -> Result<()> {
+
+        //build the query
+        let query_str = query_builder::search(term_type,role,ptm_types,organism_taxons,paginate,offset,limit,self.dialect());
+
+        with_search_params(search_term,term_type,ptm_types,organism_taxons,|search_params| {
+            return self.query_each(&query_str,search_params,|row| {
+                let search_result = build_search_result(row)?;
+                return handle_item(search_result);
+            });
+        })?;
+
+        return Ok(());
+    }
+
+    fn get_sub_forms(&self, id: &str) -> Result<Vec<String>> {
+        let query_str = query_builder::sub_forms(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(id)],build_sub_form);
+    }
+
+    fn get_events_for_sub_form(&self, sub_form: &str) -> Result<(Vec<SubstrateEvent>,HashMap<String,i64>)> {
+        let query_str = query_builder::events_for_sub_form(self.dialect());
+
+        let mut events: Vec<SubstrateEvent> = Vec::new();
+        let mut pmid_stats: HashMap<String,i64> = HashMap::new();
+
+        self.query_each(&query_str,&[Param::from(sub_form)],|row| {
+            //update the events and pmid stats
+            update_events(row,&mut events,&mut pmid_stats);
+            return Ok(());
+        })?;
+
+        return Ok((events,pmid_stats));
+    }
+
+    fn get_enzyme_events(&self, id: &str) -> Result<Vec<EnzymeEvent>> {
+        let query_str = query_builder::enzyme_events(self.dialect());
+
+        let enzyme_events = self.query_bulk(&query_str,&[Param::from(id)],build_enzyme_event)?;
+
+        return Ok(enzyme_events.into_iter().filter(|enzyme_event| enzyme_event.site != None).collect());
+    }
+
+    fn get_proteoforms(&self, id: &str) -> Result<Vec<Proteoform>> {
+        let query_str = query_builder::proteoforms(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(like_pattern(id))],build_proteoform);
+    }
+
+    fn get_proteoformppis(&self, id: &str) -> Result<Vec<ProteoformPPI>> {
+        let query_str = query_builder::proteoformppi(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(like_pattern(id))],build_proteoform_ppi);
+    }
+
+    fn get_ptmppis(&self, id: &str) -> Result<Vec<PTMPPI>> {
+        let query_str = query_builder::ptmppi(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(id)],build_pptm_ppi);
+    }
+
+    fn ptm_enzyme_sites_each(&self, substrate_keys: &Vec<SiteKey>, handle_item: &mut FnMut(SiteKey,BatchPTMEnzyme) -> Result<()>) -> Result<()> {
+        for substrate_keys_chunk in substrate_keys.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::ptm_enzymes(substrate_keys_chunk.len(),self.dialect());
+            self.query_each(&query_str,&substrate_key_params(substrate_keys_chunk),|row| {
+                let ptm_enzyme = build_ptm_enzyme(row)?;
+                if misc::filter_empty_enzymes(&ptm_enzyme) {
+                    match build_site_key(row,"sub_code","residue","position") {
+                        Some(site_key) => {
+                            handle_item(site_key,ptm_enzyme)?;
+                        },
+                        None => {}
+                    }
+                }
+                return Ok(());
+            })?;
+        }
+
+        return Ok(());
+    }
+
+    fn enzyme_substrates_each(&self, enzyme_ids: &Vec<String>, ptm_types: &Vec<String>, handle_item: &mut FnMut(BatchPTMEnzyme) -> Result<()>) -> Result<()> {
+        for enzyme_ids_chunk in enzyme_ids.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::enzyme_substrates(enzyme_ids_chunk.len(),ptm_types.len(),self.dialect());
+            let mut params = text_params(enzyme_ids_chunk);
+            params.append(&mut text_params(ptm_types));
+            self.query_each(&query_str,&params,|row| {
+                let ptm_enzyme = build_ptm_enzyme(row)?;
+                return handle_item(ptm_enzyme);
+            })?;
+        }
+
+        return Ok(());
+    }
+
+    fn ptm_ppi_sites_each(&self, substrate_keys: &Vec<SiteKey>, handle_item: &mut FnMut(SiteKey,BatchPTMPPI) -> Result<()>) -> Result<()> {
+        for substrate_keys_chunk in substrate_keys.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::ptm_ppi(substrate_keys_chunk.len(),self.dialect());
+            self.query_each(&query_str,&substrate_key_params(substrate_keys_chunk),|row| {
+                let ptm_ppi = build_ptm_ppi(row)?;
+                match build_site_key(row,"ptm_sub_code","ptm_residue","ptm_position") {
+                    Some(site_key) => {
+                        handle_item(site_key,ptm_ppi)?;
+                    },
+                    None => {}
+                }
+                return Ok(());
+            })?;
+        }
+
+        return Ok(());
+    }
+
+    fn get_sequences(&self, id: &str) -> Result<Vec<Sequence>> {
+        let query_str = query_builder::get_sequences(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(like_pattern(id))],build_sequences);
+    }
+
+    fn get_entry_sequences(&self, accessions: &Vec<String>) -> Result<Vec<Sequence>> {
+        let mut sequences: Vec<Sequence> = Vec::new();
+        for accessions_chunk in accessions.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::entry_sequences(accessions_chunk.len(),self.dialect());
+            let mut params = text_params(accessions_chunk);
+            for accession in accessions_chunk {
+                params.push(Param::from(isoform_pattern(accession)));
+            }
+            sequences.append(&mut self.query_bulk(&query_str,&params,build_sequences)?);
+        }
+        return Ok(sequences);
+    }
+
+    fn get_site_decorations(&self, form_id: &str) -> Result<Vec<(i64,String,Decoration)>> {
+        let query_str = query_builder::get_decorations(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(form_id)],build_site_decoration);
+    }
+
+    fn get_decorated_sites(&self, id: &str) -> Result<Vec<(i64,String)>> {
+        let query_str = query_builder::get_decorated_sites(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(id)],build_decorated_site);
+    }
+
+    fn get_variants(&self, id: &str) -> Result<Vec<Variant>> {
+        let query_str = query_builder::variants(self.dialect());
+        return self.query_bulk(&query_str,&[Param::from(like_pattern(id))],build_variant);
+    }
+
+    fn get_cited_records(&self, pmids: &Vec<String>) -> Result<CitedRecords> {
+        let mut cited_records = CitedRecords::default();
+        for pmids_chunk in pmids.chunks(CITED_QUERY_SIZE) {
+            let params = text_params(&cited_params(pmids_chunk));
+
+            let query_str = query_builder::cited_events(pmids_chunk.len(),self.dialect());
+            cited_records.ptm_events.append(&mut self.query_bulk(&query_str,&params,build_cited_event)?);
+
+            let query_str = query_builder::cited_enzyme_substrates(pmids_chunk.len(),self.dialect());
+            cited_records.enzyme_substrates.append(&mut self.query_bulk(&query_str,&params,build_ptm_enzyme)?);
+
+            let query_str = query_builder::cited_proteoforms(pmids_chunk.len(),self.dialect());
+            cited_records.proteoforms.append(&mut self.query_bulk(&query_str,&params,build_proteoform)?);
+
+            let query_str = query_builder::cited_proteoform_ppis(pmids_chunk.len(),self.dialect());
+            cited_records.proteoform_ppis.append(&mut self.query_bulk(&query_str,&params,build_proteoform_ppi)?);
+
+            let query_str = query_builder::cited_ptm_ppis(pmids_chunk.len(),self.dialect());
+            cited_records.ptm_ppis.append(&mut self.query_bulk(&query_str,&params,build_pptm_ppi)?);
+        }
+        return Ok(cited_records);
+    }
+
+    fn get_enzyme_relations(&self, ids: &Vec<String>, ptm_types: &Vec<String>) -> Result<Vec<BatchPTMEnzyme>> {
+        let mut relations: Vec<BatchPTMEnzyme> = Vec::new();
+        for ids_chunk in ids.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::enzyme_relations(ids_chunk.len(),ptm_types.len(),self.dialect());
+            relations.append(&mut self.query_bulk(&query_str,&network_params(ids_chunk,ptm_types),build_ptm_enzyme)?);
+        }
+        return Ok(relations);
+    }
+
+    fn get_ptm_ppi_relations(&self, ids: &Vec<String>) -> Result<Vec<BatchPTMPPI>> {
+        let mut relations: Vec<BatchPTMPPI> = Vec::new();
+        for ids_chunk in ids.chunks(BATCH_QUERY_SIZE) {
+            let query_str = query_builder::ptm_ppi_relations(ids_chunk.len(),self.dialect());
+            relations.append(&mut self.query_bulk(&query_str,&network_params(ids_chunk,&[]),build_ptm_ppi)?);
+        }
+        return Ok(relations);
+    }
+}
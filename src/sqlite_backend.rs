@@ -0,0 +1,198 @@
+use errors::*;
+use rusqlite;
+use rusqlite::types::ToSql;
+use database::*;
+use query_builder::Dialect;
+use sql_backend::{Connector,Param};
+
+// The sqlite schema uses lowercase column names, so every lookup is lowercased to let the shared
+// row builders keep using the upper case names the other engines return.
+impl<'a,'stmt> MyRow<'a> for rusqlite::Row<'a,'stmt> {
+    fn get_string(&self,column_name: &str) -> Option<String>{
+        let result: rusqlite::Result<Option<String>> = self.get_checked(column_name.to_lowercase().as_str());
+        match result {
+            Ok(val) => {
+                return val;
+            },
+            Err(_error) => {
+                return None;
+            }
+        };
+    }
+
+    fn get_string_unwrapped(&self,column_name: &str) -> String {
+        let result: rusqlite::Result<String> = self.get_checked(column_name.to_lowercase().as_str());
+        match result {
+            Ok(val) => {
+                return val;
+            },
+            Err(error) => {
+                panic!("{}",error);
+            }
+        };
+    }
+
+    fn get_i64(&self,column_name: &str) -> Option<i64>{
+        let result: rusqlite::Result<Option<i64>> = self.get_checked(column_name.to_lowercase().as_str());
+        match result {
+            Ok(val) => {
+                return val;
+            },
+            Err(_error) => {
+                return None;
+            }
+        };
+    }
+
+    fn get_bool(&self,column_name: &str) -> Option<bool>{
+        let result: rusqlite::Result<Option<bool>> = self.get_checked(column_name.to_lowercase().as_str());
+        match result {
+            Ok(val) => {
+                return val;
+            },
+            Err(_error) => {
+                return None;
+            }
+        };
+    }
+}
+
+pub struct SqliteBackend {
+    conn: rusqlite::Connection
+}
+
+impl SqliteBackend {
+    pub fn connect(db_params: &DBParams) -> Result<SqliteBackend> {
+        //the api never writes, so the database file is opened read only
+        let connect_result = rusqlite::Connection::open_with_flags(&db_params.db_name,rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY);
+        match connect_result {
+            Ok(conn) => {
+                return Ok(SqliteBackend { conn: conn });
+            },
+            Err(error) => {
+                error!("{}",error);
+                error!("Database path - {}",db_params.db_name);
+                return Err(format!("{}",error).into());
+            }
+        }
+    }
+}
+
+//query errors are reported as database errors, except for queries interrupted or blocked by a lock for too long
+fn query_error(error: rusqlite::Error) -> Error {
+    let is_timeout;
+    match error {
+        rusqlite::Error::SqliteFailure(ref failure,_) => {
+            is_timeout = failure.code == rusqlite::ErrorCode::OperationInterrupted || failure.code == rusqlite::ErrorCode::DatabaseBusy;
+        },
+        _ => {
+            is_timeout = false;
+        }
+    }
+
+    if is_timeout {
+        return ErrorKind::Timeout(format!("{}",error)).into();
+    }else{
+        return ErrorKind::Database(format!("{}",error)).into();
+    }
+}
+
+fn to_sql_params(params: &[Param]) -> Vec<&ToSql> {
+    let mut sql_params: Vec<&ToSql> = Vec::new();
+    for param in params {
+        match param {
+            &Param::Text(ref value) => {
+                sql_params.push(value);
+            },
+            &Param::Int(ref value) => {
+                sql_params.push(value);
+            }
+        }
+    }
+    return sql_params;
+}
+
+pub struct SqliteDialect;
+
+impl Dialect for SqliteDialect {
+    fn placeholder(&self, index: usize) -> String {
+        return format!("?{index}",index=index);
+    }
+
+    //like is case insensitive for ascii in sqlite
+    fn like_ignore_case(&self, column: &str, pattern: &str) -> String {
+        return format!("{column} LIKE {pattern} ESCAPE '\\'",column=column,pattern=pattern);
+    }
+
+    fn page(&self, offset: i64, limit: i64) -> String {
+        return format!("LIMIT {limit} OFFSET {offset}",limit=limit,offset=offset);
+    }
+
+    fn row_values(&self, tuples: &str) -> String {
+        return format!("(values {tuples})",tuples=tuples);
+    }
+
+    fn string_agg(&self, column: &str, separator: &str, _order_column: &str) -> String {
+        return format!("group_concat({column},'{separator}')",column=column,separator=separator);
+    }
+
+    //group_concat has no ORDER BY, so the rows are sorted before grouping
+    fn aggregated_rows(&self, table: &str, where_clause: &str, order_column: &str) -> String {
+        return format!("(select * from {table} where {where_clause} order by {order_column})",table=table,where_clause=where_clause,order_column=order_column);
+    }
+}
+
+impl Connector for SqliteBackend {
+    fn dialect(&self) -> &Dialect {
+        return &SqliteDialect;
+    }
+
+    fn ping(&self) -> Result<()> {
+        match self.conn.execute_batch("SELECT 1") {
+            Ok(_) => {
+                return Ok(());
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+    }
+
+    //the rows are stepped to one at a time, so the rest of the result is never read once handle_row stops
+    fn query_rows(&self, query_str: &str, params: &[Param], handle_row: &mut FnMut(&MyRow) -> Result<bool>) -> Result<()> {
+        let mut stmt;
+        match self.conn.prepare(query_str) {
+            Ok(val) => {
+                stmt = val;
+            },
+            Err(error) => {
+                error!("{}",error);
+                return Err(query_error(error));
+            }
+        }
+
+        let sql_params = to_sql_params(params);
+        let query_result = stmt.query(&sql_params);
+        match query_result {
+            Ok(mut rows) => {
+                while let Some(row_result) = rows.next() {
+                    match row_result {
+                        Ok(row) => {
+                            if !handle_row(&row)? {
+                                return Ok(());
+                            }
+                        },
+                        Err(error) => {
+                            error!("{}",error);
+                            return Err(query_error(error));
+                        }
+                    }
+                }
+                return Ok(());
+            },
+            Err(error) => {
+                return Err(query_error(error));
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use actix_web::{HttpResponse, http, error};
+use actix_web::dev::HttpResponseBuilder;
+use bytes::Bytes;
+use csv;
+use errors::*;
+use futures::{Sink, Stream};
+use futures::sink::Wait;
+use futures::sync::mpsc;
+use misc;
+use models::ValidationReport;
+use negotiation::Format;
+use problem;
+use render;
+use render::Flatten;
+use serde::Serialize;
+use serde_json;
+use std::io;
+use std::io::Write;
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+
+//number of chunks waiting to be sent to the client. The query thread blocks once the buffer is
+//full, which keeps the memory used by a streamed response flat whatever the size of the result.
+const CHUNK_BUFFER_SIZE: usize = 8;
+
+//size of the chunks of the response body
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type ChunkSender = mpsc::Sender<Result<Bytes>>;
+
+//the writing of a streamed response, run once by a producer thread
+type Producer = Box<FnMut() + Send>;
+
+/// Runs the producers of the streamed responses on a fixed number of threads.
+///
+/// A producer holds its pooled connection until the client has read the whole response, so the
+/// number of producer threads bounds the connections taken by streams, and slow clients can not
+/// starve the other requests of the pool. A response finding every producer busy is refused with
+/// ErrorKind::StreamsBusy rather than queued, since it would wait holding its connection.
+#[derive(Clone)]
+pub struct StreamPool {
+    sender: Arc<Mutex<std_mpsc::SyncSender<Producer>>>,
+    active: Arc<AtomicUsize>,
+    max_streams: usize
+}
+
+impl StreamPool {
+    //starts the producer threads
+    pub fn start(max_streams: usize) -> StreamPool {
+        let (sender, receiver) = std_mpsc::sync_channel::<Producer>(max_streams);
+        let stream_pool = StreamPool {
+            sender: Arc::new(Mutex::new(sender)),
+            active: Arc::new(AtomicUsize::new(0)),
+            max_streams: max_streams
+        };
+
+        //the producers take the responses from a shared receiver
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..max_streams {
+            let producer_receiver = receiver.clone();
+            let active = stream_pool.active.clone();
+            thread::spawn(move || {
+                loop {
+                    let mut producer;
+                    match lock(&producer_receiver).recv() {
+                        Ok(value) => {
+                            producer = value;
+                        },
+                        Err(_error) => {
+                            //every sender is gone, the server is stopping
+                            return;
+                        }
+                    }
+
+                    //the responses catch their own panics, this only keeps the producer thread alive
+                    match panic::catch_unwind(panic::AssertUnwindSafe(|| producer())) {
+                        Ok(_) => {},
+                        Err(payload) => {
+                            error!("streamed response aborted - {message}",message=misc::panic_message(&payload));
+                        }
+                    }
+                    active.fetch_sub(1,Ordering::SeqCst);
+                }
+            });
+        }
+
+        return stream_pool;
+    }
+
+    //hands the producer to a free thread, or fails with ErrorKind::StreamsBusy when every one is streaming
+    fn run(&self, producer: Producer) -> Result<()> {
+        if self.active.fetch_add(1,Ordering::SeqCst) >= self.max_streams {
+            self.active.fetch_sub(1,Ordering::SeqCst);
+            return Err(ErrorKind::StreamsBusy(format!("{max_streams} responses are being streamed already, try again later",max_streams=self.max_streams)).into());
+        }
+
+        //the channel holds max_streams producers, so a reserved producer never blocks here
+        match lock(&self.sender).send(producer) {
+            Ok(_) => {
+                return Ok(());
+            },
+            Err(_error) => {
+                self.active.fetch_sub(1,Ordering::SeqCst);
+                return Err("the stream producers are stopped".into());
+            }
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> ::std::sync::MutexGuard<T> {
+    match mutex.lock() {
+        Ok(guard) => {
+            return guard;
+        },
+        Err(poisoned) => {
+            return poisoned.into_inner();
+        }
+    }
+}
+
+//sends everything written to it as chunks of the response body
+struct ChunkWriter {
+    sink: Wait<ChunkSender>
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.sink.send(Ok(Bytes::from(buf))) {
+            Ok(_) => {
+                return Ok(buf.len());
+            },
+            Err(_error) => {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe,"the client closed the connection"));
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.sink.flush() {
+            Ok(_) => {
+                return Ok(());
+            },
+            Err(_error) => {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe,"the client closed the connection"));
+            }
+        }
+    }
+}
+
+enum Output {
+    Json(io::BufWriter<ChunkWriter>),
+    Ndjson(io::BufWriter<ChunkWriter>),
+    Delimited(csv::Writer<io::BufWriter<ChunkWriter>>)
+}
+
+/// Writes the items of a streamed response one at a time in the negotiated format.
+///
+/// JSON is written as an array, NDJSON as one item per line and the delimited formats as one flat
+/// row per item, with the header row written along with the first item, or on its own when the
+/// item type was given to columns and no item is written. The JSON array of a batch response is
+/// the results member of an object which also holds its validation report.
+pub struct RowWriter {
+    format: Format,
+    output: Output,
+    items_written: usize,
+    header: Option<Vec<u8>>,
+    report: Option<ValidationReport>
+}
+
+impl RowWriter {
+    fn new(format: Format, sender: ChunkSender, report: Option<ValidationReport>) -> RowWriter {
+        let chunk_writer = io::BufWriter::with_capacity(CHUNK_SIZE,ChunkWriter { sink: sender.wait() });
+
+        let output;
+        match format {
+            Format::Json => {
+                output = Output::Json(chunk_writer);
+            },
+            Format::Ndjson => {
+                output = Output::Ndjson(chunk_writer);
+            },
+            Format::Csv | Format::Tsv => {
+                output = Output::Delimited(format.csv_writer_to(chunk_writer));
+            }
+        }
+
+        return RowWriter {
+            format: format,
+            output: output,
+            items_written: 0,
+            header: None,
+            report: report
+        };
+    }
+
+    //gives the type of the items, whose flat form has the header row of a response without items
+    pub fn columns<T: Flatten>(&mut self) -> Result<()> where T::Flat: Default {
+        if !self.format.is_delimited() {
+            return Ok(());
+        }
+
+        //the header is the first line written along with an empty row
+        let mut wtr = self.format.csv_writer();
+        match wtr.serialize(T::Flat::default()) {
+            Ok(_) => {},
+            Err(error) => {
+                return Err(format!("{}",error).into());
+            }
+        }
+        let data;
+        match wtr.into_inner() {
+            Ok(value) => {
+                data = value;
+            },
+            Err(error) => {
+                return Err(format!("{}",error).into());
+            }
+        }
+        let header_length = data.iter().position(|byte| *byte == b'\n').map(|position| position + 1).unwrap_or(data.len());
+        self.header = Some(data[..header_length].to_vec());
+        return Ok(());
+    }
+
+    pub fn write<T: Serialize + Flatten>(&mut self, item: &T) -> Result<()> {
+        match self.output {
+            Output::Json(ref mut wtr) => {
+                if self.items_written == 0 {
+                    if self.report.is_some() {
+                        wtr.write_all(b"{\n\"results\": ")?;
+                    }
+                    wtr.write_all(b"[\n")?;
+                }else{
+                    wtr.write_all(b",\n")?;
+                }
+                match serde_json::to_writer_pretty(&mut *wtr,item) {
+                    Ok(_) => {},
+                    Err(error) => {
+                        return Err(format!("{}",error).into());
+                    }
+                }
+            },
+            Output::Ndjson(ref mut wtr) => {
+                match serde_json::to_writer(&mut *wtr,item) {
+                    Ok(_) => {},
+                    Err(error) => {
+                        return Err(format!("{}",error).into());
+                    }
+                }
+                wtr.write_all(b"\n")?;
+            },
+            Output::Delimited(ref mut wtr) => {
+                match wtr.serialize(item.flatten()) {
+                    Ok(_) => {},
+                    Err(error) => {
+                        return Err(format!("{}",error).into());
+                    }
+                }
+            }
+        }
+
+        self.items_written += 1;
+        return Ok(());
+    }
+
+    //closes the json array, and the object holding it along with the report, then sends what is left in the buffers
+    fn finish(self) -> Result<()> {
+        match self.output {
+            Output::Json(mut wtr) => {
+                if self.items_written == 0 {
+                    if self.report.is_some() {
+                        wtr.write_all(b"{\n\"results\": ")?;
+                    }
+                    wtr.write_all(b"[]")?;
+                }else{
+                    wtr.write_all(b"\n]")?;
+                }
+                match self.report {
+                    Some(ref report) => {
+                        for (name,value) in render::report_members(report)? {
+                            write!(wtr,",\n\"{name}\": ",name=name)?;
+                            write_json(&mut wtr,&value)?;
+                        }
+                        wtr.write_all(b"\n}")?;
+                    },
+                    None => {}
+                }
+                wtr.flush()?;
+            },
+            Output::Ndjson(mut wtr) => {
+                wtr.flush()?;
+            },
+            Output::Delimited(wtr) => {
+                match wtr.into_inner() {
+                    Ok(mut inner_wtr) => {
+                        if self.items_written == 0 {
+                            match self.header {
+                                Some(ref header) => {
+                                    inner_wtr.write_all(header)?;
+                                },
+                                None => {}
+                            }
+                        }
+                        inner_wtr.flush()?;
+                    },
+                    Err(error) => {
+                        return Err(format!("{}",error).into());
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+fn write_json<W: Write, T: Serialize>(wtr: &mut W, value: &T) -> Result<()> {
+    match serde_json::to_writer_pretty(wtr,value) {
+        Ok(_) => {
+            return Ok(());
+        },
+        Err(error) => {
+            return Err(format!("{}",error).into());
+        }
+    }
+}
+
+// Streams a response whose items are produced by write_items on a separate thread, so that they
+// are sent as soon as they are read from the database instead of being collected first. The
+// thread blocks while the client is not keeping up, and stops when the client goes away.
+//
+// The status line is sent before the first item, so a failure after that point can no longer be
+// rendered as a problem. The body is cut short instead, which the client sees as a truncated
+// chunked response, and the error is logged with the request id.
+pub fn response<F>(context: &problem::RequestContext, streams: &StreamPool, builder: &mut HttpResponseBuilder, format: Format, write_items: F) -> HttpResponse
+    where F: FnOnce(&mut RowWriter) -> Result<()> + Send + 'static
+{
+    return stream_rows(context,streams,builder,format,None,write_items);
+}
+
+// Streams the results of a batch along with the validation report of its rows. JSON holds the
+// report next to the results, while the other formats only hold the results and the counts of
+// the report are sent in the headers.
+pub fn batch_response<F>(context: &problem::RequestContext, streams: &StreamPool, builder: &mut HttpResponseBuilder, format: Format, report: ValidationReport, write_items: F) -> HttpResponse
+    where F: FnOnce(&mut RowWriter) -> Result<()> + Send + 'static
+{
+    render::report_headers(builder,&report);
+    return stream_rows(context,streams,builder,format,Some(report),write_items);
+}
+
+fn stream_rows<F>(context: &problem::RequestContext, streams: &StreamPool, builder: &mut HttpResponseBuilder, format: Format, report: Option<ValidationReport>, write_items: F) -> HttpResponse
+    where F: FnOnce(&mut RowWriter) -> Result<()> + Send + 'static
+{
+    let (sender, receiver) = mpsc::channel(CHUNK_BUFFER_SIZE);
+
+    let request_id = context.request_id.clone();
+    let error_sender = sender.clone();
+    let mut stream_items = Some(move || {
+        //a panic, like one reading an unexpected NULL, ends the body with an error as a failed query does,
+        //otherwise the closed channel would end it like a complete response
+        let result: Result<()>;
+        match panic::catch_unwind(panic::AssertUnwindSafe(move || -> Result<()> {
+            let mut row_writer = RowWriter::new(format,sender,report);
+            write_items(&mut row_writer)?;
+            return row_writer.finish();
+        })) {
+            Ok(value) => {
+                result = value;
+            },
+            Err(payload) => {
+                result = Err(format!("The response stopped unexpectedly - {message}",message=misc::panic_message(&payload)).into());
+            }
+        }
+
+        match result {
+            Ok(_) => {},
+            Err(error) => {
+                error!("{request_id} - streamed response aborted - {error}",request_id=request_id,error=error);
+                let _ = error_sender.wait().send(Err(error));
+            }
+        }
+    });
+
+    //nothing was sent yet, so a busy pool is still answered with a problem
+    let producer: Producer = Box::new(move || {
+        match stream_items.take() {
+            Some(stream_items) => {
+                stream_items();
+            },
+            None => {}
+        }
+    });
+    match streams.run(producer) {
+        Ok(_) => {},
+        Err(error) => {
+            return problem::response(context,&error);
+        }
+    }
+
+    let body = receiver
+        .map_err(|_error| error::ErrorInternalServerError("the response stream was closed"))
+        .and_then(|chunk_result| {
+            return chunk_result.map_err(|error| error::ErrorInternalServerError(format!("{}",error)));
+        });
+
+    return builder
+            .force_close()
+            .chunked()
+            .header(http::header::CONTENT_TYPE, format.content_type())
+            .streaming(body);
+}
@@ -0,0 +1,696 @@
+use actix_web::dev::Params;
+use csv;
+use errors::*;
+use models::{QuerySubstrate, RejectedRow};
+use serde_json;
+use std::str;
+
+//header names recognized for every field when the request does not name the columns, compared
+//after normalize_header
+const SUBSTRATE_AC_HEADERS: [&str; 6] = ["substrateac","accession","uniprotac","uniprot","protein","ac"];
+const SITE_RESIDUE_HEADERS: [&str; 4] = ["siteresidue","residue","aminoacid","aa"];
+const SITE_POSITION_HEADERS: [&str; 3] = ["siteposition","position","pos"];
+const SITE_HEADERS: [&str; 1] = ["site"];
+
+//a row of a batch body, or the reason it could not be read
+pub type InputRow = ::std::result::Result<QuerySubstrate,RejectedRow>;
+
+//a row of a site analysis body with its fold change, or the reason it could not be read
+pub type QuantifiedRow = ::std::result::Result<(QuerySubstrate,f64),RejectedRow>;
+
+//the formats a list of query substrates can be sent in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputFormat {
+    Json,
+    Csv,
+    Tsv,
+    Lines
+}
+
+/// Names of the columns holding each field of a query substrate in a CSV or TSV upload.
+///
+/// The site can be given either as a residue and a position column, or as a single column
+/// holding both, e.g. `S15`.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMapping {
+    pub substrate_ac: Option<String>,
+    pub site_residue: Option<String>,
+    pub site_position: Option<String>,
+    pub site: Option<String>
+}
+
+impl ColumnMapping {
+    //sets the column of a field named after a query parameter or form field, returns false for other names
+    fn set(&mut self, name: &str, column: &str) -> bool {
+        let column = Some(String::from(column.trim()));
+        match name {
+            "substrate_ac" => {
+                self.substrate_ac = column;
+            },
+            "site_residue" => {
+                self.site_residue = column;
+            },
+            "site_position" => {
+                self.site_position = column;
+            },
+            "site" => {
+                self.site = column;
+            },
+            _ => {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+//reads the column mapping from the query parameters named after the fields
+pub fn column_mapping(params: &Params) -> ColumnMapping {
+    let mut mapping = ColumnMapping::default();
+    for param in params.iter() {
+        mapping.set(&param.0,&param.1);
+    }
+    return mapping;
+}
+
+// Parses a batch request body into query substrates. The body is read according to its content
+// type: a JSON array of query substrates, CSV, TSV, the line format, or a multipart/form-data
+// upload holding one of them as a file. A body without a content type, or with any other content
+// type, is read as JSON, see body_format.
+//
+// A row that cannot be read is returned as a rejected row, so that the other rows can still be
+// looked up. Only a body that cannot be read at all is an error.
+pub fn parse_query_substrates(content_type: &str, mapping: &ColumnMapping, body: &[u8]) -> Result<Vec<InputRow>> {
+    let media_type = media_type(content_type);
+    if media_type == "multipart/form-data" {
+        return parse_multipart(content_type,mapping,body);
+    }
+
+    return parse_body(body_format(&media_type,body),mapping,body);
+}
+
+// The format of a body sent with the media type. Clients posting JSON do not always name it, like
+// curl sending application/x-www-form-urlencoded by default, so any other media type is read as
+// JSON, and so is a text/plain body holding a JSON array.
+fn body_format(media_type: &str, body: &[u8]) -> InputFormat {
+    match input_format(media_type) {
+        Some(InputFormat::Lines) => {
+            if is_json_array(body) {
+                return InputFormat::Json;
+            }else{
+                return InputFormat::Lines;
+            }
+        },
+        Some(format) => {
+            return format;
+        },
+        None => {
+            return InputFormat::Json;
+        }
+    }
+}
+
+//true when the body starts like a json array, the line format never starts with [
+fn is_json_array(body: &[u8]) -> bool {
+    return body.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'[');
+}
+
+fn input_format(media_type: &str) -> Option<InputFormat> {
+    match media_type {
+        "" | "application/json" => {
+            return Some(InputFormat::Json);
+        },
+        "text/csv" => {
+            return Some(InputFormat::Csv);
+        },
+        "text/tab-separated-values" => {
+            return Some(InputFormat::Tsv);
+        },
+        "text/plain" => {
+            return Some(InputFormat::Lines);
+        },
+        _ => {
+            return None;
+        }
+    }
+}
+
+fn parse_body(format: InputFormat, mapping: &ColumnMapping, body: &[u8]) -> Result<Vec<InputRow>> {
+    let body_str;
+    match str::from_utf8(body) {
+        Ok(value) => {
+            body_str = value;
+        },
+        Err(error) => {
+            return Err(ErrorKind::BadRequest(format!("The body is not valid UTF-8 - {}",error)).into());
+        }
+    }
+
+    match format {
+        InputFormat::Json => {
+            return parse_json(body_str);
+        },
+        InputFormat::Csv => {
+            return parse_delimited(body_str,b',',mapping);
+        },
+        InputFormat::Tsv => {
+            return parse_delimited(body_str,b'\t',mapping);
+        },
+        InputFormat::Lines => {
+            return parse_lines(body_str);
+        }
+    }
+}
+
+// Reads a JSON array of query substrates. A malformed body is a bad request and a body that is not
+// an array is unprocessable, while an item without the expected shape is a rejected row. The site
+// position may be given as a number too.
+fn parse_json(body_str: &str) -> Result<Vec<InputRow>> {
+    let items = json_items(body_str)?;
+
+    let mut rows: Vec<InputRow> = Vec::new();
+    for (index,item) in items.iter().enumerate() {
+        rows.push(json_query_substrate(index,item));
+    }
+
+    return Ok(rows);
+}
+
+// Reads the body of a site analysis, a JSON array of query substrates each with a fold_change
+// number, e.g. {"substrate_ac": "Q15796", "site_residue": "K", "site_position": 19, "fold_change": 1.5}.
+// A fold change may be given as a string too. An item without a fold change is a rejected row.
+pub fn parse_quantified_sites(content_type: &str, body: &[u8]) -> Result<Vec<QuantifiedRow>> {
+    let media_type = media_type(content_type);
+    if body_format(&media_type,body) != InputFormat::Json {
+        return Err(ErrorKind::UnsupportedMediaType(format!("Unsupported content type '{media_type}', expected application/json",media_type=media_type)).into());
+    }
+
+    let body_str;
+    match str::from_utf8(body) {
+        Ok(value) => {
+            body_str = value;
+        },
+        Err(error) => {
+            return Err(ErrorKind::BadRequest(format!("The body is not valid UTF-8 - {}",error)).into());
+        }
+    }
+
+    let items = json_items(body_str)?;
+
+    let mut rows: Vec<QuantifiedRow> = Vec::new();
+    for (index,item) in items.iter().enumerate() {
+        match json_query_substrate(index,item) {
+            Ok(query_substrate) => {
+                let fold_change = json_field(item,"fold_change").and_then(|value| value.trim().parse::<f64>().ok());
+                match fold_change {
+                    Some(value) if value.is_finite() => {
+                        rows.push(Ok((query_substrate,value)));
+                    },
+                    _ => {
+                        let reason = String::from("fold_change missing or not a number");
+                        rows.push(Err(rejected_row(index,Some(query_substrate.substrate_ac),Some(query_substrate.site_residue),Some(query_substrate.site_position),reason)));
+                    }
+                }
+            },
+            Err(rejected_row) => {
+                rows.push(Err(rejected_row));
+            }
+        }
+    }
+
+    return Ok(rows);
+}
+
+// Reads a list of identifiers, either a JSON array of strings or text with the identifiers separated
+// by spaces, tabs, new lines, commas or semicolons. Lines starting with # are skipped in text. The
+// identifiers are trimmed and kept in their order, duplicates included.
+pub fn parse_id_list(content_type: &str, body: &[u8]) -> Result<Vec<String>> {
+    let media_type = media_type(content_type);
+    let format = body_format(&media_type,body);
+    if format != InputFormat::Json && format != InputFormat::Lines {
+        return Err(ErrorKind::UnsupportedMediaType(format!("Unsupported content type '{media_type}', expected application/json or text/plain",media_type=media_type)).into());
+    }
+
+    let body_str;
+    match str::from_utf8(body) {
+        Ok(value) => {
+            body_str = value;
+        },
+        Err(error) => {
+            return Err(ErrorKind::BadRequest(format!("The body is not valid UTF-8 - {}",error)).into());
+        }
+    }
+
+    let mut ids: Vec<String> = Vec::new();
+    if format == InputFormat::Json {
+        for (index,item) in json_items(body_str)?.iter().enumerate() {
+            match *item {
+                serde_json::Value::String(ref value) => {
+                    ids.push(String::from(value.trim()));
+                },
+                _ => {
+                    return Err(ErrorKind::Unprocessable(format!("Item {index} of the list is not a string",index=index)).into());
+                }
+            }
+        }
+    }else{
+        for line in body_str.lines() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            for id in line.split(|c: char| c.is_whitespace() || c == ',' || c == ';').filter(|id| !id.is_empty()) {
+                ids.push(String::from(id));
+            }
+        }
+    }
+    return Ok(ids);
+}
+
+//the items of a json array, a malformed body is a bad request and any other value is unprocessable
+fn json_items(body_str: &str) -> Result<Vec<serde_json::Value>> {
+    match serde_json::from_str(body_str) {
+        Ok(value) => {
+            return Ok(value);
+        },
+        Err(error) => {
+            if error.is_data() {
+                return Err(ErrorKind::Unprocessable(format!("{}",error)).into());
+            }else{
+                return Err(ErrorKind::BadRequest(format!("{}",error)).into());
+            }
+        }
+    }
+}
+
+fn json_query_substrate(index: usize, item: &serde_json::Value) -> InputRow {
+    let substrate_ac = json_field(item,"substrate_ac");
+    let site_residue = json_field(item,"site_residue");
+    let site_position = json_field(item,"site_position");
+
+    let mut missing_fields: Vec<&str> = Vec::new();
+    if substrate_ac.is_none() {
+        missing_fields.push("substrate_ac");
+    }
+    if site_residue.is_none() {
+        missing_fields.push("site_residue");
+    }
+    if site_position.is_none() {
+        missing_fields.push("site_position");
+    }
+
+    if missing_fields.is_empty() {
+        return Ok(QuerySubstrate {
+            substrate_ac: substrate_ac.unwrap(),
+            site_residue: site_residue.unwrap(),
+            site_position: site_position.unwrap()
+        });
+    }else{
+        let reason = format!("{fields} missing or not a string",fields=missing_fields.join(", "));
+        return Err(rejected_row(index,substrate_ac,site_residue,site_position,reason));
+    }
+}
+
+//a string or number field of a json object
+fn json_field(item: &serde_json::Value, name: &str) -> Option<String> {
+    match item.get(name) {
+        Some(&serde_json::Value::String(ref value)) => {
+            return Some(value.clone());
+        },
+        Some(&serde_json::Value::Number(ref value)) => {
+            return Some(format!("{}",value));
+        },
+        _ => {
+            return None;
+        }
+    }
+}
+
+//reads CSV or TSV with a header row, finding the columns through the mapping or the known header names
+fn parse_delimited(body_str: &str, delimiter: u8, mapping: &ColumnMapping) -> Result<Vec<InputRow>> {
+    let mut rdr = csv::ReaderBuilder::new()
+                    .delimiter(delimiter)
+                    .flexible(true)
+                    .from_reader(body_str.as_bytes());
+
+    let headers: Vec<String>;
+    match rdr.headers() {
+        Ok(value) => {
+            headers = value.iter().map(|header| String::from(header.trim())).collect();
+        },
+        Err(error) => {
+            return Err(ErrorKind::BadRequest(format!("Could not read the header row - {}",error)).into());
+        }
+    }
+
+    let site_residue_index = find_column(&headers,&mapping.site_residue,&SITE_RESIDUE_HEADERS)?;
+    let site_position_index = find_column(&headers,&mapping.site_position,&SITE_POSITION_HEADERS)?;
+    let site_index = find_column(&headers,&mapping.site,&SITE_HEADERS)?;
+
+    let substrate_ac_index;
+    match find_column(&headers,&mapping.substrate_ac,&SUBSTRATE_AC_HEADERS)? {
+        Some(index) => {
+            substrate_ac_index = index;
+        },
+        None => {
+            return Err(missing_columns(&headers));
+        }
+    }
+
+    let has_residue_and_position = site_residue_index.is_some() && site_position_index.is_some();
+    if !has_residue_and_position && site_index.is_none() {
+        return Err(missing_columns(&headers));
+    }
+
+    let mut rows: Vec<InputRow> = Vec::new();
+    for record_result in rdr.records() {
+        let index = rows.len();
+
+        let record;
+        match record_result {
+            Ok(value) => {
+                record = value;
+            },
+            Err(error) => {
+                rows.push(Err(rejected_row(index,None,None,None,format!("{}",error))));
+                continue;
+            }
+        }
+
+        //skip blank rows
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let field = |column_index: usize| -> String {
+            return String::from(record.get(column_index).unwrap_or("").trim());
+        };
+
+        let substrate_ac = field(substrate_ac_index);
+        if has_residue_and_position {
+            rows.push(Ok(QuerySubstrate {
+                substrate_ac: substrate_ac,
+                site_residue: field(site_residue_index.unwrap()),
+                site_position: field(site_position_index.unwrap())
+            }));
+        }else{
+            let site = field(site_index.unwrap());
+            match split_site(&site) {
+                Some((site_residue,site_position)) => {
+                    rows.push(Ok(QuerySubstrate {
+                        substrate_ac: substrate_ac,
+                        site_residue: site_residue,
+                        site_position: site_position
+                    }));
+                },
+                None => {
+                    let reason = format!("'{site}' is not a site like S15",site=site);
+                    rows.push(Err(rejected_row(index,Some(substrate_ac),None,None,reason)));
+                }
+            }
+        }
+    }
+
+    return Ok(rows);
+}
+
+// Index of the column holding a field. A column named in the mapping must exist, otherwise the
+// first header matching one of the known names is used.
+fn find_column(headers: &Vec<String>, mapped_column: &Option<String>, known_headers: &[&str]) -> Result<Option<usize>> {
+    match *mapped_column {
+        Some(ref column) => {
+            match headers.iter().position(|header| header == column) {
+                Some(index) => {
+                    return Ok(Some(index));
+                },
+                None => {
+                    return Err(ErrorKind::BadRequest(format!("No column is named '{column}', the header row is {headers}",column=column,headers=headers.join(", "))).into());
+                }
+            }
+        },
+        None => {
+            for (index,header) in headers.iter().enumerate() {
+                if known_headers.contains(&normalize_header(header).as_str()) {
+                    return Ok(Some(index));
+                }
+            }
+            return Ok(None);
+        }
+    }
+}
+
+fn missing_columns(headers: &Vec<String>) -> Error {
+    let message = format!("The header row must name the substrate accession and either the site residue and position or the site, found {headers}. The columns can be named with the substrate_ac, site_residue, site_position and site parameters.",headers=headers.join(", "));
+    return ErrorKind::BadRequest(message).into();
+}
+
+//lower case without spaces, underscores or dashes, so that "Site Position" matches siteposition
+fn normalize_header(header: &str) -> String {
+    return header.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+}
+
+// Reads the line format, one site per line as an accession followed by the site, e.g.
+// "P12345 S15". The residue and position may also be separate, as in "P12345 S 15". Fields are
+// separated by spaces, tabs, commas or semicolons. Blank lines and lines starting with # are skipped.
+fn parse_lines(body_str: &str) -> Result<Vec<InputRow>> {
+    let mut rows: Vec<InputRow> = Vec::new();
+    for line in body_str.lines() {
+        let index = rows.len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == ',' || c == ';').filter(|field| !field.is_empty()).collect();
+        let site;
+        if fields.len() == 2 {
+            site = split_site(fields[1]);
+        }else if fields.len() == 3 {
+            site = split_site(&format!("{residue}{position}",residue=fields[1],position=fields[2]));
+        }else{
+            site = None;
+        }
+
+        match site {
+            Some((site_residue,site_position)) => {
+                rows.push(Ok(QuerySubstrate {
+                    substrate_ac: String::from(fields[0]),
+                    site_residue: site_residue,
+                    site_position: site_position
+                }));
+            },
+            None => {
+                let reason = format!("expected an accession and a site like 'P12345 S15', got '{line}'",line=line);
+                rows.push(Err(rejected_row(index,fields.get(0).map(|field| String::from(*field)),None,None,reason)));
+            }
+        }
+    }
+    return Ok(rows);
+}
+
+fn rejected_row(index: usize, substrate_ac: Option<String>, site_residue: Option<String>, site_position: Option<String>, reason: String) -> RejectedRow {
+    return RejectedRow {
+        index: index,
+        substrate_ac: substrate_ac,
+        site_residue: site_residue,
+        site_position: site_position,
+        reason: reason
+    };
+}
+
+//splits a site like S15 into its upper cased residue and its position
+fn split_site(site: &str) -> Option<(String,String)> {
+    let site = site.trim();
+    let mut chars = site.chars();
+    match chars.next() {
+        Some(residue) if residue.is_ascii_alphabetic() => {
+            let position = chars.as_str();
+            if !position.is_empty() && position.chars().all(|c| c.is_ascii_digit()) {
+                return Some((residue.to_ascii_uppercase().to_string(),String::from(position)));
+            }
+            return None;
+        },
+        _ => {
+            return None;
+        }
+    }
+}
+
+// Reads a multipart/form-data upload. The file part holds the query substrates in the format given
+// by its content type, or by its file name extension when the content type is missing or generic.
+// Form fields named substrate_ac, site_residue, site_position or site name the columns of a CSV or
+// TSV file, and win over the query parameters.
+fn parse_multipart(content_type: &str, mapping: &ColumnMapping, body: &[u8]) -> Result<Vec<InputRow>> {
+    let boundary;
+    match content_type_param(content_type,"boundary") {
+        Some(value) => {
+            boundary = value;
+        },
+        None => {
+            return Err(ErrorKind::BadRequest(String::from("The multipart/form-data content type has no boundary")).into());
+        }
+    }
+
+    let mut part_mapping = mapping.clone();
+    let mut file: Option<(InputFormat,&[u8])> = None;
+    for part in multipart_parts(body,&boundary)? {
+        match part.filename {
+            Some(ref filename) => {
+                if file.is_some() {
+                    return Err(ErrorKind::BadRequest(String::from("The multipart body must hold a single file")).into());
+                }
+                let format = part_format(&part.content_type,filename)?;
+                file = Some((format,part.content));
+            },
+            None => {
+                match str::from_utf8(part.content) {
+                    Ok(value) => {
+                        part_mapping.set(&part.name,value);
+                    },
+                    Err(_error) => {
+                        return Err(ErrorKind::BadRequest(format!("The form field {name} is not valid UTF-8",name=part.name)).into());
+                    }
+                }
+            }
+        }
+    }
+
+    match file {
+        Some((format,content)) => {
+            return parse_body(format,&part_mapping,content);
+        },
+        None => {
+            return Err(ErrorKind::BadRequest(String::from("The multipart body holds no file")).into());
+        }
+    }
+}
+
+//format of an uploaded file
+fn part_format(content_type: &Option<String>, filename: &str) -> Result<InputFormat> {
+    match *content_type {
+        Some(ref value) => {
+            let media_type = media_type(value);
+            if media_type != "application/octet-stream" {
+                match input_format(&media_type) {
+                    Some(format) => {
+                        return Ok(format);
+                    },
+                    None => {}
+                }
+            }
+        },
+        None => {}
+    }
+
+    let filename_lower = filename.to_lowercase();
+    if filename_lower.ends_with(".csv") {
+        return Ok(InputFormat::Csv);
+    }else if filename_lower.ends_with(".tsv") || filename_lower.ends_with(".tab") {
+        return Ok(InputFormat::Tsv);
+    }else if filename_lower.ends_with(".json") {
+        return Ok(InputFormat::Json);
+    }else if filename_lower.ends_with(".txt") || !filename_lower.contains('.') {
+        return Ok(InputFormat::Lines);
+    }
+
+    return Err(ErrorKind::UnsupportedMediaType(format!("Unsupported file {filename}, expected a .csv, .tsv, .txt or .json file",filename=filename)).into());
+}
+
+struct Part<'a> {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    content: &'a [u8]
+}
+
+//splits a multipart body into its parts, as described in RFC 7578
+fn multipart_parts<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>> {
+    let malformed = || -> Error {
+        return ErrorKind::BadRequest(String::from("Malformed multipart/form-data body")).into();
+    };
+
+    let delimiter = format!("--{boundary}",boundary=boundary).into_bytes();
+    let mut parts: Vec<Part> = Vec::new();
+
+    //skip the preamble
+    let mut rest;
+    match find(body,&delimiter) {
+        Some(index) => {
+            rest = &body[index + delimiter.len()..];
+        },
+        None => {
+            return Err(malformed());
+        }
+    }
+
+    loop {
+        //the last delimiter is followed by --
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        if !rest.starts_with(b"\r\n") {
+            return Err(malformed());
+        }
+        rest = &rest[2..];
+
+        //headers and content are separated by a blank line
+        let headers_end = find(rest,b"\r\n\r\n").ok_or_else(&malformed)?;
+        let headers_str = str::from_utf8(&rest[..headers_end]).map_err(|_error| malformed())?;
+        rest = &rest[headers_end + 4..];
+
+        //the content ends with the line break before the next delimiter
+        let mut content_delimiter = b"\r\n".to_vec();
+        content_delimiter.extend_from_slice(&delimiter);
+        let content_end = find(rest,&content_delimiter).ok_or_else(&malformed)?;
+        let content = &rest[..content_end];
+        rest = &rest[content_end + content_delimiter.len()..];
+
+        let mut name = String::new();
+        let mut filename: Option<String> = None;
+        let mut content_type: Option<String> = None;
+        for header_line in headers_str.split("\r\n") {
+            let mut header_parts = header_line.splitn(2,':');
+            let header_name = header_parts.next().unwrap_or("").trim().to_lowercase();
+            let header_value = header_parts.next().unwrap_or("").trim();
+            if header_name == "content-disposition" {
+                name = content_type_param(header_value,"name").unwrap_or(String::new());
+                filename = content_type_param(header_value,"filename");
+            }else if header_name == "content-type" {
+                content_type = Some(String::from(header_value));
+            }
+        }
+
+        parts.push(Part {
+            name: name,
+            filename: filename,
+            content_type: content_type,
+            content: content
+        });
+    }
+}
+
+//index of the first occurrence of needle in haystack
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+    return haystack.windows(needle.len()).position(|window| window == needle);
+}
+
+//lower cased media type of a content type, without its parameters
+fn media_type(content_type: &str) -> String {
+    return content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+}
+
+//value of a parameter of a header like Content-Type or Content-Disposition, without its quotes
+fn content_type_param(header_value: &str, name: &str) -> Option<String> {
+    for param in header_value.split(';').skip(1) {
+        let mut param_parts = param.splitn(2,'=');
+        let param_name = param_parts.next().unwrap_or("").trim().to_lowercase();
+        let param_value = param_parts.next().unwrap_or("").trim().trim_matches('"');
+        if param_name == name {
+            return Some(String::from(param_value));
+        }
+    }
+    return None;
+}
@@ -0,0 +1,514 @@
+use database::Backend;
+use errors::*;
+use misc;
+use models::{QuerySubstrate, RejectedRow, RowWarning, Sequence, SiteCheck, SiteStatus, SiteSuggestion, ValidationReport};
+use std::collections::{HashMap, HashSet};
+use upload::InputRow;
+
+//residues the batch lookups are expected to find sites on
+const PTM_RESIDUES: [&str; 5] = ["S","T","Y","K","R"];
+
+//residues taken on each side of a site to find it in another isoform
+const SITE_WINDOW: usize = 7;
+
+// Checks every row of a batch and keeps the ones worth looking up. A row is rejected when its
+// accession is empty or not a known entry, its residue is not a single amino acid letter or its
+// position is not a positive integer. A residue other than S, T, Y, K or R and a repeated row are
+// only warned about, and a repeated row is looked up once. Every row looked up is then checked
+// against the stored sequences, see check_sites.
+//
+// Rows are identified by their index among the data rows of the body, counted from 0. In strict
+// mode a single rejected row fails the whole request with ErrorKind::RejectedRows.
+pub fn validate(conn: &Backend, rows: Vec<InputRow>, strict: bool) -> Result<(Vec<QuerySubstrate>,ValidationReport)> {
+    let mut report = ValidationReport::default();
+
+    //check the shape of every row
+    let mut candidates: Vec<(usize,QuerySubstrate)> = Vec::new();
+    for (index,row) in rows.into_iter().enumerate() {
+        match row {
+            Ok(query_substrate) => {
+                match check_row(index,query_substrate,&mut report) {
+                    Some(checked_substrate) => {
+                        candidates.push((index,checked_substrate));
+                    },
+                    None => {}
+                }
+            },
+            Err(rejected_row) => {
+                report.rejected_rows.push(rejected_row);
+            }
+        }
+    }
+
+    //look up the accessions and their base accessions at once
+    let mut ids: Vec<String> = Vec::new();
+    for &(_,ref query_substrate) in &candidates {
+        ids.push(query_substrate.substrate_ac.clone());
+        ids.push(misc::base_accession(&query_substrate.substrate_ac));
+    }
+    ids.sort();
+    ids.dedup();
+    let known_entries: HashSet<String> = conn.get_known_entries(&ids)?.into_iter().collect();
+
+    let mut checked_substrates: Vec<(usize,QuerySubstrate)> = Vec::new();
+    let mut first_rows: HashMap<(String,String,String),usize> = HashMap::new();
+    for (index,query_substrate) in candidates {
+        let is_known = known_entries.contains(&query_substrate.substrate_ac)
+                       || known_entries.contains(&misc::base_accession(&query_substrate.substrate_ac));
+        if !is_known {
+            let reason = format!("{substrate_ac} is not a known entry",substrate_ac=query_substrate.substrate_ac);
+            report.rejected_rows.push(rejected(index,&query_substrate,reason));
+            continue;
+        }
+
+        let key = (query_substrate.substrate_ac.clone(),query_substrate.site_residue.clone(),query_substrate.site_position.clone());
+        match first_rows.get(&key) {
+            Some(first_index) => {
+                report.warnings.push(RowWarning {
+                    index: index,
+                    message: format!("Duplicate of row {first_index}, looked up once",first_index=first_index)
+                });
+                continue;
+            },
+            None => {}
+        }
+        first_rows.insert(key,index);
+        checked_substrates.push((index,query_substrate));
+    }
+
+    report.site_checks = check_sites(conn,&checked_substrates)?;
+    let query_substrates: Vec<QuerySubstrate> = checked_substrates.into_iter().map(|(_,query_substrate)| query_substrate).collect();
+
+    report.rejected_rows.sort_by_key(|rejected_row| rejected_row.index);
+    report.warnings.sort_by_key(|warning| warning.index);
+
+    if strict && !report.rejected_rows.is_empty() {
+        return Err(ErrorKind::RejectedRows(report.rejected_rows).into());
+    }
+
+    return Ok((query_substrates,report));
+}
+
+// Checks a list of entry ids, like the enzymes of a reverse batch lookup, and keeps the ones worth
+// looking up. An empty id or an id that is not a known entry, neither itself nor through its base
+// accession, is rejected, and a repeated id is looked up once with a warning. In strict mode a single
+// rejected id fails the whole request with ErrorKind::RejectedRows.
+pub fn validate_ids(conn: &Backend, ids: Vec<String>, strict: bool) -> Result<(Vec<String>,ValidationReport)> {
+    let mut lookup_ids: Vec<String> = Vec::new();
+    for id in &ids {
+        lookup_ids.push(id.clone());
+        lookup_ids.push(misc::base_accession(id));
+    }
+    lookup_ids.sort();
+    lookup_ids.dedup();
+    let known_entries: HashSet<String> = conn.get_known_entries(&lookup_ids)?.into_iter().collect();
+
+    return check_ids(ids,strict,&|id| known_entries.contains(id) || known_entries.contains(&misc::base_accession(id)));
+}
+
+// Checks a list of ids against is_known, as validate_ids does, for lookups that find the known ids
+// themselves. The ids kept are in the order of the list.
+pub fn check_ids(ids: Vec<String>, strict: bool, is_known: &Fn(&str) -> bool) -> Result<(Vec<String>,ValidationReport)> {
+    return check_list(ids,strict,&|id| {
+        if is_known(id) {
+            return None;
+        }
+        return Some(format!("{id} is not a known entry",id=id));
+    });
+}
+
+// Checks a list of PMIDs like check_ids, a PMID is rejected unless it is made of digits only.
+pub fn check_pmids(pmids: Vec<String>, strict: bool) -> Result<(Vec<String>,ValidationReport)> {
+    return check_list(pmids,strict,&|pmid| {
+        if is_pmid(pmid) {
+            return None;
+        }
+        return Some(format!("{pmid} is not a PMID",pmid=pmid));
+    });
+}
+
+//true when the value is made of digits only, as PMIDs are
+pub fn is_pmid(value: &str) -> bool {
+    return !value.is_empty() && value.chars().all(|c| c.is_ascii_digit());
+}
+
+//rejects the empty items and the items rejection gives a reason for, and keeps repeated items once
+fn check_list(items: Vec<String>, strict: bool, rejection: &Fn(&str) -> Option<String>) -> Result<(Vec<String>,ValidationReport)> {
+    let mut report = ValidationReport::default();
+
+    let mut checked_items: Vec<String> = Vec::new();
+    let mut first_rows: HashMap<String,usize> = HashMap::new();
+    for (index,item) in items.into_iter().enumerate() {
+        if item.is_empty() {
+            report.rejected_rows.push(rejected_id(index,&item,String::from("the id is empty")));
+            continue;
+        }
+
+        match first_rows.get(&item) {
+            Some(first_index) => {
+                report.warnings.push(RowWarning {
+                    index: index,
+                    message: format!("Duplicate of row {first_index}, looked up once",first_index=first_index)
+                });
+                continue;
+            },
+            None => {}
+        }
+        first_rows.insert(item.clone(),index);
+
+        match rejection(&item) {
+            Some(reason) => {
+                report.rejected_rows.push(rejected_id(index,&item,reason));
+                continue;
+            },
+            None => {}
+        }
+        checked_items.push(item);
+    }
+
+    if strict && !report.rejected_rows.is_empty() {
+        return Err(ErrorKind::RejectedRows(report.rejected_rows).into());
+    }
+
+    return Ok((checked_items,report));
+}
+
+// Reads a site written as its residue followed by its position, e.g. S15, into the residue upper
+// cased and the position. Anything else is a bad request.
+pub fn parse_site(site: &str) -> Result<(String,i64)> {
+    let site = site.trim();
+    let mut chars = site.chars();
+    match chars.next() {
+        Some(residue) if residue.is_ascii_alphabetic() => {
+            match chars.as_str().parse::<u32>() {
+                Ok(position) if position > 0 => {
+                    return Ok((residue.to_ascii_uppercase().to_string(),position as i64));
+                },
+                _ => {}
+            }
+        },
+        _ => {}
+    }
+    return Err(ErrorKind::BadRequest(format!("Site '{site}' is not a residue followed by a positive position, e.g. S15",site=site)).into());
+}
+
+//checks a single site against the stored sequences like a row of a batch, see check_sites
+pub fn check_site(conn: &Backend, query_substrate: QuerySubstrate) -> Result<SiteCheck> {
+    let mut site_checks = check_sites(conn,&vec![(0,query_substrate)])?;
+    match site_checks.pop() {
+        Some(site_check) => {
+            return Ok(site_check);
+        },
+        None => {
+            return Err("No site check for the site".into());
+        }
+    }
+}
+
+//a rejected id of a list, the id is reported as the substrate_ac of the row
+pub fn rejected_id(index: usize, id: &str, reason: String) -> RejectedRow {
+    return RejectedRow {
+        index: index,
+        substrate_ac: Some(String::from(id)),
+        site_residue: None,
+        site_position: None,
+        reason: reason
+    };
+}
+
+//trims the fields of a row and checks them, returns None when the row is rejected
+fn check_row(index: usize, query_substrate: QuerySubstrate, report: &mut ValidationReport) -> Option<QuerySubstrate> {
+    let query_substrate = QuerySubstrate {
+        substrate_ac: String::from(query_substrate.substrate_ac.trim()),
+        site_residue: query_substrate.site_residue.trim().to_uppercase(),
+        site_position: String::from(query_substrate.site_position.trim())
+    };
+
+    let mut reasons: Vec<String> = Vec::new();
+    if query_substrate.substrate_ac.is_empty() {
+        reasons.push(String::from("substrate_ac is empty"));
+    }
+
+    let is_residue = query_substrate.site_residue.len() == 1
+                     && query_substrate.site_residue.chars().all(|c| c.is_ascii_alphabetic());
+    if !is_residue {
+        reasons.push(format!("site_residue '{residue}' is not a single amino acid letter",residue=query_substrate.site_residue));
+    }
+
+    match query_substrate.site_position.parse::<u32>() {
+        Ok(position) if position > 0 => {},
+        _ => {
+            reasons.push(format!("site_position '{position}' is not a positive integer",position=query_substrate.site_position));
+        }
+    }
+
+    if !reasons.is_empty() {
+        report.rejected_rows.push(rejected(index,&query_substrate,reasons.join(", ")));
+        return None;
+    }
+
+    if !PTM_RESIDUES.contains(&query_substrate.site_residue.as_str()) {
+        report.warnings.push(RowWarning {
+            index: index,
+            message: format!("Residue {residue} is not one of {residues}, few sites are expected to match",
+                             residue=query_substrate.site_residue,
+                             residues=PTM_RESIDUES.join(", "))
+        });
+    }
+
+    return Some(query_substrate);
+}
+
+fn rejected(index: usize, query_substrate: &QuerySubstrate, reason: String) -> RejectedRow {
+    return RejectedRow {
+        index: index,
+        substrate_ac: Some(query_substrate.substrate_ac.clone()),
+        site_residue: Some(query_substrate.site_residue.clone()),
+        site_position: Some(query_substrate.site_position.clone()),
+        reason: reason
+    };
+}
+
+// Compares the residue of every row with the residue at its position in the stored sequence of its
+// accession. The sequences of all the entries are read at once, along with the sequences of their
+// isoforms, in one query for every BATCH_QUERY_SIZE entries.
+//
+// When the residue does not match, the isoforms having the residue at that position are suggested,
+// as the position may be numbered after one of them. The site is then looked up in the sequence of
+// the accession through the residues around it, and the position found there is suggested too.
+fn check_sites(conn: &Backend, query_substrates: &Vec<(usize,QuerySubstrate)>) -> Result<Vec<SiteCheck>> {
+    let mut base_accessions: Vec<String> = Vec::new();
+    let mut seen_accessions: HashSet<String> = HashSet::new();
+    for &(_,ref query_substrate) in query_substrates {
+        let base_accession = misc::base_accession(&query_substrate.substrate_ac);
+        if seen_accessions.insert(base_accession.clone()) {
+            base_accessions.push(base_accession);
+        }
+    }
+    let entry_sequences = group_entry_sequences(&base_accessions,conn.get_entry_sequences(&base_accessions)?);
+
+    let mut site_checks: Vec<SiteCheck> = Vec::new();
+    for &(index,ref query_substrate) in query_substrates {
+        let sequences = &entry_sequences[&misc::base_accession(&query_substrate.substrate_ac)];
+
+        //positions were checked to be positive integers already
+        let position = query_substrate.site_position.parse::<usize>().unwrap_or(0);
+        let residue = query_substrate.site_residue.as_str();
+
+        let status;
+        let mut sequence_residue: Option<String> = None;
+        match sequences.iter().find(|sequence| sequence.id == query_substrate.substrate_ac) {
+            Some(sequence) => {
+                match residue_at(&sequence.sequence,position) {
+                    Some(value) => {
+                        if value == residue {
+                            status = SiteStatus::Match;
+                        }else{
+                            status = SiteStatus::Mismatch;
+                        }
+                        sequence_residue = Some(String::from(value));
+                    },
+                    None => {
+                        status = SiteStatus::OutOfRange;
+                    }
+                }
+            },
+            None => {
+                status = SiteStatus::NoSequence;
+            }
+        }
+
+        let mut suggestions: Vec<SiteSuggestion> = Vec::new();
+        if status != SiteStatus::Match {
+            suggestions = suggest_sites(&query_substrate.substrate_ac,residue,position,sequences);
+        }
+
+        site_checks.push(SiteCheck {
+            index: index,
+            substrate_ac: query_substrate.substrate_ac.clone(),
+            site_residue: query_substrate.site_residue.clone(),
+            site_position: query_substrate.site_position.clone(),
+            status: status,
+            sequence_residue: sequence_residue,
+            suggestions: suggestions
+        });
+    }
+
+    return Ok(site_checks);
+}
+
+//the sequences of every entry and of its isoforms, by entry accession
+fn group_entry_sequences(base_accessions: &Vec<String>, sequences: Vec<Sequence>) -> HashMap<String,Vec<Sequence>> {
+    let mut entry_sequences: HashMap<String,Vec<Sequence>> = HashMap::new();
+    for base_accession in base_accessions {
+        entry_sequences.insert(base_accession.clone(),Vec::new());
+    }
+
+    for sequence in sequences {
+        //an isoform, like Q15796-2, belongs to the entry before its last dash
+        let entry_accession;
+        if entry_sequences.contains_key(&sequence.id) {
+            entry_accession = sequence.id.clone();
+        }else{
+            match sequence.id.rfind('-') {
+                Some(index) => {
+                    entry_accession = String::from(&sequence.id[..index]);
+                },
+                None => {
+                    continue;
+                }
+            }
+        }
+
+        match entry_sequences.get_mut(&entry_accession) {
+            Some(value) => {
+                value.push(sequence);
+            },
+            None => {}
+        }
+    }
+    return entry_sequences;
+}
+
+//the residue at a position counted from 1, None when the position is past the end of the sequence
+fn residue_at(sequence: &str, position: usize) -> Option<&str> {
+    if position == 0 || position > sequence.len() {
+        return None;
+    }
+    return sequence.get(position - 1..position);
+}
+
+fn suggest_sites(substrate_ac: &str, residue: &str, position: usize, sequences: &Vec<Sequence>) -> Vec<SiteSuggestion> {
+    let target_sequence = sequences.iter().find(|sequence| sequence.id == substrate_ac);
+
+    let mut suggestions: Vec<SiteSuggestion> = Vec::new();
+    for sequence in sequences {
+        if sequence.id == substrate_ac || residue_at(&sequence.sequence,position) != Some(residue) {
+            continue;
+        }
+
+        let isoform_suggestion = SiteSuggestion {
+            substrate_ac: sequence.id.clone(),
+            site_position: position
+        };
+        if !suggestions.contains(&isoform_suggestion) {
+            suggestions.push(isoform_suggestion);
+        }
+
+        //the same site numbered after the sequence of the accession
+        match target_sequence {
+            Some(target) => {
+                match map_position(&sequence.sequence,position,&target.sequence) {
+                    Some(target_position) => {
+                        let target_suggestion = SiteSuggestion {
+                            substrate_ac: String::from(substrate_ac),
+                            site_position: target_position
+                        };
+                        if target_position != position && !suggestions.contains(&target_suggestion) {
+                            suggestions.push(target_suggestion);
+                        }
+                    },
+                    None => {}
+                }
+            },
+            None => {}
+        }
+    }
+    return suggestions;
+}
+
+//position of a site of one sequence in another, found through the residues around it when they occur only once there
+fn map_position(from_sequence: &str, position: usize, to_sequence: &str) -> Option<usize> {
+    let start = (position - 1).saturating_sub(SITE_WINDOW);
+    let end = (position + SITE_WINDOW).min(from_sequence.len());
+    let window;
+    match from_sequence.get(start..end) {
+        Some(value) => {
+            window = value;
+        },
+        None => {
+            return None;
+        }
+    }
+
+    let matches: Vec<usize> = to_sequence.match_indices(window).map(|(match_index,_)| match_index).collect();
+    if matches.len() != 1 {
+        return None;
+    }
+    return Some(matches[0] + (position - 1 - start) + 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use database::{BATCH_QUERY_SIZE, MyRow};
+    use query_builder::Dialect;
+    use sql_backend::{Connector, Param};
+    use sqlite_backend::SqliteDialect;
+
+    //a connection finding no rows, which keeps every query it is asked to run
+    struct RecordingConnector {
+        queries: RefCell<Vec<String>>
+    }
+
+    impl Connector for RecordingConnector {
+        fn dialect(&self) -> &Dialect {
+            return &SqliteDialect;
+        }
+
+        fn ping(&self) -> Result<()> {
+            return Ok(());
+        }
+
+        fn query_rows(&self, query_str: &str, _params: &[Param], _handle_row: &mut FnMut(&MyRow) -> Result<bool>) -> Result<()> {
+            self.queries.borrow_mut().push(String::from(query_str));
+            return Ok(());
+        }
+    }
+
+    fn sequence(id: &str) -> Sequence {
+        return Sequence {
+            id: String::from(id),
+            sequence: String::from("MSSILPFTPPVVK")
+        };
+    }
+
+    #[test]
+    fn sequences_are_read_once_for_every_batch() {
+        let entry_count = 1000;
+        let mut query_substrates: Vec<(usize,QuerySubstrate)> = Vec::new();
+        for index in 0..entry_count {
+            for substrate_ac in vec![format!("P{index:05}",index=index),format!("P{index:05}-2",index=index)] {
+                let query_substrate = QuerySubstrate {
+                    substrate_ac: substrate_ac,
+                    site_residue: String::from("S"),
+                    site_position: String::from("10")
+                };
+                let row_index = query_substrates.len();
+                query_substrates.push((row_index,query_substrate));
+            }
+        }
+
+        let conn = RecordingConnector { queries: RefCell::new(Vec::new()) };
+        let site_checks = check_sites(&conn,&query_substrates).unwrap();
+        assert_eq!(site_checks.len(),2 * entry_count);
+
+        let sequence_queries = conn.queries.borrow().iter().filter(|query_str| query_str.contains("FROM SEQUENCE")).count();
+        assert_eq!(sequence_queries,(entry_count + BATCH_QUERY_SIZE - 1) / BATCH_QUERY_SIZE);
+    }
+
+    #[test]
+    fn isoform_sequences_are_grouped_with_their_entry() {
+        let base_accessions = vec![String::from("Q15796"),String::from("P84022")];
+        let sequences = vec![sequence("Q15796"),sequence("Q15796-2"),sequence("P84022-3"),sequence("Q157961")];
+
+        let entry_sequences = group_entry_sequences(&base_accessions,sequences);
+
+        let ids = |accession: &str| -> Vec<String> {
+            return entry_sequences[accession].iter().map(|sequence| sequence.id.clone()).collect();
+        };
+        assert_eq!(ids("Q15796"),vec!["Q15796","Q15796-2"]);
+        assert_eq!(ids("P84022"),vec!["P84022-3"]);
+    }
+}